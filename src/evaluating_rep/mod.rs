@@ -1,2 +1,3 @@
 pub mod stats;
-pub mod optimise;
\ No newline at end of file
+pub mod optimise;
+pub mod scenarios;
\ No newline at end of file
@@ -285,7 +285,8 @@ pub fn test_sc_iterator() {
         user_default_reputation: vec![1.0; 15],
         user_organizations: vec![1; 15],
         organization_reputation_threshold: vec![1.0; 15],
-        organization_default_reputation: vec![1.0; 15]
+        organization_default_reputation: vec![1.0; 15],
+        ..Default::default()
     };
     let mut ind_var: IndependantVar<IndependantVarPart> = IndependantVar {
         sc: sc,
@@ -0,0 +1,136 @@
+use crate::witness_rep::{
+    simulation::{SimulationConfig, ReliabilityChange},
+    implementation::{
+        organization_policy::{PolicyConfig, AdmissionRule, KeyloadScope},
+        witness_abuse::{AbusiveWitness, WitnessAbuse}
+    }
+};
+
+// Adds an organization whose members are sybils: unreliable users the organization
+// certifies without question. The new organization has the index after the existing
+// ones, and its members are appended after the existing users.
+pub fn add_rogue_organization(
+    sc: &SimulationConfig,
+    num_sybils: usize,
+    sybil_reliability: f32
+) -> SimulationConfig {
+    let mut rogue = sc.clone();
+    let rogue_org = sc.user_organizations.iter().max().map(|m| m + 1).unwrap_or(0);
+
+    // the sybils behave like any other user, apart from their reliability
+    let user_threshold = sc.user_reputation_threshold.last().cloned().unwrap_or(0.0);
+    let user_default = sc.user_default_reputation.last().cloned().unwrap_or(0.5);
+    rogue.num_users += num_sybils;
+    rogue.reliability.extend(vec![sybil_reliability; num_sybils]);
+    rogue.user_reputation_threshold.extend(vec![user_threshold; num_sybils]);
+    rogue.user_default_reputation.extend(vec![user_default; num_sybils]);
+    rogue.user_organizations.extend(vec![rogue_org; num_sybils]);
+
+    // the rogue organization vouches for its members no matter what
+    rogue.organization_reputation_threshold.resize(rogue_org, 0.0);
+    rogue.organization_reputation_threshold.push(0.0);
+    rogue.organization_default_reputation.resize(rogue_org, user_default);
+    rogue.organization_default_reputation.push(1.0);
+    if let Some(policies) = rogue.organization_policy.as_mut() {
+        policies.push(PolicyConfig {
+            admission: AdmissionRule::Open,
+            keyload: KeyloadScope::Everyone,
+            review_threshold: 0.0
+        });
+    }
+
+    // the other organizations have no reason to distrust it beforehand
+    if let Some(matrix) = rogue.organization_trust.as_mut() {
+        for row in matrix.iter_mut() {
+            row.push(1.0);
+        }
+        matrix.push(vec![1.0; rogue_org + 1]);
+    }
+    if let Some(thresholds) = rogue.user_organization_threshold.as_mut() {
+        let threshold = thresholds.last().cloned().unwrap_or(0.0);
+        thresholds.extend(vec![threshold; num_sybils]);
+    }
+    if let Some(tsgs) = rogue.user_tsg.as_mut() {
+        let tsg = tsgs.last().cloned().unwrap();
+        tsgs.extend(vec![tsg; num_sybils]);
+    }
+    if let Some(models) = rogue.user_reputation_model.as_mut() {
+        let model = models.last().cloned().unwrap();
+        models.extend(vec![model; num_sybils]);
+    }
+    return rogue;
+}
+
+// The given users change their behaviour from the start of the run, e.g. users who
+// were honest for most of the simulation and then turn dishonest
+pub fn change_reliability_at(
+    sc: &SimulationConfig,
+    users: &Vec<usize>,
+    run: usize,
+    reliability: f32
+) -> SimulationConfig {
+    let mut changed = sc.clone();
+    let changes = changed.reliability_changes.get_or_insert(Vec::new());
+    for user in users.iter() {
+        changes.push(ReliabilityChange {
+            run: run,
+            user: *user,
+            reliability: reliability
+        });
+    }
+    return changed;
+}
+
+// The given users abuse the rules on witness statements whenever they are chosen as witnesses
+pub fn add_abusive_witnesses(
+    sc: &SimulationConfig,
+    users: &Vec<usize>,
+    abuse: WitnessAbuse
+) -> SimulationConfig {
+    let mut abusive = sc.clone();
+    let abusers = abusive.witness_abuse.get_or_insert(Vec::new());
+    for user in users.iter() {
+        abusers.push(AbusiveWitness {
+            user: *user,
+            abuse: abuse.clone()
+        });
+    }
+    return abusive;
+}
+
+#[test]
+pub fn test_add_rogue_organization() {
+    let sc = SimulationConfig {
+        node_url: String::from(""),
+        num_users: 4,
+        average_proximity: 1.0,
+        witness_floor: 1,
+        runs: 1,
+        reliability: vec![1.0; 4],
+        user_reputation_threshold: vec![0.1; 4],
+        user_default_reputation: vec![0.5; 4],
+        user_organizations: vec![0, 0, 1, 1],
+        organization_reputation_threshold: vec![0.1; 2],
+        organization_default_reputation: vec![0.5; 2],
+        organization_trust: Some(vec![vec![1.0; 2]; 2]),
+        user_organization_threshold: Some(vec![0.3; 4]),
+        ..Default::default()
+    };
+    let rogue = add_rogue_organization(&sc, 3, 0.1);
+    assert_eq!(rogue.num_users, 7);
+    assert_eq!(rogue.user_organizations, vec![0, 0, 1, 1, 2, 2, 2]);
+    assert_eq!(rogue.organization_default_reputation.len(), 3);
+    assert_eq!(rogue.user_organization_threshold.unwrap().len(), 7);
+    let matrix = rogue.organization_trust.unwrap();
+    assert!(matrix.len() == 3 && matrix.iter().all(|row| row.len() == 3));
+
+    let turned = change_reliability_at(&sc, &vec![0, 2], 90, 0.1);
+    let changes = turned.reliability_changes.unwrap();
+    assert_eq!(changes.len(), 2);
+    assert!(changes.iter().all(|change| change.run == 90 && change.reliability == 0.1));
+
+    let abusive = add_abusive_witnesses(&sc, &vec![1, 3], WitnessAbuse::CopyLate);
+    let abusers = abusive.witness_abuse.unwrap();
+    assert_eq!(abusers.iter().map(|a| a.user).collect::<Vec<usize>>(), vec![1, 3]);
+    assert!(abusers.iter().all(|a| a.abuse == WitnessAbuse::CopyLate));
+}
@@ -5,6 +5,7 @@ use rustlearn::{
     metrics::mean_squared_error
 };
 use std::collections::HashMap;
+use crate::witness_rep::utility::score_lines::get_line_info;
 
 pub type TrueReliability    = f32;
 pub type EstimReliabilities = Vec<f32>;
@@ -16,6 +17,11 @@ pub fn run_avg_mean_squared_error(rel_map: ReputationMap) -> Result<f32> {
     let mut true_rels: Vec<f32> = Vec::new();
     let mut estm_rels: Vec<f32> = Vec::new();
     for (_, (true_rel, est_rels)) in rel_map{
+        // nobody holds a reputation for the user, e.g. a user never chosen as a witness
+        if est_rels.len() == 0 {
+            continue;
+        }
+
         // insert the true reliability score
         true_rels.push(true_rel);
         
@@ -34,15 +40,21 @@ pub fn run_avg_mean_squared_error(rel_map: ReputationMap) -> Result<f32> {
 }
 
 pub fn read_reliabilities(dir_name: String, use_arg: bool) -> Result<ReputationMap> {
+    return read_estimates(dir_name, use_arg, "reputation_maps.txt");
+}
+
+// Pairs the true reliabilities with estimates written in the format of reputation_maps.txt,
+// e.g. the global reputations
+pub fn read_estimates(dir_name: String, use_arg: bool, estimates_file: &str) -> Result<ReputationMap> {
     // read the data from file
     let file_name_start: String;
     let file_name_end: String;
     if use_arg {
         file_name_start = format!("./runs/{}/start_reliability.txt", dir_name);
-        file_name_end = format!("./runs/{}/reputation_maps.txt", dir_name);
+        file_name_end = format!("./runs/{}/{}", dir_name, estimates_file);
     } else {
         file_name_start = format!("{}/start_reliability.txt", dir_name);
-        file_name_end = format!("{}/reputation_maps.txt", dir_name);
+        file_name_end = format!("{}/{}", dir_name, estimates_file);
     }
     println!("{}", file_name_start);
     let start_rels = fs::read_to_string(file_name_start)?;
@@ -92,12 +104,154 @@ pub fn read_reliabilities(dir_name: String, use_arg: bool) -> Result<ReputationM
     return Ok(rel_map);
 }
 
-pub fn get_line_info(line: &str) -> Option<(String, f32)> {
-    if line == "" {
-        return None;
+// Reads the organization reputations written after each run, as held by the users
+// outside of each organization
+pub fn read_organization_reputations(dir_name: String) -> Result<Vec<HashMap<String, f32>>> {
+    return read_per_run(dir_name, "organization_reputations.txt");
+}
+
+// Reads the reputations of the users whose reliability changed, after each run
+pub fn read_reliability_changes(dir_name: String) -> Result<Vec<HashMap<String, f32>>> {
+    return read_per_run(dir_name, "reliability_changes.txt");
+}
+
+// Reads a file holding a paragraph of reputations per run, each starting with the run number
+pub fn read_per_run(dir_name: String, file: &str) -> Result<Vec<HashMap<String, f32>>> {
+    let file_name = format!("{}/{}", dir_name, file);
+    let reps = fs::read_to_string(file_name)?;
+
+    let mut runs: Vec<HashMap<String, f32>> = Vec::new();
+    for paragraph in reps.split("\n\n") {
+        let mut lines = paragraph.split('\n');
+
+        // the first line is the run number
+        if let None = lines.next().filter(|line| line.starts_with("Run")) {
+            continue;
+        }
+        runs.push(lines.filter_map(get_line_info).collect());
+    }
+    return Ok(runs);
+}
+
+// The accuracy of the users' reputations for others as participants and as witnesses
+pub fn run_avg_role_mean_squared_errors(dir_name: String) -> Result<(f32, f32)> {
+    let participant_map = read_estimates(dir_name.clone(), false, "participant_reputations.txt")?;
+    let witness_map = read_estimates(dir_name, false, "witness_reputations.txt")?;
+    return Ok((
+        run_avg_mean_squared_error(participant_map)?,
+        run_avg_mean_squared_error(witness_map)?
+    ));
+}
+
+// The average confidence the users hold in their reputations, or None if the
+// users' model has no notion of confidence
+pub fn read_average_confidence(dir_name: String) -> Result<Option<f32>> {
+    let file_name = format!("{}/reputation_confidences.txt", dir_name);
+    let confidences: Vec<f32> = fs::read_to_string(file_name)?
+        .split("\n\n\n")
+        .flat_map(|paragraph| paragraph.split('\n').skip(1).filter_map(get_line_info).collect::<Vec<_>>())
+        .map(|(_, confidence)| confidence)
+        .collect();
+    if confidences.len() == 0 {
+        return Ok(None);
+    }
+    return Ok(Some(confidences.iter().sum::<f32>() / confidences.len() as f32));
+}
+
+// The messages the recommendation protocol cost per counterparty decision, and the
+// fraction of the decisions that were right
+pub fn read_recommendation_summary(dir_name: String) -> Result<(f32, f32)> {
+    let summary = read_summary(dir_name, "recommendations.txt")?;
+    let decisions = summary.get("decisions").cloned().unwrap_or(0.0).max(1.0);
+    let messages = summary.get("messages").cloned().unwrap_or(0.0);
+    let correct = summary.get("correct").cloned().unwrap_or(0.0);
+    return Ok((messages / decisions, correct / decisions));
+}
+
+// The average number of users and organizations who learned about each interaction
+pub fn read_average_observers(dir_name: String) -> Result<f32> {
+    let summary = read_summary(dir_name, "visibility.txt")?;
+    return Ok(summary.get("observers").cloned().unwrap_or(0.0));
+}
+
+// The average balance of the users of each reliability, keyed "reliability r"
+pub fn read_strategy_utilities(dir_name: String) -> Result<HashMap<String, f32>> {
+    return read_summary(dir_name, "strategy_utilities.txt");
+}
+
+// Reads a file of "name: value" lines
+pub fn read_summary(dir_name: String, file: &str) -> Result<HashMap<String, f32>> {
+    let file_name = format!("{}/{}", dir_name, file);
+    return Ok(fs::read_to_string(file_name)?
+        .split('\n')
+        .filter_map(get_line_info)
+        .collect());
+}
+
+// The fraction of the pairs of different users in which the first holds a reputation
+// for the second
+pub fn coverage(rel_map: &ReputationMap) -> f32 {
+    let users = rel_map.len() as f32;
+    if users < 2.0 {
+        return 0.0;
     }
-    let mut split = line.split(": ");
-    let str = split.next().unwrap();
-    let rel: f32 = split.next().unwrap().parse().unwrap();
-    return Some((String::from(str), rel));
-}
\ No newline at end of file
+    let estimates: usize = rel_map.values().map(|(_, est_rels)| est_rels.len()).sum();
+    return estimates as f32 / (users * (users - 1.0));
+}
+
+// The number of runs it took for the organization's reputation to fall below the threshold
+pub fn runs_until_discounted(
+    org_reps: &Vec<HashMap<String, f32>>,
+    org_pubkey: &str,
+    threshold: f32
+) -> Option<usize> {
+    return org_reps
+        .iter()
+        .position(|run| run.get(org_pubkey).map_or(false, |rep| *rep < threshold))
+        .map(|run| run + 1);
+}
+
+// The number of runs after change_run it took for the user's reputation to come
+// within tolerance of their new reliability
+pub fn runs_until_tracked(
+    reps: &Vec<HashMap<String, f32>>,
+    pubkey: &str,
+    change_run: usize,
+    reliability: f32,
+    tolerance: f32
+) -> Option<usize> {
+    return reps
+        .iter()
+        .skip(change_run)
+        .position(|run| run.get(pubkey).map_or(false, |rep| (rep - reliability).abs() <= tolerance))
+        .map(|run| run + 1);
+}
+
+#[test]
+pub fn test_runs_until_discounted() {
+    let org_reps: Vec<HashMap<String, f32>> = vec![0.5, 0.4, 0.2, 0.1]
+        .into_iter()
+        .map(|rep| vec![(String::from("org"), rep)].into_iter().collect())
+        .collect();
+    assert_eq!(runs_until_discounted(&org_reps, "org", 0.3), Some(3));
+    assert_eq!(runs_until_discounted(&org_reps, "org", 0.0), None);
+}
+
+#[test]
+pub fn test_runs_until_tracked() {
+    let reps: Vec<HashMap<String, f32>> = vec![0.9, 0.9, 0.7, 0.4, 0.2]
+        .into_iter()
+        .map(|rep| vec![(String::from("user"), rep)].into_iter().collect())
+        .collect();
+    assert_eq!(runs_until_tracked(&reps, "user", 2, 0.2, 0.1), Some(3));
+    assert_eq!(runs_until_tracked(&reps, "user", 2, 0.0, 0.1), None);
+}
+
+#[test]
+pub fn test_coverage() {
+    let mut rel_map = ReputationMap::new();
+    rel_map.insert(String::from("a"), (1.0, vec![0.9, 0.8]));
+    rel_map.insert(String::from("b"), (0.5, vec![0.5]));
+    rel_map.insert(String::from("c"), (0.1, Vec::new()));
+    assert_eq!(coverage(&rel_map), 0.5);
+}
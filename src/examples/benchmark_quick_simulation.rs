@@ -0,0 +1,99 @@
+use crate::witness_rep::{
+    self,
+    implementation::{
+        reputation_model::ReputationModelConfig,
+        visibility::VisibilityConfig,
+        witness_selection::WitnessSelectionConfig
+    }
+};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::time::Instant;
+
+// The populations and numbers of interactions the quick simulation is timed on, from
+// a quick check up to the scale it is meant to handle
+const BENCHMARKS: [(usize, usize); 4] = [
+    (100, 1_000),
+    (1_000, 10_000),
+    (10_000, 10_000),
+    (10_000, 100_000),
+];
+
+// A population of num_users across 10 organizations, with reliabilities spread over
+// [0.1,1]. The witnesses are chosen at random among a few eligible users, and each
+// interaction only reaches a fraction of the users, as it would at this scale.
+fn benchmark_config(url: &str, num_users: usize, runs: usize) -> witness_rep::simulation::SimulationConfig {
+    let num_orgs = 10;
+    return witness_rep::simulation::SimulationConfig {
+        node_url: String::from(url),
+        num_users: num_users,
+        average_proximity: 0.5,
+        witness_floor: 2,
+        runs: runs,
+        reliability: (0..num_users).map(|i| 0.1 + 0.1 * (i % 10) as f32).collect(),
+        user_reputation_threshold: vec![0.1; num_users],
+        user_default_reputation: vec![0.5; num_users],
+        user_organizations: (0..num_users).map(|i| i % num_orgs).collect(),
+        organization_reputation_threshold: vec![0.1; num_orgs],
+        organization_default_reputation: vec![0.5; num_orgs],
+        reputation_model: Some(ReputationModelConfig::Beta { prior_strength: 2.0, min_confidence: 0.0 }),
+        visibility: Some(VisibilityConfig::Proximity { proximity: 0.01 }),
+        witness_selection: Some(WitnessSelectionConfig::Random),
+        witness_ceiling: Some(3),
+        ..Default::default()
+    };
+}
+
+// Times the quick simulation on each of the benchmarks, and appends the times to
+// runs/benchmarks.txt so that they can be tracked from one version to the next
+pub async fn benchmark_quick_simulation(url: &str) -> Result<()> {
+    let time: DateTime<Utc> = Utc::now();
+    let mut output = format!("Benchmarks of {:?}\n", time);
+    for (num_users, runs) in BENCHMARKS.iter() {
+        let sc = benchmark_config(url, *num_users, *runs);
+        let start = Instant::now();
+        let (_, ran_fully) = witness_rep::quick_simulation::quick_simulation(sc, false).await?;
+        let seconds = start.elapsed().as_secs_f32();
+
+        let result = format!(
+            "users={} interactions={} seconds={} interactions_per_second={} ran_fully={}\n",
+            num_users, runs, seconds, *runs as f32 / seconds, ran_fully
+        );
+        print!("{}", result);
+        output.push_str(&result);
+    }
+    output.push_str("\n");
+
+    fs::create_dir_all("./runs")?;
+    let mut file = OpenOptions::new().create(true).append(true).open("./runs/benchmarks.txt")?;
+    file.write_all(output.as_bytes())?;
+    return Ok(());
+}
+
+// The quick simulation at the scale it is meant for: 10k users, with each interaction only
+// reaching the few users in range of it. Too slow for every cargo test, so it only runs when
+// asked for, and in release:
+//      cargo test --release -- --ignored test_quick_simulation_at_scale
+#[tokio::test]
+#[ignore]
+pub async fn test_quick_simulation_at_scale() {
+    use witness_rep::{engine::{self, EngineOptions}, implementation::ledger::Backend};
+
+    let (num_users, runs) = (10_000, 10_000);
+    let run_dir = std::env::temp_dir().join("quick_simulation_at_scale");
+    let _ = fs::remove_dir_all(&run_dir);
+    let mut options = EngineOptions::new(Backend::Quick, "quick_simulation_at_scale", false);
+    options.seed = Some(48);
+    options.run_dir = Some(String::from(run_dir.to_str().unwrap()));
+
+    let start = Instant::now();
+    let (_, ran_fully) = engine::run(&benchmark_config("http://localhost:14265", num_users, runs), &options).await.unwrap();
+    let seconds = start.elapsed().as_secs_f32();
+    println!(
+        "users={} interactions={} seconds={} interactions_per_second={} ran_fully={}",
+        num_users, runs, seconds, runs as f32 / seconds, ran_fully
+    );
+}
@@ -0,0 +1,77 @@
+use crate::witness_rep::utility::batch_verify::{self, PendingSignature, SignatureVerifier};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use identity::crypto::{KeyPair, Ed25519, Sign};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::time::Instant;
+
+// The numbers of signatures verified, and the number of signatures of an interaction
+// (participants and witnesses), which is how many verify_msg checks in a batch
+const BENCHMARKS: [usize; 3] = [1_000, 10_000, 50_000];
+const INTERACTION_SIGS: usize = 5;
+
+// Signatures of distinct messages by a handful of signers, like those of a channel
+fn signatures(count: usize) -> Result<Vec<PendingSignature>> {
+    let keypairs: Vec<KeyPair> = (0..10)
+        .map(|_| KeyPair::new_ed25519())
+        .collect::<std::result::Result<Vec<KeyPair>, _>>()?;
+    let mut sigs: Vec<PendingSignature> = Vec::new();
+    for i in 0..count {
+        let keypair = &keypairs[i % keypairs.len()];
+        let message = format!("benchmark_verification statement {}", i).into_bytes();
+        let signature: [u8; 64] = Ed25519::sign(&message, keypair.private())?;
+        sigs.push(PendingSignature {
+            key: keypair.public().as_ref().to_vec(),
+            message: message,
+            signature: signature.to_vec()
+        });
+    }
+    return Ok(sigs);
+}
+
+// Verifies the signatures an interaction at a time, returning the signatures verified per second
+fn time_batches(verifier: &mut SignatureVerifier, sigs: &Vec<PendingSignature>) -> f32 {
+    let start = Instant::now();
+    for interaction in sigs.chunks(INTERACTION_SIGS) {
+        let valid = verifier.verify_batch(&interaction.to_vec());
+        assert!(!valid.contains(&false));
+    }
+    return sigs.len() as f32 / start.elapsed().as_secs_f32();
+}
+
+// Times verifying signatures one by one, in batches of an interaction, and again in batches
+// once the cache holds them (as when a channel is audited after being read), and appends
+// the throughputs to runs/benchmarks.txt
+pub async fn benchmark_verification() -> Result<()> {
+    let time: DateTime<Utc> = Utc::now();
+    let mut output = format!("Verification benchmarks of {:?}\n", time);
+    let mut verifier = SignatureVerifier::with_capacity(*BENCHMARKS.iter().max().unwrap());
+    for count in BENCHMARKS.iter() {
+        let sigs = signatures(*count)?;
+
+        let start = Instant::now();
+        for sig in sigs.iter() {
+            assert!(batch_verify::verify_one(sig));
+        }
+        let one_by_one = sigs.len() as f32 / start.elapsed().as_secs_f32();
+
+        verifier.clear_cache();
+        let batched = time_batches(&mut verifier, &sigs);
+        let cached = time_batches(&mut verifier, &sigs);
+
+        let result = format!(
+            "signatures={} one_by_one_per_second={} batched_per_second={} cached_per_second={}\n",
+            count, one_by_one, batched, cached
+        );
+        print!("{}", result);
+        output.push_str(&result);
+    }
+    output.push_str(&format!("{:?}\n\n", verifier.stats));
+
+    fs::create_dir_all("./runs")?;
+    let mut file = OpenOptions::new().create(true).append(true).open("./runs/benchmarks.txt")?;
+    file.write_all(output.as_bytes())?;
+    return Ok(());
+}
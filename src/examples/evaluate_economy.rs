@@ -0,0 +1,52 @@
+use crate::{
+    witness_rep::{
+        self,
+        implementation::economy::EconomyConfig
+    },
+    evaluating_rep
+};
+
+use anyhow::Result;
+
+// Compares the balances of honest and dishonest users when nobody is turned down
+// for their reputation with those when users are, to check that the reputation system
+// makes cheating unprofitable
+pub async fn evaluate_economy(url: &str) -> Result<()> {
+    let mut results: Vec<(f32, Vec<(String, f32)>, bool)> = Vec::new();
+    for threshold in vec![0.0, 0.5] {
+        let sc = witness_rep::simulation::SimulationConfig {
+            node_url: String::from(url),
+            num_users: 15,
+            average_proximity: 0.5,
+            witness_floor: 2,
+            runs: 200,
+            reliability: vec![1.0, 1.0, 0.2, 0.6, 0.6, 1.0, 1.0, 0.2, 0.2, 0.6, 1.0, 0.6, 0.2, 0.6, 1.0],
+            user_reputation_threshold: vec![threshold; 15],
+            user_default_reputation: vec![0.5; 15],
+            user_organizations: vec![0,0,0,0,0,1,1,1,1,1,2,2,2,2,2],
+            organization_reputation_threshold: vec![threshold; 3],
+            organization_default_reputation: vec![0.5; 3],
+            economy: Some(EconomyConfig {
+                contract_value: 1.0,
+                cheating_gain: 1.0,
+                witness_fee: 0.05
+            }),
+            ..Default::default()
+        };
+        let (dir_name, ran_fully) = witness_rep::quick_simulation::quick_simulation(sc, false).await?;
+
+        let mut utilities: Vec<(String, f32)> = evaluating_rep::stats::read_strategy_utilities(dir_name)?
+            .into_iter()
+            .collect();
+        utilities.sort_by(|a, b| a.0.cmp(&b.0));
+        results.push((threshold, utilities, ran_fully));
+    }
+
+    for (threshold, utilities, ran_fully) in results {
+        println!("user_reputation_threshold={} ran_fully={}", threshold, ran_fully);
+        for (strategy, utility) in utilities {
+            println!("-- {}: average balance={}", strategy, utility);
+        }
+    }
+    return Ok(());
+}
@@ -0,0 +1,65 @@
+use crate::{
+    witness_rep::{
+        self,
+        implementation::faults::FaultConfig
+    },
+    evaluating_rep
+};
+
+use anyhow::Result;
+
+// Compares the accuracy of the reputation maps as more of the messages of the interactions
+// are lost or late, witnesses go offline and participants crash
+pub async fn evaluate_faults(url: &str) -> Result<()> {
+    let setups: Vec<Option<FaultConfig>> = vec![
+        None,
+        Some(FaultConfig { drop_packet: 0.1, delay_packet: 0.1, ..FaultConfig::default() }),
+        Some(FaultConfig { witness_offline: 0.3, participant_crash: 0.1, ..FaultConfig::default() }),
+        Some(FaultConfig { keyload_failure: 0.2, ..FaultConfig::default() }),
+        Some(FaultConfig {
+            drop_packet: 0.2,
+            delay_packet: 0.2,
+            witness_offline: 0.3,
+            keyload_failure: 0.1,
+            participant_crash: 0.1,
+            node_error: 0.0,
+            ..FaultConfig::default()
+        }),
+    ];
+
+    let mut results: Vec<(Option<FaultConfig>, f32, f32, bool)> = Vec::new();
+    for faults in setups {
+        let sc = witness_rep::simulation::SimulationConfig {
+            node_url: String::from(url),
+            num_users: 15,
+            average_proximity: 0.5,
+            witness_floor: 2,
+            runs: 100,
+            reliability: vec![1.0, 1.0, 0.4, 0.7, 0.6, 0.8, 0.9, 0.7, 0.3, 0.6, 1.0, 0.7, 0.4, 0.5, 1.0],
+            user_reputation_threshold: vec![0.1; 15],
+            user_default_reputation: vec![0.5; 15],
+            user_organizations: vec![0,0,0,0,0,1,1,1,1,1,2,2,2,2,2],
+            organization_reputation_threshold: vec![0.1; 3],
+            organization_default_reputation: vec![0.5; 3],
+            faults: faults.clone(),
+            ..Default::default()
+        };
+        let (dir_name, ran_fully) = witness_rep::quick_simulation::quick_simulation(sc, false).await?;
+
+        let abandoned = match faults {
+            None    => 0.0,
+            Some(_) => evaluating_rep::stats::read_summary(dir_name.clone(), "faults.txt")?
+                .get("abandoned")
+                .cloned()
+                .unwrap_or(0.0)
+        };
+        let rel_map = evaluating_rep::stats::read_reliabilities(dir_name, false)?;
+        let mse = evaluating_rep::stats::run_avg_mean_squared_error(rel_map)?;
+        results.push((faults, mse, abandoned, ran_fully));
+    }
+
+    for (faults, mse, abandoned, ran_fully) in results {
+        println!("{:?}: mse={} abandoned={} ran_fully={}", faults, mse, abandoned, ran_fully);
+    }
+    return Ok(());
+}
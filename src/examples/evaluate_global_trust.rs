@@ -0,0 +1,59 @@
+use crate::{
+    witness_rep::{
+        self,
+        implementation::global_trust::{GlobalTrustConfig, PreTrusted}
+    },
+    evaluating_rep
+};
+
+use anyhow::Result;
+
+// Compares the accuracy of the users' local reputation maps with that of the global
+// reputations, in a population where a rogue organization certifies sybils
+pub async fn evaluate_global_trust(url: &str) -> Result<()> {
+    let configs = vec![
+        (PreTrusted::Everyone, false),
+        (PreTrusted::OrganizationMembers, false),
+        (PreTrusted::OrganizationMembers, true),
+    ];
+
+    let mut results: Vec<(String, f32, f32, bool)> = Vec::new();
+    for (pre_trusted, use_for_checks) in configs {
+        let global_trust = GlobalTrustConfig {
+            interval: 10,
+            pre_trusted: pre_trusted.clone(),
+            pre_trust_weight: 0.2,
+            max_iterations: 50,
+            tolerance: 0.0001,
+            use_for_checks: use_for_checks
+        };
+        let sc = witness_rep::simulation::SimulationConfig {
+            node_url: String::from(url),
+            num_users: 10,
+            average_proximity: 0.5,
+            witness_floor: 2,
+            runs: 100,
+            reliability: vec![1.0, 0.9, 0.8, 0.9, 0.7, 0.8, 1.0, 0.9, 0.6, 0.8],
+            user_reputation_threshold: vec![0.3; 10],
+            user_default_reputation: vec![0.5; 10],
+            user_organizations: vec![0,0,0,0,0,1,1,1,1,1],
+            organization_reputation_threshold: vec![0.1; 2],
+            organization_default_reputation: vec![0.5; 2],
+            global_trust: Some(global_trust),
+            ..Default::default()
+        };
+        let sc = evaluating_rep::scenarios::add_rogue_organization(&sc, 5, 0.1);
+        let (dir_name, ran_fully) = witness_rep::quick_simulation::quick_simulation(sc, false).await?;
+
+        let rel_map = evaluating_rep::stats::read_reliabilities(dir_name.clone(), false)?;
+        let local_mse = evaluating_rep::stats::run_avg_mean_squared_error(rel_map)?;
+        let rel_map = evaluating_rep::stats::read_estimates(dir_name, false, "global_reputations.txt")?;
+        let global_mse = evaluating_rep::stats::run_avg_mean_squared_error(rel_map)?;
+        results.push((format!("{:?} use_for_checks={}", pre_trusted, use_for_checks), local_mse, global_mse, ran_fully));
+    }
+
+    for (config, local_mse, global_mse, ran_fully) in results {
+        println!("{}: local mse={} global mse={} ran_fully={}", config, local_mse, global_mse, ran_fully);
+    }
+    return Ok(());
+}
@@ -17,7 +17,8 @@ pub async fn evaluate_num_users_var(url: &str) -> Result<()> {
         user_default_reputation: vec![0.5; 4],
         user_organizations: vec![0,1,2,3],
         organization_reputation_threshold: vec![0.1; 4],
-        organization_default_reputation: vec![0.5; 4]
+        organization_default_reputation: vec![0.5; 4],
+        ..Default::default()
     };
 
     let mut ind_var_0: IndependantVar<IndependantVarApp> = IndependantVar {
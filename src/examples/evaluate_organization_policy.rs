@@ -0,0 +1,54 @@
+use crate::{
+    witness_rep::{
+        self,
+        implementation::organization_policy::{PolicyConfig, AdmissionRule, KeyloadScope}
+    },
+    evaluating_rep
+};
+
+use anyhow::Result;
+
+// Runs the same population under increasingly strict organizations, to compare
+// how organization strictness affects the accuracy of the reputation maps
+pub async fn evaluate_organization_policy(url: &str) -> Result<()> {
+    let admission_rules = vec![
+        AdmissionRule::Open,
+        AdmissionRule::Average,
+        AdmissionRule::Minimum,
+        AdmissionRule::PerRole { participant_threshold: 0.3, witness_threshold: 0.6 },
+    ];
+
+    let mut results: Vec<(String, f32, bool)> = Vec::new();
+    for rule in admission_rules {
+        let policy = PolicyConfig {
+            admission: rule.clone(),
+            keyload: KeyloadScope::Everyone,
+            review_threshold: 0.3
+        };
+        let sc = witness_rep::simulation::SimulationConfig {
+            node_url: String::from(url),
+            num_users: 15,
+            average_proximity: 0.5,
+            witness_floor: 2,
+            runs: 100,
+            reliability: vec![1.0, 1.0, 0.4, 0.7, 0.6, 0.8, 0.9, 0.7, 0.3, 0.6, 1.0, 0.7, 0.4, 0.5, 1.0],
+            user_reputation_threshold: vec![0.1; 15],
+            user_default_reputation: vec![0.5; 15],
+            user_organizations: vec![0,0,0,0,0,1,1,1,1,1,2,2,2,2,2],
+            organization_reputation_threshold: vec![0.4; 3],
+            organization_default_reputation: vec![0.5; 3],
+            organization_policy: Some(vec![policy; 3]),
+            ..Default::default()
+        };
+        let (dir_name, ran_fully) = witness_rep::quick_simulation::quick_simulation(sc, false).await?;
+
+        let rel_map = evaluating_rep::stats::read_reliabilities(dir_name, false)?;
+        let mse = evaluating_rep::stats::run_avg_mean_squared_error(rel_map)?;
+        results.push((format!("{:?}", rule), mse, ran_fully));
+    }
+
+    for (rule, mse, ran_fully) in results {
+        println!("{}: mse={} ran_fully={}", rule, mse, ran_fully);
+    }
+    return Ok(());
+}
@@ -0,0 +1,53 @@
+use crate::{
+    witness_rep::{
+        self,
+        implementation::recommendation::RecommendationConfig
+    },
+    evaluating_rep
+};
+
+use anyhow::Result;
+
+// Compares how often users choose their counterparties right, and what it costs them in
+// messages, when they decide alone, when they ask nearby users for recommendations, and
+// when some of those users lie
+pub async fn evaluate_recommendation(url: &str) -> Result<()> {
+    let setups: Vec<(&str, RecommendationConfig)> = vec![
+        ("alone", RecommendationConfig { recommenders: 0, weight: 0.0, liars: Vec::new() }),
+        ("honest", RecommendationConfig { recommenders: 3, weight: 0.5, liars: Vec::new() }),
+        ("liars", RecommendationConfig { recommenders: 3, weight: 0.5, liars: vec![0, 3, 6, 9, 12] }),
+    ];
+
+    let mut results: Vec<(&str, f32, f32, f32, bool)> = Vec::new();
+    for (name, recommendation) in setups {
+        let sc = witness_rep::simulation::SimulationConfig {
+            node_url: String::from(url),
+            num_users: 15,
+            average_proximity: 0.5,
+            witness_floor: 2,
+            runs: 100,
+            reliability: vec![1.0, 1.0, 0.2, 0.7, 0.6, 0.8, 0.9, 0.1, 0.3, 0.6, 1.0, 0.7, 0.2, 0.5, 1.0],
+            user_reputation_threshold: vec![0.5; 15],
+            user_default_reputation: vec![0.5; 15],
+            user_organizations: vec![0,0,0,0,0,1,1,1,1,1,2,2,2,2,2],
+            organization_reputation_threshold: vec![0.4; 3],
+            organization_default_reputation: vec![0.5; 3],
+            recommendation: Some(recommendation),
+            ..Default::default()
+        };
+        let (dir_name, ran_fully) = witness_rep::quick_simulation::quick_simulation(sc, false).await?;
+
+        let (messages, accuracy) = evaluating_rep::stats::read_recommendation_summary(dir_name.clone())?;
+        let rel_map = evaluating_rep::stats::read_reliabilities(dir_name, false)?;
+        let mse = evaluating_rep::stats::run_avg_mean_squared_error(rel_map)?;
+        results.push((name, accuracy, messages, mse, ran_fully));
+    }
+
+    for (name, accuracy, messages, mse, ran_fully) in results {
+        println!(
+            "{}: decision accuracy={} messages per decision={} mse={} ran_fully={}",
+            name, accuracy, messages, mse, ran_fully
+        );
+    }
+    return Ok(());
+}
@@ -0,0 +1,61 @@
+use crate::{
+    witness_rep::{
+        self,
+        implementation::reputation_model::{ReputationModelConfig, Decay}
+    },
+    evaluating_rep
+};
+
+use anyhow::Result;
+
+// Two users are honest for most of the simulation and then turn dishonest. Compares how
+// many runs it takes the other users to notice, with each way of forgetting old verdicts.
+pub async fn evaluate_reputation_decay(url: &str) -> Result<()> {
+    let change_run = 60;
+    let new_reliability = 0.1;
+    let models = vec![
+        ReputationModelConfig::Average,
+        ReputationModelConfig::Decayed { decay: Decay::Exponential { weight: 0.2 } },
+        ReputationModelConfig::Decayed { decay: Decay::SlidingWindow { updates: 40 } },
+        ReputationModelConfig::Decayed { decay: Decay::CappedMemory { verdicts: 10 } },
+    ];
+
+    let mut results: Vec<(String, Vec<Option<usize>>, f32)> = Vec::new();
+    for model in models {
+        let sc = witness_rep::simulation::SimulationConfig {
+            node_url: String::from(url),
+            num_users: 10,
+            average_proximity: 0.5,
+            witness_floor: 2,
+            runs: 100,
+            reliability: vec![1.0, 0.9, 0.8, 0.9, 0.7, 0.8, 1.0, 0.9, 0.6, 0.8],
+            user_reputation_threshold: vec![0.1; 10],
+            user_default_reputation: vec![0.5; 10],
+            user_organizations: vec![0,0,0,0,0,1,1,1,1,1],
+            organization_reputation_threshold: vec![0.1; 2],
+            organization_default_reputation: vec![0.5; 2],
+            user_reputation_model: Some(vec![model.clone(); 10]),
+            ..Default::default()
+        };
+        let sc = evaluating_rep::scenarios::change_reliability_at(&sc, &vec![0, 6], change_run, new_reliability);
+        let (dir_name, _) = witness_rep::quick_simulation::quick_simulation(sc, false).await?;
+
+        let reps = evaluating_rep::stats::read_reliability_changes(dir_name.clone())?;
+        let tracked: Vec<Option<usize>> = match reps.last() {
+            Some(last_run) => last_run
+                .keys()
+                .map(|pk| evaluating_rep::stats::runs_until_tracked(&reps, pk, change_run, new_reliability, 0.2))
+                .collect(),
+            None => Vec::new()
+        };
+        let rel_map = evaluating_rep::stats::read_reliabilities(dir_name, false)?;
+        let mse = evaluating_rep::stats::run_avg_mean_squared_error(rel_map)?;
+        results.push((format!("{:?}", model), tracked, mse));
+    }
+
+    // the mse is against the reliabilities before the change
+    for (model, tracked, mse) in results {
+        println!("{}: runs until tracked={:?} mse={}", model, tracked, mse);
+    }
+    return Ok(());
+}
@@ -0,0 +1,50 @@
+use crate::{
+    witness_rep::{
+        self,
+        implementation::reputation_model::ReputationModelConfig
+    },
+    evaluating_rep
+};
+
+use anyhow::Result;
+
+// Runs the same population with each reputation model, to compare the accuracy
+// of the reputation maps and how confident the users are in them
+pub async fn evaluate_reputation_model(url: &str) -> Result<()> {
+    let models = vec![
+        ReputationModelConfig::Average,
+        ReputationModelConfig::Beta { prior_strength: 2.0, min_confidence: 0.0 },
+        ReputationModelConfig::Beta { prior_strength: 2.0, min_confidence: 0.3 },
+        ReputationModelConfig::Beta { prior_strength: 10.0, min_confidence: 0.0 },
+    ];
+
+    let mut results: Vec<(String, f32, Option<f32>, bool)> = Vec::new();
+    for model in models {
+        let sc = witness_rep::simulation::SimulationConfig {
+            node_url: String::from(url),
+            num_users: 15,
+            average_proximity: 0.5,
+            witness_floor: 2,
+            runs: 100,
+            reliability: vec![1.0, 1.0, 0.4, 0.7, 0.6, 0.8, 0.9, 0.7, 0.3, 0.6, 1.0, 0.7, 0.4, 0.5, 1.0],
+            user_reputation_threshold: vec![0.1; 15],
+            user_default_reputation: vec![0.5; 15],
+            user_organizations: vec![0,0,0,0,0,1,1,1,1,1,2,2,2,2,2],
+            organization_reputation_threshold: vec![0.4; 3],
+            organization_default_reputation: vec![0.5; 3],
+            reputation_model: Some(model.clone()),
+            ..Default::default()
+        };
+        let (dir_name, ran_fully) = witness_rep::quick_simulation::quick_simulation(sc, false).await?;
+
+        let confidence = evaluating_rep::stats::read_average_confidence(dir_name.clone())?;
+        let rel_map = evaluating_rep::stats::read_reliabilities(dir_name, false)?;
+        let mse = evaluating_rep::stats::run_avg_mean_squared_error(rel_map)?;
+        results.push((format!("{:?}", model), mse, confidence, ran_fully));
+    }
+
+    for (model, mse, confidence, ran_fully) in results {
+        println!("{}: mse={} confidence={:?} ran_fully={}", model, mse, confidence, ran_fully);
+    }
+    return Ok(());
+}
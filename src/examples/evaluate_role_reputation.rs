@@ -0,0 +1,46 @@
+use crate::{
+    witness_rep,
+    evaluating_rep
+};
+
+use anyhow::Result;
+
+// Compares the accuracy of the combined reputations with that of the separate
+// reputations for users as participants and as witnesses
+pub async fn evaluate_role_reputation(url: &str) -> Result<()> {
+    let mut results: Vec<(bool, f32, Option<(f32, f32)>, bool)> = Vec::new();
+    for role_reputation in vec![false, true] {
+        let sc = witness_rep::simulation::SimulationConfig {
+            node_url: String::from(url),
+            num_users: 15,
+            average_proximity: 0.5,
+            witness_floor: 2,
+            runs: 100,
+            reliability: vec![1.0, 1.0, 0.4, 0.7, 0.6, 0.8, 0.9, 0.7, 0.3, 0.6, 1.0, 0.7, 0.4, 0.5, 1.0],
+            user_reputation_threshold: vec![0.3; 15],
+            user_default_reputation: vec![0.5; 15],
+            user_organizations: vec![0,0,0,0,0,1,1,1,1,1,2,2,2,2,2],
+            organization_reputation_threshold: vec![0.4; 3],
+            organization_default_reputation: vec![0.5; 3],
+            role_reputation: role_reputation,
+            ..Default::default()
+        };
+        let (dir_name, ran_fully) = witness_rep::quick_simulation::quick_simulation(sc, false).await?;
+
+        let role_mses = match role_reputation {
+            true  => Some(evaluating_rep::stats::run_avg_role_mean_squared_errors(dir_name.clone())?),
+            false => None
+        };
+        let rel_map = evaluating_rep::stats::read_reliabilities(dir_name, false)?;
+        let mse = evaluating_rep::stats::run_avg_mean_squared_error(rel_map)?;
+        results.push((role_reputation, mse, role_mses, ran_fully));
+    }
+
+    for (role_reputation, mse, role_mses, ran_fully) in results {
+        println!(
+            "role_reputation={}: mse={} (participant, witness) mse={:?} ran_fully={}",
+            role_reputation, mse, role_mses, ran_fully
+        );
+    }
+    return Ok(());
+}
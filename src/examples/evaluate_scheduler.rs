@@ -0,0 +1,57 @@
+use crate::{
+    witness_rep::{
+        self,
+        implementation::scheduler::SchedulerConfig
+    },
+    evaluating_rep
+};
+
+use anyhow::Result;
+
+// Compares the accuracy of the reputation maps as the messages of the interactions take
+// longer to reach the users, so that the users act on staler reputations, and as more
+// interactions overlap
+pub async fn evaluate_scheduler(url: &str) -> Result<()> {
+    let timings: Vec<SchedulerConfig> = vec![
+        SchedulerConfig { mean_interarrival: 100, duration: 10, max_statement_delay: 5, max_propagation_delay: 0 },
+        SchedulerConfig { mean_interarrival: 100, duration: 10, max_statement_delay: 5, max_propagation_delay: 500 },
+        SchedulerConfig { mean_interarrival: 5, duration: 10, max_statement_delay: 5, max_propagation_delay: 50 },
+        SchedulerConfig { mean_interarrival: 5, duration: 10, max_statement_delay: 20, max_propagation_delay: 50 },
+    ];
+
+    let mut results: Vec<(SchedulerConfig, f32, f32, f32, bool)> = Vec::new();
+    for timing in timings {
+        let sc = witness_rep::simulation::SimulationConfig {
+            node_url: String::from(url),
+            num_users: 15,
+            average_proximity: 0.5,
+            witness_floor: 2,
+            runs: 100,
+            reliability: vec![1.0, 1.0, 0.4, 0.7, 0.6, 0.8, 0.9, 0.7, 0.3, 0.6, 1.0, 0.7, 0.4, 0.5, 1.0],
+            user_reputation_threshold: vec![0.1; 15],
+            user_default_reputation: vec![0.5; 15],
+            user_organizations: vec![0,0,0,0,0,1,1,1,1,1,2,2,2,2,2],
+            organization_reputation_threshold: vec![0.1; 3],
+            organization_default_reputation: vec![0.5; 3],
+            witness_ceiling: Some(3),
+            scheduler: Some(timing.clone()),
+            ..Default::default()
+        };
+        let (dir_name, ran_fully) = witness_rep::event_simulation::event_simulation(sc, false).await?;
+
+        let schedule = evaluating_rep::stats::read_summary(dir_name.clone(), "schedule.txt")?;
+        let late = schedule.get("late_statements").cloned().unwrap_or(0.0);
+        let concurrent = schedule.get("max_concurrent").cloned().unwrap_or(0.0);
+        let rel_map = evaluating_rep::stats::read_reliabilities(dir_name, false)?;
+        let mse = evaluating_rep::stats::run_avg_mean_squared_error(rel_map)?;
+        results.push((timing, mse, late, concurrent, ran_fully));
+    }
+
+    for (timing, mse, late, concurrent, ran_fully) in results {
+        println!(
+            "{:?}: mse={} late_statements={} max_concurrent={} ran_fully={}",
+            timing, mse, late, concurrent, ran_fully
+        );
+    }
+    return Ok(());
+}
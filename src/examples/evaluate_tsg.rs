@@ -0,0 +1,60 @@
+use crate::{
+    witness_rep::{
+        self,
+        trust_score_generators::user_tsg::TsgConfig
+    },
+    evaluating_rep
+};
+
+use anyhow::Result;
+
+// Runs the same population with each TSG, and with a mixed population where the
+// users of each organization use a different TSG, to compare the accuracy of the
+// reputation maps
+pub async fn evaluate_tsg(url: &str) -> Result<()> {
+    let tsgs = vec![
+        TsgConfig::Organization,
+        TsgConfig::MajorityVote,
+        TsgConfig::ReputationWeighted,
+        TsgConfig::FirstHandAware,
+        TsgConfig::OrganizationWeighted { own_org_weight: 0.7 },
+    ];
+    let mut populations: Vec<(String, Vec<TsgConfig>)> = tsgs
+        .iter()
+        .map(|tsg| (format!("{:?}", tsg), vec![tsg.clone(); 15]))
+        .collect();
+    let mut mixed: Vec<TsgConfig> = Vec::new();
+    for tsg in vec![TsgConfig::Organization, TsgConfig::ReputationWeighted, TsgConfig::FirstHandAware] {
+        mixed.extend(vec![tsg; 5]);
+    }
+    populations.push((String::from("Mixed"), mixed));
+
+    let mut results: Vec<(String, f32, bool)> = Vec::new();
+    for (name, user_tsg) in populations {
+        let sc = witness_rep::simulation::SimulationConfig {
+            node_url: String::from(url),
+            num_users: 15,
+            average_proximity: 0.5,
+            witness_floor: 2,
+            runs: 100,
+            reliability: vec![1.0, 1.0, 0.4, 0.7, 0.6, 0.8, 0.9, 0.7, 0.3, 0.6, 1.0, 0.7, 0.4, 0.5, 1.0],
+            user_reputation_threshold: vec![0.1; 15],
+            user_default_reputation: vec![0.5; 15],
+            user_organizations: vec![0,0,0,0,0,1,1,1,1,1,2,2,2,2,2],
+            organization_reputation_threshold: vec![0.4; 3],
+            organization_default_reputation: vec![0.5; 3],
+            user_tsg: Some(user_tsg),
+            ..Default::default()
+        };
+        let (dir_name, ran_fully) = witness_rep::quick_simulation::quick_simulation(sc, false).await?;
+
+        let rel_map = evaluating_rep::stats::read_reliabilities(dir_name, false)?;
+        let mse = evaluating_rep::stats::run_avg_mean_squared_error(rel_map)?;
+        results.push((name, mse, ran_fully));
+    }
+
+    for (name, mse, ran_fully) in results {
+        println!("{}: mse={} ran_fully={}", name, mse, ran_fully);
+    }
+    return Ok(());
+}
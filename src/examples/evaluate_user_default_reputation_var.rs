@@ -17,7 +17,8 @@ pub async fn evaluate_user_default_reputation_var(url: &str) -> Result<()> {
         user_default_reputation: vec![0.5; 15],
         user_organizations: vec![0,0,0,0,0,1,1,1,1,1,2,2,2,2,2],
         organization_reputation_threshold: vec![0.1; 15],
-        organization_default_reputation: vec![0.5; 15]
+        organization_default_reputation: vec![0.5; 15],
+        ..Default::default()
     };
 
     let mut ind_var_0: IndependantVar<IndependantVarPart> = IndependantVar {
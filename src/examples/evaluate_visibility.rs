@@ -0,0 +1,69 @@
+use crate::{
+    witness_rep::{
+        self,
+        implementation::{
+            organization_policy::{PolicyConfig, AdmissionRule, KeyloadScope},
+            visibility::VisibilityConfig
+        }
+    },
+    evaluating_rep
+};
+
+use anyhow::Result;
+
+// Compares the accuracy of the reputation maps when everyone learns about every
+// interaction with that under the partial visibility models, and with keyloads that
+// only reach those involved in the interaction
+pub async fn evaluate_visibility(url: &str) -> Result<()> {
+    let setups: Vec<(Option<VisibilityConfig>, KeyloadScope)> = vec![
+        (None, KeyloadScope::Everyone),
+        (Some(VisibilityConfig::HostingOrganization), KeyloadScope::Everyone),
+        (Some(VisibilityConfig::Proximity { proximity: 0.3 }), KeyloadScope::Everyone),
+        (Some(VisibilityConfig::SocialDistance { hops: 1 }), KeyloadScope::Everyone),
+        (Some(VisibilityConfig::HostingOrganization), KeyloadScope::InteractionOnly),
+        (Some(VisibilityConfig::InteractionOnly), KeyloadScope::Everyone),
+    ];
+
+    let mut results: Vec<(String, f32, f32, Option<f32>, bool)> = Vec::new();
+    for (visibility, keyload) in setups {
+        let policy = PolicyConfig {
+            admission: AdmissionRule::Average,
+            keyload: keyload.clone(),
+            review_threshold: 0.3
+        };
+        let sc = witness_rep::simulation::SimulationConfig {
+            node_url: String::from(url),
+            num_users: 15,
+            average_proximity: 0.5,
+            witness_floor: 2,
+            runs: 100,
+            reliability: vec![1.0, 1.0, 0.4, 0.7, 0.6, 0.8, 0.9, 0.7, 0.3, 0.6, 1.0, 0.7, 0.4, 0.5, 1.0],
+            user_reputation_threshold: vec![0.1; 15],
+            user_default_reputation: vec![0.5; 15],
+            user_organizations: vec![0,0,0,0,0,1,1,1,1,1,2,2,2,2,2],
+            organization_reputation_threshold: vec![0.4; 3],
+            organization_default_reputation: vec![0.5; 3],
+            organization_policy: Some(vec![policy; 3]),
+            visibility: visibility.clone(),
+            ..Default::default()
+        };
+        let (dir_name, ran_fully) = witness_rep::quick_simulation::quick_simulation(sc, false).await?;
+
+        let observers = match visibility {
+            None    => None,
+            Some(_) => Some(evaluating_rep::stats::read_average_observers(dir_name.clone())?)
+        };
+        let rel_map = evaluating_rep::stats::read_reliabilities(dir_name, false)?;
+        let coverage = evaluating_rep::stats::coverage(&rel_map);
+        let mse = evaluating_rep::stats::run_avg_mean_squared_error(rel_map)?;
+        results.push((format!("{:?} ({:?} keyload)", visibility, keyload), mse, coverage, observers, ran_fully));
+    }
+
+    for (setup, mse, coverage, observers, ran_fully) in results {
+        println!(
+            "{}: mse={} coverage={} observers per interaction={:?} ran_fully={}",
+            setup, mse, coverage, observers, ran_fully
+        );
+    }
+    return Ok(());
+}
@@ -0,0 +1,69 @@
+use crate::{
+    witness_rep::{
+        self,
+        implementation::witness_abuse::WitnessAbuse
+    },
+    evaluating_rep
+};
+
+use anyhow::Result;
+
+// Compares the reputation the abusive witnesses end up with against that of the other
+// users, for each way of abusing the rules on witness statements. The abusers are as
+// reliable as everyone else, so any difference is down to the abuse.
+pub async fn evaluate_witness_abuse(url: &str) -> Result<()> {
+    let abusers: Vec<usize> = vec![0, 5, 10];
+    let abuses: Vec<Option<WitnessAbuse>> = vec![
+        None,
+        Some(WitnessAbuse::Withhold),
+        Some(WitnessAbuse::CopyLate),
+        Some(WitnessAbuse::Equivocate),
+        Some(WitnessAbuse::Repeat),
+    ];
+
+    let mut results: Vec<(Option<WitnessAbuse>, f32, f32, bool)> = Vec::new();
+    for abuse in abuses {
+        let sc = witness_rep::simulation::SimulationConfig {
+            node_url: String::from(url),
+            num_users: 15,
+            average_proximity: 0.5,
+            witness_floor: 2,
+            runs: 100,
+            reliability: vec![0.8; 15],
+            user_reputation_threshold: vec![0.1; 15],
+            user_default_reputation: vec![0.5; 15],
+            user_organizations: vec![0,0,0,0,0,1,1,1,1,1,2,2,2,2,2],
+            organization_reputation_threshold: vec![0.1; 3],
+            organization_default_reputation: vec![0.5; 3],
+            ..Default::default()
+        };
+        let sc = match &abuse {
+            None    => sc,
+            Some(a) => evaluating_rep::scenarios::add_abusive_witnesses(&sc, &abusers, a.clone())
+        };
+        let (dir_name, ran_fully) = witness_rep::quick_simulation::quick_simulation(sc, false).await?;
+
+        // the average reputation held for the abusers, and for everyone else
+        let user_pks = witness_rep::simulation::read_user_pubkeys(&dir_name)?;
+        let rel_map = evaluating_rep::stats::read_reliabilities(dir_name, false)?;
+        let average = |abusive: bool| -> f32 {
+            let estimates: Vec<f32> = user_pks
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| abusers.contains(i) == abusive)
+                .filter_map(|(_, pk)| rel_map.get(pk))
+                .flat_map(|(_, est_rels)| est_rels.iter().cloned())
+                .collect();
+            if estimates.len() == 0 {
+                return 0.0;
+            }
+            return estimates.iter().sum::<f32>() / estimates.len() as f32;
+        };
+        results.push((abuse, average(true), average(false), ran_fully));
+    }
+
+    for (abuse, abusers_rep, others_rep, ran_fully) in results {
+        println!("{:?}: abusers={} others={} ran_fully={}", abuse, abusers_rep, others_rep, ran_fully);
+    }
+    return Ok(());
+}
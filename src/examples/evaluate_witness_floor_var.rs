@@ -17,7 +17,8 @@ pub async fn evaluate_witness_floor_var(url: &str) -> Result<()> {
         user_default_reputation: vec![0.5; 30],
         user_organizations: vec![0,0,0,0,0,1,1,1,1,1,2,2,2,2,2,0,0,0,0,0,1,1,1,1,1,2,2,2,2,2],
         organization_reputation_threshold: vec![0.1; 30],
-        organization_default_reputation: vec![0.5; 30]
+        organization_default_reputation: vec![0.5; 30],
+        ..Default::default()
     };
 
     let mut ind_var_0: IndependantVar<IndependantVarApp> = IndependantVar {
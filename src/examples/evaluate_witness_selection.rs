@@ -0,0 +1,52 @@
+use crate::{
+    witness_rep::{
+        self,
+        implementation::witness_selection::WitnessSelectionConfig
+    },
+    evaluating_rep
+};
+
+use anyhow::Result;
+
+// Compares the accuracy of the reputation maps when the participants choose at most
+// three witnesses by each of the selection strategies
+pub async fn evaluate_witness_selection(url: &str) -> Result<()> {
+    let strategies = vec![
+        WitnessSelectionConfig::AllEligible,
+        WitnessSelectionConfig::TopReputation,
+        WitnessSelectionConfig::Random,
+        WitnessSelectionConfig::OrganizationDiverse,
+        WitnessSelectionConfig::CostAware { base_fee: 1.0, reputation_premium: 2.0, budget: 6.0 },
+    ];
+
+    let mut results: Vec<(String, f32, bool)> = Vec::new();
+    for strategy in strategies {
+        let sc = witness_rep::simulation::SimulationConfig {
+            node_url: String::from(url),
+            num_users: 15,
+            average_proximity: 0.5,
+            witness_floor: 2,
+            runs: 100,
+            reliability: vec![1.0, 1.0, 0.4, 0.7, 0.6, 0.8, 0.9, 0.7, 0.3, 0.6, 1.0, 0.7, 0.4, 0.5, 1.0],
+            user_reputation_threshold: vec![0.1; 15],
+            user_default_reputation: vec![0.5; 15],
+            user_organizations: vec![0,0,0,0,0,1,1,1,1,1,2,2,2,2,2],
+            organization_reputation_threshold: vec![0.4; 3],
+            organization_default_reputation: vec![0.5; 3],
+            witness_selection: Some(strategy.clone()),
+            witness_ceiling: Some(3),
+            max_tries: Some(200),
+            ..Default::default()
+        };
+        let (dir_name, ran_fully) = witness_rep::quick_simulation::quick_simulation(sc, false).await?;
+
+        let rel_map = evaluating_rep::stats::read_reliabilities(dir_name, false)?;
+        let mse = evaluating_rep::stats::run_avg_mean_squared_error(rel_map)?;
+        results.push((format!("{:?}", strategy), mse, ran_fully));
+    }
+
+    for (strategy, mse, ran_fully) in results {
+        println!("{}: mse={} ran_fully={}", strategy, mse, ran_fully);
+    }
+    return Ok(());
+}
@@ -6,3 +6,21 @@ pub mod evaluate_runs_var;
 pub mod evaluate_witness_floor_var;
 pub mod run_moderate_sim;
 pub mod run_simple_sim;
+pub mod run_org_trust_sim;
+pub mod evaluate_organization_policy;
+pub mod run_rogue_organization_sim;
+pub mod evaluate_reputation_model;
+pub mod evaluate_global_trust;
+pub mod evaluate_reputation_decay;
+pub mod evaluate_tsg;
+pub mod evaluate_role_reputation;
+pub mod evaluate_recommendation;
+pub mod evaluate_visibility;
+pub mod evaluate_witness_selection;
+pub mod evaluate_economy;
+pub mod evaluate_scheduler;
+pub mod run_actor_sim;
+pub mod evaluate_faults;
+pub mod evaluate_witness_abuse;
+pub mod benchmark_quick_simulation;
+pub mod benchmark_verification;
\ No newline at end of file
@@ -0,0 +1,39 @@
+use crate::{
+    witness_rep,
+    evaluating_rep
+};
+
+use anyhow::Result;
+
+// Runs the same population with the interactions carried out step by step and as a
+// protocol between actors, to check that the two agree on the accuracy of the reputations
+pub async fn run_actor_sim(url: &str) -> Result<()> {
+    let mut results: Vec<(bool, f32, bool)> = Vec::new();
+    for actors in vec![false, true] {
+        let sc = witness_rep::simulation::SimulationConfig {
+            node_url: String::from(url),
+            num_users: 15,
+            average_proximity: 0.5,
+            witness_floor: 2,
+            runs: 100,
+            reliability: vec![1.0, 1.0, 0.4, 0.7, 0.6, 0.8, 0.9, 0.7, 0.3, 0.6, 1.0, 0.7, 0.4, 0.5, 1.0],
+            user_reputation_threshold: vec![0.1; 15],
+            user_default_reputation: vec![0.5; 15],
+            user_organizations: vec![0,0,0,0,0,1,1,1,1,1,2,2,2,2,2],
+            organization_reputation_threshold: vec![0.1; 3],
+            organization_default_reputation: vec![0.5; 3],
+            actors: actors,
+            ..Default::default()
+        };
+        let (dir_name, ran_fully) = witness_rep::quick_simulation::quick_simulation(sc, false).await?;
+
+        let rel_map = evaluating_rep::stats::read_reliabilities(dir_name, false)?;
+        let mse = evaluating_rep::stats::run_avg_mean_squared_error(rel_map)?;
+        results.push((actors, mse, ran_fully));
+    }
+
+    for (actors, mse, ran_fully) in results {
+        println!("actors={}: mse={} ran_fully={}", actors, mse, ran_fully);
+    }
+    return Ok(());
+}
@@ -19,7 +19,8 @@ pub async fn run_moderate_sim(url: &str) -> Result<()> {
         user_default_reputation: vec![0.5; 15],
         user_organizations: vec![0,0,0,0,0,1,1,1,1,1,2,2,2,2,2],
         organization_reputation_threshold: vec![0.1; 15],
-        organization_default_reputation: vec![0.5; 15]
+        organization_default_reputation: vec![0.5; 15],
+        ..Default::default()
     };
     let dir_name = witness_rep::simulation::simulation(sc).await?;
 
@@ -0,0 +1,36 @@
+use crate::{
+    witness_rep,
+    evaluating_rep
+};
+
+use anyhow::Result;
+
+pub async fn run_org_trust_sim(url: &str) -> Result<()> {
+    // organization 2 is distrusted by the others, and so are its witnesses
+    let sc = witness_rep::simulation::SimulationConfig {
+        node_url: String::from(url),
+        num_users: 15,
+        average_proximity: 0.5,
+        witness_floor: 2,
+        runs: 100,
+        reliability: vec![1.0, 1.0, 0.4, 0.7, 0.6, 0.8, 0.9, 0.7, 0.3, 0.6, 0.2, 0.3, 0.4, 0.3, 0.2],
+        user_reputation_threshold: vec![0.1; 15],
+        user_default_reputation: vec![0.5; 15],
+        user_organizations: vec![0,0,0,0,0,1,1,1,1,1,2,2,2,2,2],
+        organization_reputation_threshold: vec![0.1; 3],
+        organization_default_reputation: vec![0.5; 3],
+        organization_trust: Some(vec![
+            vec![1.0, 0.8, 0.1],
+            vec![0.8, 1.0, 0.1],
+            vec![0.5, 0.5, 1.0],
+        ]),
+        ..Default::default()
+    };
+    let (dir_name, _) = witness_rep::quick_simulation::quick_simulation(sc, false).await?;
+
+    // evaluate the results
+    let rel_map = evaluating_rep::stats::read_reliabilities(dir_name, false)?;
+    let mse = evaluating_rep::stats::run_avg_mean_squared_error(rel_map)?;
+    println!("{}", mse);
+    return Ok(());
+}
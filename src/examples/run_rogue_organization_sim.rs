@@ -0,0 +1,42 @@
+use crate::{
+    witness_rep,
+    evaluating_rep
+};
+
+use anyhow::Result;
+
+pub async fn run_rogue_organization_sim(url: &str) -> Result<()> {
+    // two honest organizations, whose users discount organizations below 0.4
+    let sc = witness_rep::simulation::SimulationConfig {
+        node_url: String::from(url),
+        num_users: 10,
+        average_proximity: 0.5,
+        witness_floor: 2,
+        runs: 100,
+        reliability: vec![1.0, 0.9, 0.8, 0.9, 0.7, 0.8, 1.0, 0.9, 0.6, 0.8],
+        user_reputation_threshold: vec![0.1; 10],
+        user_default_reputation: vec![0.5; 10],
+        user_organizations: vec![0,0,0,0,0,1,1,1,1,1],
+        organization_reputation_threshold: vec![0.1; 2],
+        organization_default_reputation: vec![0.5; 2],
+        user_organization_threshold: Some(vec![0.4; 10]),
+        ..Default::default()
+    };
+
+    // a third organization certifies 5 sybils
+    let sc = evaluating_rep::scenarios::add_rogue_organization(&sc, 5, 0.1);
+    let (dir_name, _) = witness_rep::quick_simulation::quick_simulation(sc, false).await?;
+
+    // evaluate the results
+    let org_reps = evaluating_rep::stats::read_organization_reputations(dir_name.clone())?;
+    if let Some(last_run) = org_reps.last() {
+        for (org_pk, rep) in last_run.iter() {
+            let discounted = evaluating_rep::stats::runs_until_discounted(&org_reps, org_pk, 0.4);
+            println!("{}: {} (discounted after {:?} runs)", org_pk, rep, discounted);
+        }
+    }
+    let rel_map = evaluating_rep::stats::read_reliabilities(dir_name, false)?;
+    let mse = evaluating_rep::stats::run_avg_mean_squared_error(rel_map)?;
+    println!("{}", mse);
+    return Ok(());
+}
@@ -19,7 +19,8 @@ pub async fn run_simple_sim(url: &str) -> Result<()> {
         user_default_reputation: vec![0.5; 4],
         user_organizations: vec![0,1,1,2],
         organization_reputation_threshold: vec![0.1; 4],
-        organization_default_reputation: vec![0.5; 4]
+        organization_default_reputation: vec![0.5; 4],
+        ..Default::default()
     };
     let dir_name = witness_rep::simulation::simulation(sc).await?;
 
@@ -0,0 +1,516 @@
+use crate::witness_rep::{
+    iota_did::{
+        create_and_upload_did::{recreate_dids, Key, RunMode},
+        did_registry::{DidResolver, new_registry, register_documents},
+        keystore::{EncryptedKeystore, encrypt, decrypt}
+    },
+    implementation::{
+        interaction::LazyMethod,
+        user_and_organization::{UserIdentity, OrganizationIdentity, IdInfo, UserState, parse_reputation_scores},
+        organization_policy::{PolicyDecision, new_policy, default_policy_config},
+        reputation_model::{
+            ReputationModel, ModelState, RoleReputations,
+            new_saveable_model, restore_model, reputation_scores_string
+        },
+        user_arena::Interner,
+        global_trust::GlobalView,
+        recommendation::Recommender,
+        economy::Account,
+        visibility::{Visibility, ObserverIndex},
+        ledger::{Ledger, Backend},
+        faults::FaultCounts,
+        witness_abuse::WitnessAbuse
+    },
+    simulation::{SimulationConfig, process_interactions},
+    engine::{Setup, Progress, run_engine, run_rand_gen},
+    utility::{
+        audit::{read_history, compare_reputations},
+        read_msgs::ChannelReader,
+        verify_interaction::WhichBranch
+    },
+    trust_score_generators::{cross_org_tsg::OrgTrustMatrix, user_tsg::TsgConfig}
+};
+
+use wb_reputation_system::{
+    data_types::{
+        event_protocol_messages::signatures::organization_cert::OrganizationCertificate,
+        identity::identity::{Identity, ReputationMap},
+        tsg_data_types::message::MessageAndPubkey
+    }
+};
+
+use iota_streams::{
+    app::transport::tangle::client::Client,
+    app_channels::api::tangle::{Author, Subscriber},
+    core::Result
+};
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ParticipantState {
+    pub seed: String,
+    pub did_key: Key,
+    pub reliability: Option<f32>,
+    pub org_cert: OrganizationCertificate,
+    pub org_memberships: HashMap<String, String>,
+    pub organization_threshold: Option<f32>,
+    pub user_reputation_threshold: f32,
+    pub user_default_reputation: f32,
+    /// The reputation scores held, to check the ledger against (see resume)
+    pub reputation_scores: String,
+    /// Everything the user's reputation model holds, including what is not on the
+    /// ledger, such as the clock of a decayed model
+    pub model_state: ModelState,
+    /// The states of the user's participant and witness reputations, if they keep them
+    #[serde(default)]
+    pub role_states: Option<(ModelState, ModelState)>,
+    /// The global reputations as last computed, which cannot be rebuilt from the ledger
+    #[serde(default)]
+    pub global_trust: Option<GlobalView>,
+    #[serde(default)]
+    pub tsg: TsgConfig,
+    /// The cost and accuracy of the user's recommendations so far
+    #[serde(default)]
+    pub recommender: Option<Recommender>,
+    #[serde(default)]
+    pub account: Option<Account>,
+    #[serde(default)]
+    pub witness_abuse: Option<WitnessAbuse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OrganizationState {
+    pub organization: usize,
+    pub seed: String,
+    pub did_key: Key,
+    pub org_cert: OrganizationCertificate,
+    pub ann_msg: String,
+    /// The Streams author, exported so the channel can be continued
+    pub author_state: Vec<u8>,
+    pub user_reputation_threshold: f32,
+    pub user_default_reputation: f32,
+    pub decisions: Vec<PolicyDecision>,
+    pub reputation_scores: String,
+    pub model_state: ModelState,
+    #[serde(default)]
+    pub org_memberships: HashMap<String, String>,
+}
+
+/// Everything needed to continue a simulation after the last completed run. It holds the
+/// secret keys of the population, so it is only written out encrypted (see save_checkpoint).
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Checkpoint {
+    /// The run the simulation continues from
+    pub next_run: usize,
+    /// The seed the generator of each run is derived from (see engine::run_rand_gen)
+    pub run_seed: u64,
+    pub lazy_methods: Vec<LazyMethod>,
+    pub participants: Vec<ParticipantState>,
+    pub organizations: Vec<OrganizationState>,
+    /// The contents of organization_reputations.txt so far
+    pub organization_reputations: String,
+    /// What the participants of each run knew first hand, which is not on the ledger
+    #[serde(default)]
+    pub first_hand: BTreeMap<usize, HashMap<String, bool>>,
+    /// Who learned about each run, and the subscriptions and contacts it was decided by
+    #[serde(default)]
+    pub visibility: Visibility,
+    /// The faults injected so far, which faults.txt counts from the first run
+    #[serde(default)]
+    pub faults: FaultCounts,
+    /// The time signatures are made at, if the simulation fixed it (see EngineOptions)
+    #[serde(default)]
+    pub clock: Option<u32>,
+}
+
+// Every identity of a checkpointed simulation keeps their reputations in a model
+// whose state can be saved (see simulation::identity_model)
+fn model_state(id_info: &IdInfo) -> ModelState {
+    return id_info.state.reputation_model
+        .as_ref()
+        .map(|model| model.save())
+        .expect("The reputation model of a checkpointed simulation must be saveable");
+}
+
+// The author state is exported encrypted with the passphrase, as is the checkpoint itself
+pub async fn create_checkpoint(
+    next_run: usize,
+    run_seed: u64,
+    lazy_methods: &Vec<LazyMethod>,
+    participants: &Vec<UserIdentity>,
+    organizations: &Vec<OrganizationIdentity>,
+    org_indices: &Vec<usize>,
+    organization_reputations: &str,
+    first_hand: &BTreeMap<usize, HashMap<String, bool>>,
+    visibility: &Visibility,
+    faults: &FaultCounts,
+    clock: Option<u32>,
+    passphrase: &str
+) -> Result<Checkpoint> {
+    let participant_states: Vec<ParticipantState> = participants
+        .iter()
+        .map(|part| ParticipantState {
+            seed: part.id_info.seed.clone().unwrap(),
+            did_key: part.id_info.did_key,
+            reliability: part.id_info.reliability,
+            org_cert: part.id_info.org_cert.clone(),
+            org_memberships: part.id_info.state.org_memberships.clone(),
+            organization_threshold: part.id_info.state.organization_threshold,
+            user_reputation_threshold: part.user_reputation_threshold,
+            user_default_reputation: part.user_default_reputation,
+            reputation_scores: reputation_scores_string(part),
+            model_state: model_state(&part.id_info),
+            role_states: part.id_info.state.role_reputations
+                .as_ref()
+                .map(|roles| (roles.participant.save(), roles.witness.save())),
+            global_trust: part.id_info.state.global_trust.clone(),
+            tsg: part.id_info.state.tsg.clone(),
+            recommender: part.id_info.state.recommender.clone(),
+            account: part.id_info.state.account.clone(),
+            witness_abuse: part.id_info.state.witness_abuse.clone()
+        })
+        .collect();
+
+    let mut organization_states: Vec<OrganizationState> = Vec::new();
+    for (i, org) in organizations.iter().enumerate() {
+        organization_states.push(OrganizationState {
+            organization: org_indices[i],
+            seed: org.identity.id_info.seed.clone().unwrap(),
+            did_key: org.identity.id_info.did_key,
+            org_cert: org.identity.id_info.org_cert.clone(),
+            ann_msg: org.ann_msg.clone().unwrap(),
+            author_state: org.identity.channel_client.export(passphrase).await?,
+            user_reputation_threshold: org.identity.user_reputation_threshold,
+            user_default_reputation: org.identity.user_default_reputation,
+            decisions: org.decisions.clone(),
+            reputation_scores: reputation_scores_string(&org.identity),
+            model_state: model_state(&org.identity.id_info),
+            org_memberships: org.identity.id_info.state.org_memberships.clone()
+        });
+    }
+
+    return Ok(Checkpoint {
+        next_run: next_run,
+        run_seed: run_seed,
+        lazy_methods: lazy_methods.clone(),
+        participants: participant_states,
+        organizations: organization_states,
+        organization_reputations: String::from(organization_reputations),
+        first_hand: first_hand.clone(),
+        visibility: visibility.clone(),
+        faults: faults.clone(),
+        clock: clock
+    });
+}
+
+// The checkpoint is encrypted like the keystore, and written to a temporary file first,
+// so a crash while writing never leaves the run directory without a valid checkpoint
+pub fn save_checkpoint(run_dir: &str, checkpoint: &Checkpoint, passphrase: &str) -> Result<()> {
+    let encrypted = encrypt(passphrase, &serde_json::to_vec(checkpoint)?)?;
+    let tmp_name = format!("{}/checkpoint.json.tmp", run_dir);
+    fs::write(&tmp_name, serde_json::to_string(&encrypted)?)?;
+    fs::rename(&tmp_name, format!("{}/checkpoint.json", run_dir))?;
+    return Ok(());
+}
+
+// Fails if the passphrase is wrong
+pub fn load_checkpoint(run_dir: &str, passphrase: &str) -> Result<Checkpoint> {
+    let encrypted: EncryptedKeystore = serde_json::from_str(
+        &fs::read_to_string(format!("{}/checkpoint.json", run_dir))?
+    )?;
+    return Ok(serde_json::from_slice(&decrypt(passphrase, &encrypted)?)?);
+}
+
+// Continues the simulation in run_dir from its last checkpoint, which is decrypted with the
+// passphrase of the simulation's keystore. The passphrase is not among the parameters saved
+// in run_dir, so it has to be given again. Every identity is restored as it was saved. With
+// check_ledger, the reputation maps are also rebuilt by replaying the interactions on the
+// ledger up to the checkpoint, and resuming fails if they do not match the saved ones.
+pub async fn resume(run_dir: &str, passphrase: &str, check_ledger: bool, print: bool) -> Result<String> {
+    let mut sc: SimulationConfig = serde_json::from_str(
+        &fs::read_to_string(format!("{}/sim_parameters.txt", run_dir))?
+    )?;
+    match sc.keystore.as_mut() {
+        Some(keystore)  => keystore.passphrase = String::from(passphrase),
+        None            => return Err(anyhow!("Only a simulation with a keystore is checkpointed"))
+    }
+    let checkpoint = load_checkpoint(run_dir, passphrase)?;
+    if print {
+        println!("Resuming {} from run {}", run_dir, checkpoint.next_run);
+    }
+
+    let client = Client::new_from_url(&sc.node_url);
+    let ids = Interner::new();
+    let organizations = restore_organizations(&sc, &checkpoint, &client, passphrase, false, &ids).await?;
+    let org_indices: Vec<usize> = checkpoint.organizations.iter().map(|o| o.organization).collect();
+    let participants = restore_participants(&checkpoint, &client, false, &ids);
+
+    // a registry kept in memory did not survive, so the documents are published again
+    let did_registry: Option<Box<dyn DidResolver>> = match &sc.did_registry {
+        Some(config) => {
+            let mut registry = new_registry(config)?;
+            let secrets: Vec<Key> = checkpoint.organizations
+                .iter()
+                .map(|o| o.did_key)
+                .chain(checkpoint.participants.iter().map(|p| p.did_key))
+                .collect();
+            register_documents(registry.as_mut(), &recreate_dids(&secrets, RunMode::Local).await?)?;
+            Some(registry)
+        },
+        None => None
+    };
+
+    let org_pubkeys: HashMap<usize, String> = checkpoint.organizations
+        .iter()
+        .map(|o| (o.organization, o.org_cert.org_pubkey.clone()))
+        .collect();
+    let org_trust: Option<OrgTrustMatrix> = sc.organization_trust
+        .as_ref()
+        .map(|matrix| OrgTrustMatrix::from_config(matrix, &org_pubkeys));
+
+    let mut ledger = Ledger::new(Backend::Streams, &sc.node_url, did_registry);
+    if let Some(time) = checkpoint.clock {
+        ledger = ledger.with_clock(time);
+    }
+
+    // the ledger is replayed by a copy of the population whose models start empty. It is
+    // read through the ledger's readers, which the resumed runs carry on from.
+    if check_ledger {
+        let replayed_ids = Interner::new();
+        let mut replayed_orgs = restore_organizations(&sc, &checkpoint, &client, passphrase, true, &replayed_ids).await?;
+        let mut replayed = restore_participants(&checkpoint, &client, true, &replayed_ids);
+        let (readers, _) = ledger.readers();
+        rebuild_reputations(
+            &sc.node_url, readers, &mut replayed, &mut replayed_orgs, &org_trust,
+            &checkpoint.first_hand, &checkpoint.visibility, checkpoint.next_run
+        ).await?;
+        check_rebuilt_reputations(&checkpoint, &replayed, &replayed_orgs)?;
+    }
+    let mut setup = Setup {
+        folder_name: String::from(run_dir),
+        ledger: ledger,
+        participants: participants,
+        organizations: organizations,
+        org_indices: org_indices,
+        org_trust: org_trust,
+        lazy_methods: checkpoint.lazy_methods,
+        // every run draws from a generator of its own (see engine::run_rand_gen)
+        rand_gen: run_rand_gen(checkpoint.run_seed, checkpoint.next_run),
+        run_seed: checkpoint.run_seed
+    };
+    let progress = Progress {
+        first_run: checkpoint.next_run,
+        org_rep_output: checkpoint.organization_reputations,
+        first_hand: checkpoint.first_hand,
+        visibility: checkpoint.visibility,
+        fault_counts: checkpoint.faults
+    };
+    let (folder_name, _) = run_engine(&sc, &mut setup, progress, print).await?;
+    return Ok(folder_name);
+}
+
+// The model a state was saved from, or, if empty, a new model like it
+fn restored_model(state: &ModelState, default_reputation: f32, empty: bool, ids: &Interner) -> Box<dyn ReputationModel> {
+    let model = restore_model(state, ids);
+    return match empty {
+        true  => new_saveable_model(&model.config(), default_reputation, ids),
+        false => model
+    };
+}
+
+async fn restore_organizations(
+    sc: &SimulationConfig,
+    checkpoint: &Checkpoint,
+    client: &Client,
+    passphrase: &str,
+    empty_models: bool,
+    ids: &Interner
+) -> Result<Vec<OrganizationIdentity>> {
+    // every organization's reader subscriber is created from the same name
+    let reader_pk = Subscriber::new("reader", client.clone()).get_public_key().clone();
+
+    let mut organizations: Vec<OrganizationIdentity> = Vec::new();
+    for state in checkpoint.organizations.iter() {
+        let author = Author::import(&state.author_state, passphrase, client.clone()).await?;
+        let policy_config = match &sc.organization_policy {
+            Some(policies)  => policies[state.organization].clone(),
+            None            => default_policy_config()
+        };
+        organizations.push(OrganizationIdentity {
+            identity: Identity {
+                channel_client: author,
+                id_info: IdInfo {
+                    seed: Some(state.seed.clone()),
+                    did_key: state.did_key,
+                    reliability: None,
+                    org_cert: state.org_cert.clone(),
+                    state: UserState {
+                        org_memberships: state.org_memberships.clone(),
+                        reputation_model: Some(restored_model(
+                            &state.model_state, state.user_default_reputation, empty_models, ids
+                        )),
+                        tsg: TsgConfig::Organization,
+                        ..Default::default()
+                    }
+                },
+                reputation_map: ReputationMap::new(),
+                user_reputation_threshold: state.user_reputation_threshold,
+                user_default_reputation: state.user_default_reputation
+            },
+            ann_msg: Some(state.ann_msg.clone()),
+            policy: new_policy(&policy_config),
+            readers: vec![reader_pk.clone()],
+            decisions: state.decisions.clone()
+        });
+    }
+    return Ok(organizations);
+}
+
+fn restore_participants(checkpoint: &Checkpoint, client: &Client, empty_models: bool, ids: &Interner) -> Vec<UserIdentity> {
+    return checkpoint.participants
+        .iter()
+        .map(|state| UserIdentity {
+            channel_client: Subscriber::new(&state.seed, client.clone()),
+            id_info: IdInfo {
+                seed: Some(state.seed.clone()),
+                did_key: state.did_key,
+                reliability: state.reliability,
+                org_cert: state.org_cert.clone(),
+                state: UserState {
+                    org_memberships: state.org_memberships.clone(),
+                    organization_threshold: state.organization_threshold,
+                    reputation_model: Some(restored_model(
+                        &state.model_state, state.user_default_reputation, empty_models, ids
+                    )),
+                    global_trust: state.global_trust.clone(),
+                    tsg: state.tsg.clone(),
+                    role_reputations: state.role_states.as_ref().map(|(participant, witness)| RoleReputations {
+                        participant: restored_model(participant, state.user_default_reputation, empty_models, ids),
+                        witness: restored_model(witness, state.user_default_reputation, empty_models, ids)
+                    }),
+                    recommender: state.recommender.clone(),
+                    account: state.account.clone(),
+                    witness_abuse: state.witness_abuse.clone(),
+                    ..Default::default()
+                }
+            },
+            reputation_map: ReputationMap::new(),
+            user_reputation_threshold: state.user_reputation_threshold,
+            user_default_reputation: state.user_default_reputation
+        })
+        .collect();
+}
+
+// Replays the interactions of the runs before next_run, in the order they took place
+async fn rebuild_reputations(
+    node_url: &str,
+    readers: &mut HashMap<String, ChannelReader>,
+    participants: &mut Vec<UserIdentity>,
+    organizations: &mut Vec<OrganizationIdentity>,
+    org_trust: &Option<OrgTrustMatrix>,
+    first_hand: &BTreeMap<usize, HashMap<String, bool>>,
+    visibility: &Visibility,
+    next_run: usize
+) -> Result<()> {
+    let ann_msgs: Vec<String> = organizations
+        .iter()
+        .filter_map(|org| org.ann_msg.clone())
+        .collect();
+    let (history, history_arrivals) = read_history(node_url, &ann_msgs, readers).await?;
+
+    // runs after the checkpoint which were interrupted are on the ledger too. They are
+    // run again, and on the same channel the new branch replaces the interrupted one,
+    // but if a run moves to another channel, readers merging channels may see both.
+    let interrupted = history.range(next_run..).count();
+    if interrupted > 0 {
+        println!("-- Warning: {} runs after the checkpoint were already on the ledger", interrupted);
+    }
+
+    // the readers parse each branch once, and keep the parsed interactions for later reads
+    let every_branch = WhichBranch::FromBranch(0);
+    let mut parsed: BTreeMap<usize, Vec<MessageAndPubkey>> = BTreeMap::new();
+    for ann_msg in ann_msgs.iter() {
+        let reader = readers.get_mut(ann_msg).unwrap();
+        let runs = reader.runs(&every_branch);
+        parsed.extend(runs.into_iter().zip(reader.interactions(&every_branch)?));
+    }
+
+    let mut interactions: Vec<Vec<MessageAndPubkey>> = Vec::new();
+    let mut arrivals: Vec<Vec<u32>> = Vec::new();
+    let mut knowledge: Vec<HashMap<String, bool>> = Vec::new();
+    let mut observers: Vec<Option<HashSet<String>>> = Vec::new();
+    for run in history.range(..next_run).map(|(run, _)| run) {
+        interactions.push(parsed.remove(run).unwrap_or_default());
+        arrivals.push(history_arrivals.get(run).cloned().unwrap_or_default());
+        knowledge.push(first_hand.get(run).cloned().unwrap_or(HashMap::new()));
+        observers.push(visibility.observers.get(run).cloned());
+    }
+    let index = ObserverIndex::new(participants, organizations);
+    process_interactions(participants, organizations, &index, &interactions, &arrivals, &knowledge, &observers, org_trust);
+    return Ok(());
+}
+
+fn check_rebuilt_reputations(
+    checkpoint: &Checkpoint,
+    participants: &Vec<UserIdentity>,
+    organizations: &Vec<OrganizationIdentity>
+) -> Result<()> {
+    let saved = checkpoint.participants
+        .iter()
+        .map(|p| &p.reputation_scores)
+        .chain(checkpoint.organizations.iter().map(|o| &o.reputation_scores));
+    let rebuilt = participants
+        .iter()
+        .map(|p| (p.id_info.org_cert.client_pubkey.clone(), reputation_scores_string(p)))
+        .chain(organizations.iter().map(|o| (
+            o.identity.id_info.org_cert.client_pubkey.clone(),
+            reputation_scores_string(&o.identity)
+        )));
+
+    for (saved_scores, (holder, rebuilt_scores)) in saved.zip(rebuilt) {
+        let findings = compare_reputations(
+            &holder,
+            &parse_reputation_scores(&rebuilt_scores),
+            &parse_reputation_scores(saved_scores)
+        );
+        if findings.len() > 0 {
+            return Err(anyhow!("The ledger does not match the checkpoint: {:?}", findings));
+        }
+    }
+    return Ok(());
+}
+
+#[test]
+pub fn test_checkpoint_round_trip() {
+    let run_dir = std::env::temp_dir().join("checkpoint_round_trip");
+    fs::create_dir_all(&run_dir).unwrap();
+    let run_dir = run_dir.to_str().unwrap();
+
+    let checkpoint = Checkpoint {
+        next_run: 10,
+        run_seed: 42,
+        lazy_methods: vec![LazyMethod::Random, LazyMethod::Constant(true)],
+        participants: Vec::new(),
+        organizations: Vec::new(),
+        organization_reputations: String::from("Run 0\n\n"),
+        first_hand: vec![(3, vec![(String::from("a"), false)].into_iter().collect())].into_iter().collect(),
+        visibility: Visibility::default()
+    };
+    save_checkpoint(run_dir, &checkpoint, "correct horse").unwrap();
+
+    // the checkpoint holds secret keys, so it is only readable with the passphrase
+    let written = fs::read_to_string(format!("{}/checkpoint.json", run_dir)).unwrap();
+    assert!(!written.contains("Run 0"));
+    assert!(load_checkpoint(run_dir, "wrong horse").is_err());
+
+    let loaded = load_checkpoint(run_dir, "correct horse").unwrap();
+    assert_eq!(loaded.next_run, 10);
+    assert_eq!(loaded.run_seed, 42);
+    assert_eq!(loaded.lazy_methods.len(), 2);
+    assert_eq!(loaded.organization_reputations, checkpoint.organization_reputations);
+    assert_eq!(loaded.first_hand, checkpoint.first_hand);
+}
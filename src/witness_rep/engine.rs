@@ -0,0 +1,1040 @@
+use crate::witness_rep::{
+    iota_did::{
+        create_and_upload_did::{recreate_dids, Key, RunMode},
+        did_registry::{new_registry, register_documents},
+        keystore::{Population, load_population, save_population}
+    },
+    implementation::{generate_contract, generate_sigs},
+    implementation::{
+        interaction::{interaction, LazyMethod},
+        actor_interaction::actor_interaction,
+        ledger::{Ledger, Backend},
+        user_and_organization::{
+            UserIdentity, OrganizationIdentity,
+            IdInfo, UserState, get_index_org_with_pubkey
+        },
+        organization_policy::{new_policy, default_policy_config},
+        organization_reputation::organization_reputations_string,
+        reputation_model::{
+            ReputationModelConfig, Role, reputation_scores_string,
+            reputation_confidences_string, user_reputations_string, role_reputations_string
+        },
+        user_arena::Interner,
+        global_trust::{update_global_trust, is_due, global_reputations_string},
+        recommendation::{Recommender, recommendation_summary_string},
+        visibility::{Visibility, ObserverIndex, visibility_summary_string},
+        witness_selection::{WitnessSelection, new_witness_selection},
+        economy::{
+            Account, contract_compensation, settle_interaction, deny,
+            utilities_string, strategy_utilities_string
+        },
+        faults::{FaultInjector, FaultCounts, faults_summary_string}
+    },
+    simulation::{
+        SimulationConfig, ALPH9, DEFAULT_DURATION, DEFAULT_MAX_TRIES,
+        check_organization_config, check_population, update_population, generate_participants_and_witnesses,
+        process_interactions, policy_decisions_string, first_hand_knowledge,
+        identity_model, user_model_config, user_tsg_config, user_role_reputations, user_witness_abuse,
+        read_user_pubkeys, changed_user_pubkeys, apply_reliability_changes
+    },
+    utility::audit,
+    checkpoint,
+    trust_score_generators::{
+        cross_org_tsg::OrgTrustMatrix,
+        statement_rules::check_interaction,
+        user_tsg::TsgConfig
+    }
+};
+
+use wb_reputation_system::data_types::{
+    identity::identity::{Identity, ReputationMap},
+    tsg_data_types::message::MessageAndPubkey
+};
+
+use iota_streams::{
+    app::transport::tangle::client::Client,
+    app_channels::api::tangle::{Author, ChannelType, Subscriber},
+    app_channels::Tangle,
+    core::Result
+};
+use identity::crypto::KeyPair;
+
+use anyhow::anyhow;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use chrono::prelude::{Utc, DateTime};
+
+/// How a simulation is run, besides its parameters
+#[derive(Clone, Debug)]
+pub struct EngineOptions {
+    /// Where the messages of the interactions are published
+    pub backend: Backend,
+    /// Seeds every random choice of the simulation, including the keys of the population,
+    /// so that the simulation can be repeated. If None, it is seeded from entropy
+    pub seed: Option<u64>,
+    /// The time signatures and certificates are made at. If None, the current time
+    pub clock: Option<u32>,
+    /// The directory the output is written to. If None, a directory named after the
+    /// kind of simulation and the time is created in ./runs
+    pub run_dir: Option<String>,
+    pub kind: String,
+    pub print: bool,
+}
+
+impl EngineOptions {
+    pub fn new(backend: Backend, kind: &str, print: bool) -> EngineOptions {
+        return EngineOptions {
+            backend: backend,
+            seed: None,
+            clock: None,
+            run_dir: None,
+            kind: String::from(kind),
+            print: print
+        };
+    }
+}
+
+/// A simulation that is ready to start its first run
+pub struct Setup {
+    pub folder_name: String,
+    pub ledger: Ledger,
+    pub participants: Vec<UserIdentity>,
+    pub organizations: Vec<OrganizationIdentity>,
+    /// The index of each of the organizations, in the simulation parameters
+    pub org_indices: Vec<usize>,
+    pub org_trust: Option<OrgTrustMatrix>,
+    pub lazy_methods: Vec<LazyMethod>,
+    pub rand_gen: StdRng,
+    /// The seed the generator of each run is derived from (see run_rand_gen)
+    pub run_seed: u64,
+}
+
+/// Where a simulation stands before its next run, which is carried over from the runs
+/// before it. The default is the start of a simulation.
+#[derive(Default)]
+pub struct Progress {
+    /// The run the simulation continues from
+    pub first_run: usize,
+    /// The contents of organization_reputations.txt so far
+    pub org_rep_output: String,
+    /// What the participants of each run knew first hand
+    pub first_hand: BTreeMap<usize, HashMap<String, bool>>,
+    pub visibility: Visibility,
+    /// The faults injected so far
+    pub fault_counts: FaultCounts,
+}
+
+// Runs the simulation on the backend of the options, from its setup to the results.
+// Returns the run directory, and whether every run ended in an interaction.
+pub async fn run(sc: &SimulationConfig, options: &EngineOptions) -> Result<(String, bool)> {
+    let mut setup = engine_setup(sc, options).await?;
+    return run_engine(sc, &mut setup, Progress::default(), options.print).await;
+}
+
+// Checks the simulation parameters, creates the run directory, the population and the
+// organizations' channels, and chooses the lazy methods. On Streams, the population is
+// reused from the keystore if it holds one, and the channels are recorded in it.
+pub async fn engine_setup(sc: &SimulationConfig, options: &EngineOptions) -> Result<Setup> {
+    if sc.reliability.len() != sc.num_users {
+        panic!("Number of elements in 'reliability' parameter must equal the num_users!");
+    } else if sc.user_organizations.len() != sc.num_users {
+        panic!("Number of elements in 'organizations' parameter must equal the num_users!");
+    }
+    check_organization_config(sc)?;
+    if sc.checkpoint_interval.is_some() && sc.keystore.is_none() && options.backend == Backend::Streams {
+        return Err(anyhow!("Checkpoints are encrypted with the passphrase of the keystore, so they need a keystore"));
+    }
+    if sc.actors && options.backend != Backend::Quick {
+        return Err(anyhow!("The actors exchange their messages directly, so they can only run on the quick backend"));
+    }
+
+    let mut rand_gen = match options.seed {
+        Some(seed)  => StdRng::seed_from_u64(seed),
+        None        => StdRng::from_entropy()
+    };
+    let folder_name = create_run_folder(sc, options)?;
+
+    // DID documents are only created if there is a registry to publish them to
+    let did_registry = match &sc.did_registry {
+        Some(config)    => Some(new_registry(config)?),
+        None            => None
+    };
+    let mut ledger = Ledger::new(options.backend.clone(), &sc.node_url, did_registry);
+    if let Some(time) = options.clock {
+        ledger = ledger.with_clock(time);
+    }
+
+    // a population stored by a previous simulation is reused. Only Streams channels
+    // outlive the simulation, so only they are stored.
+    let population: Option<Population> = match (&sc.keystore, &options.backend) {
+        (Some(keystore), Backend::Streams) => load_population(&keystore.path, &keystore.passphrase)?,
+        _ => None
+    };
+    if let Some(stored) = &population {
+        check_population(sc, stored)?;
+    }
+
+    let (mut organizations, participants, org_indices) = create_population(
+        sc, &population, &mut ledger, &folder_name, &mut rand_gen
+    ).await?;
+
+    // organizations (acting as authors) create the channels
+    for i in 0..organizations.len() {
+        ledger.open_channel(&mut organizations[i], i).await?;
+    }
+
+    // the population is stored before running, so the channels can be reopened
+    // even if the simulation does not finish
+    if let (Some(keystore), Backend::Streams) = (&sc.keystore, &options.backend) {
+        let orgs: Vec<&usize> = org_indices.iter().collect();
+        let stored = update_population(population, &participants, &organizations, &orgs, &sc.user_organizations);
+        save_population(&keystore.path, &keystore.passphrase, &stored)?;
+    }
+
+    let org_pubkeys: HashMap<usize, String> = org_indices
+        .iter()
+        .cloned()
+        .zip(organizations.iter().map(|org| org.identity.id_info.org_cert.org_pubkey.clone()))
+        .collect();
+    let org_trust: Option<OrgTrustMatrix> = sc.organization_trust
+        .as_ref()
+        .map(|matrix| OrgTrustMatrix::from_config(matrix, &org_pubkeys));
+
+    // generate the lazy methods
+    if options.print {
+        println!("Generating lazy methods:");
+    }
+    let lazy_methods = random_lazy_methods(sc.runs, &mut rand_gen);
+    if options.print {
+        println!("-- Lazy methods to be used: {:?}\n", lazy_methods);
+    }
+    let run_seed: u64 = rand_gen.gen();
+
+    return Ok(Setup {
+        folder_name: folder_name,
+        ledger: ledger,
+        participants: participants,
+        organizations: organizations,
+        org_indices: org_indices,
+        org_trust: org_trust,
+        lazy_methods: lazy_methods,
+        rand_gen: rand_gen,
+        run_seed: run_seed
+    });
+}
+
+// Creates the directory the output of a run goes to, unless the options name one, and
+// records the simulation parameters in it
+pub fn create_run_folder(sc: &SimulationConfig, options: &EngineOptions) -> Result<String> {
+    let folder_name = match &options.run_dir {
+        Some(dir) => dir.clone(),
+        None => {
+            let time: DateTime<Utc> = Utc::now();
+            format!("./runs/{} {:?}", options.kind, time)
+        }
+    };
+    if options.print {
+        println!("{}", folder_name);
+    }
+    fs::create_dir_all(&folder_name)?;
+
+    let file_name = format!("{}/sim_parameters.txt", &folder_name);
+    let output = serde_json::to_string(sc)?;
+    fs::write(file_name, output).expect("Unable to write file");
+    return Ok(folder_name);
+}
+
+// Creates the organizations and the users of the simulation, and writes the reliability
+// of each user to start_reliability.txt. The keys of a stored population are reused, and
+// new keys are drawn from rand_gen, so that the same seed gives the same population. The
+// DID documents are published to the ledger's registry, if it has one. The organizations
+// are returned in the order of their indices, which are returned with them.
+pub async fn create_population(
+    sc: &SimulationConfig,
+    population: &Option<Population>,
+    ledger: &mut Ledger,
+    folder_name: &str,
+    rand_gen: &mut impl Rng
+) -> Result<(Vec<OrganizationIdentity>, Vec<UserIdentity>, Vec<usize>)> {
+
+    //--------------------------------------------------------------
+    //--------------------------------------------------------------
+    //  CREATE ORGANIZATIONS WHICH ACT AS AN OVERLAY FOR PARTICIPANTS
+    //--------------------------------------------------------------
+
+    let client = Client::new_from_url(&sc.node_url);
+    let model_config = sc.reputation_model.clone().unwrap_or(ReputationModelConfig::Average);
+    let ids = Interner::new();
+    let run_mode = match ledger.resolver() {
+        Some(_) => RunMode::Local,
+        None    => RunMode::Testing
+    };
+
+    // we find the set of organizations
+    let orgs_set: BTreeSet<usize> = sc.user_organizations.iter().cloned().collect();
+    let orgs: Vec<usize> = orgs_set.into_iter().collect();
+
+    // in their simplest form, an organization can be represented by
+    // a keypair, so we assign one to each organization
+    let org_secrets: Vec<Key> = match population {
+        Some(stored) => orgs
+            .iter()
+            .map(|org| stored.organization(*org).unwrap().did_key)
+            .collect(),
+        None => random_secrets(orgs.len(), rand_gen)
+    };
+    let org_did_details = recreate_dids(&org_secrets, run_mode.clone()).await?;
+    if let Some(registry) = ledger.resolver_mut() {
+        register_documents(registry, &org_did_details)?;
+    }
+
+    // we create a mapping of organization index to keypair and
+    // create an OrganizationIdentity object for each organization
+    let mut org_kp_map: HashMap<usize, KeyPair> = HashMap::new();
+    let mut organizations: Vec<OrganizationIdentity> = Vec::new();
+    for (i, (_, (kp, (_,sec)), _)) in org_did_details.into_iter().enumerate() {
+        let org = orgs[i];
+
+        let seed: &str = &random_seed(rand_gen);
+
+        let on: Author<Tangle> = Author::new(seed, ChannelType::MultiBranch, client.clone());
+        let pubkey =  generate_sigs::get_multibase(&kp);
+        let reputation_map: ReputationMap = HashMap::new();
+
+        let org_id: Identity<Author<Client>, IdInfo> = Identity{
+            channel_client: on,
+            id_info: IdInfo {
+                seed: Some(String::from(seed)),
+                did_key: sec,
+                reliability: None,
+                org_cert: generate_sigs::generate_org_cert(pubkey, &kp, ledger.timeout(DEFAULT_DURATION))?,
+                state: UserState {
+                    reputation_model: identity_model(sc, &model_config, sc.organization_default_reputation[org], &ids),
+                    tsg: TsgConfig::Organization,
+                    ..Default::default()
+                }
+            },
+            reputation_map: reputation_map,
+            user_reputation_threshold: sc.organization_reputation_threshold[org],
+            user_default_reputation: sc.organization_default_reputation[org]
+        };
+        org_kp_map.insert(org, kp);
+
+        let policy_config = match &sc.organization_policy {
+            Some(policies)  => policies[org].clone(),
+            None            => default_policy_config()
+        };
+
+        let org_id_with_announcement = OrganizationIdentity{
+            identity: org_id,
+            ann_msg: None,
+            policy: new_policy(&policy_config),
+            readers: Vec::new(),
+            decisions: Vec::new()
+        };
+        organizations.push(org_id_with_announcement);
+    }
+
+    //--------------------------------------------------------------
+    // CREATE PARTICIPANTS FOR SIMULATION
+    // (MORE DETAILS IN ALL_IN_ONE_TRANSACTION.RS)
+    //--------------------------------------------------------------
+
+    // create Decentalised Ids
+    let user_secrets: Vec<Key> = match population {
+        Some(stored) => stored.users.iter().map(|u| u.did_key).collect(),
+        None => random_secrets(sc.num_users, rand_gen)
+    };
+    let did_details = recreate_dids(&user_secrets, run_mode).await?;
+    if let Some(registry) = ledger.resolver_mut() {
+        register_documents(registry, &did_details)?;
+    }
+
+    let part_did_kps : Vec<&KeyPair> = did_details
+                                            .iter()
+                                            .map(|(_, (kp,_), _)| kp)
+                                            .collect();
+
+    // create channel subscriber instances
+    let mut output: String = String::new();
+    let mut participants: Vec<UserIdentity> = Vec::new();
+    for i in 0..sc.num_users{
+        let name = match population {
+            Some(stored)    => stored.users[i].seed.clone(),
+            None            => random_seed(rand_gen)
+        };
+        let tn = Subscriber::new(&name, client.clone());
+        let org_kp = &org_kp_map[&sc.user_organizations[i]];
+        let part_did_pk = generate_sigs::get_multibase(&part_did_kps[i]);
+        let reputation_map: ReputationMap = HashMap::new();
+
+        let id = UserIdentity {
+            channel_client: tn,
+            id_info: IdInfo {
+                seed: Some(name),
+                did_key: user_secrets[i],
+                reliability: Some(sc.reliability[i]),
+                org_cert: generate_sigs::generate_org_cert(part_did_pk.clone(), org_kp, ledger.timeout(DEFAULT_DURATION))?,
+                state: UserState {
+                    organization_threshold: sc.user_organization_threshold
+                        .as_ref()
+                        .map(|thresholds| thresholds[i]),
+                    reputation_model: identity_model(sc, &user_model_config(sc, i), sc.user_default_reputation[i], &ids),
+                    tsg: user_tsg_config(sc, i),
+                    role_reputations: user_role_reputations(sc, i, &ids),
+                    recommender: sc.recommendation.as_ref().map(|r| Recommender::new(r, i)),
+                    account: sc.economy.as_ref().map(|_| Account::default()),
+                    witness_abuse: user_witness_abuse(sc, i),
+                    ..Default::default()
+                }
+            },
+            reputation_map: reputation_map,
+            user_reputation_threshold: sc.user_reputation_threshold[i],
+            user_default_reputation: sc.user_default_reputation[i]
+        };
+        participants.push(id);
+
+        let part_entry = format!("{}: {}\n", part_did_pk, sc.reliability[i]);
+        output.push_str(&part_entry);
+    }
+
+    let file_name = format!("{}/start_reliability.txt", folder_name);
+    fs::write(file_name, output).expect("Unable to write file");
+
+    return Ok((organizations, participants, orgs));
+}
+
+// A seed for a new Streams client. Seeds are secret, as anyone who knows one can act as
+// its client, so they are drawn like the DID keys rather than derived from the user.
+pub fn random_seed(rand_gen: &mut impl Rng) -> String {
+    return (0..81)
+        .map(|_| {
+            ALPH9
+                .chars()
+                .nth(rand_gen.gen_range(0, 27))
+                .unwrap()
+        })
+        .collect::<String>();
+}
+
+// Secret keys for new DIDs. Any 32 bytes are an Ed25519 secret key.
+pub fn random_secrets(n: usize, rand_gen: &mut impl Rng) -> Vec<Key> {
+    return (0..n)
+        .map(|_| {
+            let mut secret: Key = [0; 32];
+            rand_gen.fill(&mut secret);
+            secret
+        })
+        .collect();
+}
+
+// A lazy method for each run, chosen at random
+pub fn random_lazy_methods(runs: usize, rand_gen: &mut impl Rng) -> Vec<LazyMethod> {
+    return (0..runs)
+        .map(|_| {
+            match rand_gen.gen_range(0,3) {
+                0 => LazyMethod::Constant(true),
+                1 => LazyMethod::Constant(false),
+                2 => LazyMethod::Random,
+                _ => panic!("Random number generator failure")
+            }
+        }).collect();
+}
+
+// Runs the simulation from the first run of the progress until the end, and writes the results.
+// The state passed in is either freshly set up or restored by checkpoint::resume. On Streams, the
+// state is checkpointed every checkpoint_interval runs, and the channels are audited at
+// the end. Returns the run directory, and whether every run ended in an interaction.
+pub async fn run_engine(
+    sc: &SimulationConfig,
+    setup: &mut Setup,
+    progress: Progress,
+    print: bool
+) -> Result<(String, bool)> {
+    let Progress { first_run, mut org_rep_output, mut first_hand, mut visibility, fault_counts } = progress;
+    let ledger = &mut setup.ledger;
+    let participants = &mut setup.participants;
+    let organizations = &mut setup.organizations;
+    let org_indices = &setup.org_indices;
+    let org_trust = &setup.org_trust;
+    let lazy_methods = &setup.lazy_methods;
+    let run_seed = setup.run_seed;
+    let folder_name = setup.folder_name.clone();
+    let streams = ledger.backend == Backend::Streams;
+
+    // users' views of the organizations are tracked after every run
+    // the users whose reliability changes, and the reputations they have after each run
+    let user_pks = read_user_pubkeys(&folder_name)?;
+    let changed_pks = changed_user_pubkeys(&sc.reliability_changes, &user_pks);
+    let mut changes_output: String = match first_run {
+        0 => String::new(),
+        _ => fs::read_to_string(format!("{}/reliability_changes.txt", &folder_name)).unwrap_or(String::new())
+    };
+
+    let mut org_pubkeys: Vec<(usize, String)> = org_indices
+        .iter()
+        .cloned()
+        .zip(organizations.iter().map(|org| org.identity.id_info.org_cert.org_pubkey.clone()))
+        .collect();
+    org_pubkeys.sort();
+    let org_pubkeys_list: Vec<String> = org_pubkeys.into_iter().map(|(_, pk)| pk).collect();
+
+    let witness_selection = new_witness_selection(&sc.witness_selection);
+    let observer_index = ObserverIndex::new(participants, organizations);
+    let mut faults = FaultInjector::seeded(&sc.faults, run_seed).with_counts(&fault_counts);
+    let mut ran_fully = true;
+    for i in first_run..sc.runs {
+        if print {
+            println!("\n\n\n---------------------STARTING RUN {}---------------------", i);
+        }
+        let mut rand_gen = run_rand_gen(run_seed, i);
+        faults.reseed(rand_gen.gen());
+        apply_reliability_changes(&sc.reliability_changes, i, participants, &user_pks);
+
+        // run the iteration
+        let ran = engine_iteration(
+            sc,
+            ledger,
+            organizations,
+            participants,
+            witness_selection.as_ref(),
+            lazy_methods[i].clone(),
+            org_trust,
+            &observer_index,
+            &mut rand_gen,
+            &mut first_hand,
+            &mut visibility,
+            &mut faults,
+            i,
+            &folder_name,
+            print
+        ).await?;
+
+        if !ran {
+            if print {
+                println!("FAILED TO RUN");
+            }
+            ran_fully = false;
+        }
+
+        org_rep_output.push_str(&organization_reputations_string(i, participants, &org_pubkeys_list));
+        if let Some(gt) = is_due(&sc.global_trust, i) {
+            update_global_trust(participants, gt);
+        }
+        if changed_pks.len() > 0 {
+            changes_output.push_str(&user_reputations_string(i, participants, &changed_pks));
+            let file_name = format!("{}/reliability_changes.txt", &folder_name);
+            fs::write(file_name, &changes_output).expect("Unable to write file");
+        }
+
+        // only the Streams channels outlive the simulation, so only a simulation on them can be resumed
+        if let (Some(interval), true) = (sc.checkpoint_interval, streams) {
+            if (i + 1) % interval == 0 && i + 1 < sc.runs {
+                // engine_setup made sure there is a keystore to take the passphrase from
+                let passphrase = &sc.keystore.as_ref().unwrap().passphrase;
+                if print {
+                    println!("Checkpointing after run {}", i);
+                }
+                let checkpoint = checkpoint::create_checkpoint(
+                    i + 1, run_seed, lazy_methods, participants, organizations, org_indices,
+                    &org_rep_output, &first_hand, &visibility, &faults.counts(), ledger.clock(), passphrase
+                ).await?;
+                checkpoint::save_checkpoint(&folder_name, &checkpoint, passphrase)?;
+            }
+        }
+    }
+    let file_name = format!("{}/organization_reputations.txt", &folder_name);
+    fs::write(file_name, org_rep_output).expect("Unable to write file");
+
+    write_results(sc, &folder_name, participants, organizations, &visibility, &faults);
+
+    // an independent auditor recomputes the reputation maps from the ledger
+    // to check that what the participants hold is consistent with it. Users who only
+    // learned about some of the interactions cannot be held to the whole ledger.
+    if !streams || sc.visibility.is_some() {
+        return Ok((folder_name, ran_fully));
+    }
+    if print {
+        println!("Auditing the organizations' channels:");
+    }
+    let (readers, resolver) = ledger.readers();
+    let report = audit::audit_simulation(
+        &sc.node_url, participants, organizations, org_trust, readers, resolver
+    ).await?;
+    if print {
+        println!("-- {} findings\n", report.findings.len());
+    }
+    let file_name = format!("{}/audit_report.txt", &folder_name);
+    fs::write(file_name, audit::audit_report_string(&report)).expect("Unable to write file");
+
+    return Ok((folder_name, ran_fully));
+}
+
+// The generator of a run. It is derived from the seed of the simulation and the run, rather
+// than carried over from the run before, so that a simulation resumed from a checkpoint
+// makes the same choices as one that was never interrupted.
+pub fn run_rand_gen(run_seed: u64, run: usize) -> StdRng {
+    return StdRng::seed_from_u64(run_seed ^ (run as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+}
+
+// Runs a single iteration of a simualtion on the ledger. Returns false if the
+// run did not end in an interaction.
+pub async fn engine_iteration(
+    sc: &SimulationConfig,
+    ledger: &mut Ledger,
+    organizations: &mut Vec<OrganizationIdentity>,
+    mut participants: &mut Vec<UserIdentity>,
+    witness_selection: &dyn WitnessSelection,
+    lazy_method: LazyMethod,
+    org_trust: &Option<OrgTrustMatrix>,
+    observer_index: &ObserverIndex,
+    rand_gen: &mut StdRng,
+    first_hand: &mut BTreeMap<usize, HashMap<String, bool>>,
+    visibility: &mut Visibility,
+    faults: &mut FaultInjector,
+    run: usize,
+    folder_name: &str,
+    print: bool
+) -> Result<bool> {
+
+    //--------------------------------------------------------------
+    // GENERATE GROUPS OF TRANSACATING NODES AND WITNESSES
+    //--------------------------------------------------------------
+
+    let gen_op = generate_participants_and_witnesses(
+        &mut participants,
+        sc.average_proximity,
+        sc.witness_floor,
+        sc.witness_ceiling,
+        witness_selection,
+        rand_gen,
+        sc.max_tries.unwrap_or(DEFAULT_MAX_TRIES),
+        print
+    )?;
+
+    let (mut participant_clients, mut witness_clients, checkout) = match gen_op{
+        None => {
+            return Ok(false);
+        },
+        Some(x) => x
+    };
+
+    //--------------------------------------------------------------
+    // GET THE ORGANIZATION OF THE INITIATING TRANSACTING NODE [0]
+    //--------------------------------------------------------------
+
+    // get orgs' pubkey and find the org with that pubkey
+    let init_tn_org_pk = &participant_clients[0].id_info.org_cert.org_pubkey;
+    let org_index = get_index_org_with_pubkey(&organizations, init_tn_org_pk);
+    if print {
+        println!("\nRun under organization {}\n", organizations[org_index].identity.id_info.org_cert.client_pubkey);
+    }
+
+    //--------------------------------------------------------------
+    // GENERATE CONTRACT
+    //--------------------------------------------------------------
+
+    if print {
+        println!("Generating contract:");
+    }
+    let compensation = sc.economy
+        .as_ref()
+        .map(|e| contract_compensation(e, participant_clients.len(), witness_clients.len()));
+    let contract = generate_contract::generate_exchange_contract(
+        &mut participant_clients,
+        organizations[org_index].ann_msg.clone().unwrap(),
+        compensation
+    )?;
+    if print {
+        println!("-- Contract generated\n");
+    }
+
+    //--------------------------------------------------------------
+    // PERFORM THE INTERACTION WITH CONTRACT
+    //--------------------------------------------------------------
+
+    // the actors exchange their messages directly, so they only stand in for the quick backend
+    // (see engine_setup)
+    let interaction_result = match sc.actors {
+        true  => actor_interaction(
+            contract.clone(),
+            &mut participant_clients,
+            &mut witness_clients,
+            &mut organizations[org_index],
+            lazy_method,
+            run,
+            print,
+            faults
+        ).await?,
+        false => interaction(
+            contract.clone(),
+            &mut participant_clients,
+            &mut witness_clients,
+            &mut organizations[org_index],
+            lazy_method,
+            run,
+            print,
+            ledger,
+            faults,
+            rand_gen
+        ).await?
+    };
+
+    let (tn_honesty, wn_honesty, msgs, arrivals) = match interaction_result {
+        Some(x) => x,
+        // nothing of the interaction is on the channel, so there is nothing to verify or process
+        None if faults.take_abandoned() => {
+            println!("The interaction was given up because of a fault");
+            checkout.put_back(participants, participant_clients, witness_clients);
+            return Ok(false);
+        },
+        None => {
+            println!(
+                "The average reputation of the participants does not satisfy the organizations threshold"
+            );
+            // the participants missed out on the interaction because of their reputations
+            for part in participant_clients.iter_mut() {
+                deny(part);
+            }
+            checkout.put_back(participants, participant_clients, witness_clients);
+            return Ok(false);
+        }
+    };
+    if let Some(e) = &sc.economy {
+        settle_interaction(&contract, e.cheating_gain, &mut participant_clients, &mut witness_clients, &tn_honesty);
+    }
+
+    // the participants remember how the others behaved
+    first_hand.insert(run, first_hand_knowledge(&participant_clients, &tn_honesty));
+    let involved: Vec<String> = participant_clients
+        .iter()
+        .chain(witness_clients.iter())
+        .map(|u| u.id_info.org_cert.client_pubkey.clone())
+        .collect();
+    let host_org = participant_clients[0].id_info.org_cert.org_pubkey.clone();
+
+    // put the particpants back where they were in the original array
+    checkout.put_back(participants, participant_clients, witness_clients);
+
+    // decide who learns about the interaction
+    if let Some(config) = &sc.visibility {
+        let keyload = organizations[org_index].policy.keyload_scope();
+        visibility.record_interaction(config, run, &involved, &host_org, &keyload, participants.iter(), rand_gen);
+    }
+
+    //--------------------------------------------------------------
+    // SAVE THE OUTPUT TO FILE
+    //--------------------------------------------------------------
+
+    let file_name = format!("{}/output_{}", folder_name, run);
+    fs::write(file_name, interaction_output_string(&tn_honesty, &wn_honesty, &msgs, &arrivals)).expect("Unable to write file");
+
+    //--------------------------------------------------------------
+    // ALL PARTICIPANTS NOW UPDATE THEIR reputation SCORES BY
+    // PROCESSING THE LATEST INTERACTION
+    //--------------------------------------------------------------
+
+    // participants and organizations update their reputation scores of each other
+    process_interactions(
+        participants,
+        organizations,
+        observer_index,
+        &vec![msgs],
+        &vec![arrivals],
+        &vec![first_hand[&run].clone()],
+        &vec![visibility.observers.get(&run).cloned()],
+        org_trust
+    );
+
+    return Ok(true);
+}
+
+// The honesty of the participants and witnesses of an interaction, the statements
+// that broke the rules (see statement_rules.rs) and its messages
+pub fn interaction_output_string(
+    tn_honesty: &Vec<bool>,
+    wn_honesty: &Vec<bool>,
+    msgs: &Vec<MessageAndPubkey>,
+    arrivals: &Vec<u32>
+) -> String {
+    let mut output: String = String::new();
+    let info = format!("TN honesty {:?}\nWN honesty: {:?}\n\n", tn_honesty, wn_honesty);
+    output.push_str(&info);
+
+    let report = check_interaction(msgs, arrivals);
+    let breaches = format!(
+        "Late statements: {:?}\nRepeated statements: {:?}\nEquivocators: {:?}\n\n",
+        report.late, report.duplicates, report.equivocators
+    );
+    output.push_str(&breaches);
+
+    // print the message and then the pubkey of its sender
+    for msg in msgs.iter() {
+        output.push_str(&format!("Message {:?}\n\n", msg));
+    }
+    return output;
+}
+
+// Writes the reputations the users end up with, and the summaries the simulation parameters ask for
+pub fn write_results(
+    sc: &SimulationConfig,
+    folder_name: &str,
+    participants: &Vec<UserIdentity>,
+    organizations: &Vec<OrganizationIdentity>,
+    visibility: &Visibility,
+    faults: &FaultInjector
+) {
+    // write all of the reputation maps to file, next to their did public key
+    let mut output: String = String::new();
+    for part in participants.iter() {
+        let pk = format!("{}\n", part.id_info.org_cert.client_pubkey);
+        let map = format!("{}\n\n", reputation_scores_string(part));
+        output.push_str(&pk);
+        output.push_str(&map);
+    }
+    let file_name = format!("{}/reputation_maps.txt", folder_name);
+    fs::write(file_name, output).expect("Unable to write file");
+
+    let file_name = format!("{}/reputation_confidences.txt", folder_name);
+    fs::write(file_name, reputation_confidences_string(participants)).expect("Unable to write file");
+
+    if sc.global_trust.is_some() {
+        let file_name = format!("{}/global_reputations.txt", folder_name);
+        fs::write(file_name, global_reputations_string(participants)).expect("Unable to write file");
+    }
+
+    if sc.role_reputation {
+        let file_name = format!("{}/participant_reputations.txt", folder_name);
+        fs::write(file_name, role_reputations_string(participants, Role::Participant)).expect("Unable to write file");
+        let file_name = format!("{}/witness_reputations.txt", folder_name);
+        fs::write(file_name, role_reputations_string(participants, Role::Witness)).expect("Unable to write file");
+    }
+
+    if sc.recommendation.is_some() {
+        let file_name = format!("{}/recommendations.txt", folder_name);
+        fs::write(file_name, recommendation_summary_string(participants)).expect("Unable to write file");
+    }
+
+    if sc.visibility.is_some() {
+        let file_name = format!("{}/visibility.txt", folder_name);
+        fs::write(file_name, visibility_summary_string(visibility)).expect("Unable to write file");
+    }
+
+    if let Some(economy) = &sc.economy {
+        let file_name = format!("{}/utilities.txt", folder_name);
+        fs::write(file_name, utilities_string(participants, economy)).expect("Unable to write file");
+        let file_name = format!("{}/strategy_utilities.txt", folder_name);
+        fs::write(file_name, strategy_utilities_string(participants)).expect("Unable to write file");
+    }
+
+    if sc.faults.is_some() {
+        let file_name = format!("{}/faults.txt", folder_name);
+        fs::write(file_name, faults_summary_string(faults)).expect("Unable to write file");
+    }
+
+    let file_name = format!("{}/policy_decisions.txt", folder_name);
+    fs::write(file_name, policy_decisions_string(organizations)).expect("Unable to write file");
+}
+
+#[cfg(test)]
+fn conformance_config(faults: Option<crate::witness_rep::implementation::faults::FaultConfig>) -> SimulationConfig {
+    use crate::witness_rep::implementation::witness_abuse::{AbusiveWitness, WitnessAbuse};
+    return SimulationConfig {
+        node_url: String::from("http://localhost:14265"),
+        num_users: 8,
+        average_proximity: 0.8,
+        witness_floor: 2,
+        runs: 12,
+        reliability: vec![1.0, 0.9, 0.3, 0.7, 1.0, 0.5, 0.8, 0.2],
+        user_reputation_threshold: vec![0.1; 8],
+        user_default_reputation: vec![0.5; 8],
+        user_organizations: vec![0,0,0,0,1,1,1,1],
+        organization_reputation_threshold: vec![0.1, 0.2],
+        organization_default_reputation: vec![0.5; 2],
+        witness_ceiling: Some(3),
+        faults: faults,
+        witness_abuse: Some(vec![
+            AbusiveWitness { user: 3, abuse: WitnessAbuse::Repeat },
+            AbusiveWitness { user: 6, abuse: WitnessAbuse::CopyLate }
+        ]),
+        ..Default::default()
+    };
+}
+
+// Runs the simulation on the backend into a fresh directory, from the same seed and clock as the others
+#[cfg(test)]
+async fn run_conformance(sc: &SimulationConfig, backend: Backend, name: &str, clock: u32) -> String {
+    let run_dir = std::env::temp_dir().join(name);
+    let _ = fs::remove_dir_all(&run_dir);
+
+    let mut options = EngineOptions::new(backend, name, false);
+    options.seed = Some(46);
+    options.clock = Some(clock);
+    options.run_dir = Some(String::from(run_dir.to_str().unwrap()));
+    let (folder_name, _) = run(sc, &options).await.unwrap();
+    return folder_name;
+}
+
+// The reputation map of each user, whose entries are sorted as an upstream map is written in any order
+#[cfg(test)]
+fn read_reputation_maps(folder_name: &str) -> Vec<Vec<String>> {
+    let maps = fs::read_to_string(format!("{}/reputation_maps.txt", folder_name)).unwrap();
+    return maps
+        .split("\n\n")
+        .map(|map| map.trim())
+        .filter(|map| map.len() > 0)
+        .map(|map| {
+            let mut lines: Vec<String> = map.lines().map(String::from).collect();
+            lines[1..].sort();
+            lines
+        })
+        .collect();
+}
+
+// The fault setups the backends are compared under: none, and every fault at once
+#[cfg(test)]
+fn conformance_faults() -> Vec<Option<crate::witness_rep::implementation::faults::FaultConfig>> {
+    use crate::witness_rep::implementation::faults::FaultConfig;
+    return vec![
+        None,
+        Some(FaultConfig {
+            drop_packet: 0.2,
+            delay_packet: 0.2,
+            witness_offline: 0.2,
+            keyload_failure: 0.1,
+            participant_crash: 0.1,
+            node_error: 0.1,
+            max_delay: None
+        })
+    ];
+}
+
+// Checks that two runs of the simulation, from the same seed, give the same results
+#[cfg(test)]
+fn assert_conformance(sc: &SimulationConfig, expected: &str, actual: &str) {
+    // the honesty of the users, the breaches of the statement rules and the messages of each run
+    for run in 0..sc.runs {
+        let output_name = format!("output_{}", run);
+        assert_eq!(
+            fs::read_to_string(format!("{}/{}", expected, output_name)).ok(),
+            fs::read_to_string(format!("{}/{}", actual, output_name)).ok(),
+            "{} differs between the backends", output_name
+        );
+    }
+    assert_eq!(read_reputation_maps(expected), read_reputation_maps(actual));
+    assert_eq!(
+        fs::read_to_string(format!("{}/organization_reputations.txt", expected)).unwrap(),
+        fs::read_to_string(format!("{}/organization_reputations.txt", actual)).unwrap()
+    );
+}
+
+// The in-memory ledger verifies and parses each branch as Streams does, so it must give the
+// TSGs exactly the messages the quick backend hands them, and the same seed must lead both
+// through the same interactions. This only compares the in-memory and quick backends. Streams
+// is compared by test_streams_conformance, which needs a node and so is ignored by default:
+// a plain cargo test, as CI runs it, never checks the Streams backend against the others.
+#[tokio::test]
+pub async fn test_backend_conformance() {
+    let clock = generate_sigs::get_timeout(0);
+    for (k, faults) in conformance_faults().into_iter().enumerate() {
+        let sc = conformance_config(faults);
+        let in_memory = run_conformance(&sc, Backend::InMemory, &format!("conformance_in_memory_{}", k), clock).await;
+        let quick = run_conformance(&sc, Backend::Quick, &format!("conformance_quick_{}", k), clock).await;
+        assert_conformance(&sc, &in_memory, &quick);
+    }
+}
+
+// Streams must give the same results as the quick backend from the same seed. It needs a
+// node, whose url is read from NODE_URL, so it only runs when asked for:
+//      NODE_URL=http://localhost:14265 cargo test -- --ignored test_streams_conformance
+#[tokio::test]
+#[ignore]
+pub async fn test_streams_conformance() {
+    let node_url = match std::env::var("NODE_URL") {
+        Ok(url) => url,
+        Err(_)  => panic!("NODE_URL must be set to the url of a node to run the Streams backend against")
+    };
+    let clock = generate_sigs::get_timeout(0);
+    for (k, faults) in conformance_faults().into_iter().enumerate() {
+        let sc = SimulationConfig {
+            node_url: node_url.clone(),
+            ..conformance_config(faults)
+        };
+        let streams = run_conformance(&sc, Backend::Streams, &format!("conformance_streams_{}", k), clock).await;
+        let quick = run_conformance(&sc, Backend::Quick, &format!("conformance_quick_streams_{}", k), clock).await;
+        assert_conformance(&sc, &streams, &quick);
+    }
+}
+
+// A simulation resumed from its last checkpoint must end with the results it had when it ran
+// without interruption. The simulation runs through once, checkpointing along the way, and is
+// then resumed as if it had crashed right after the checkpoint. Checkpoints are only taken on
+// Streams, so this needs a node like test_streams_conformance:
+//      NODE_URL=http://localhost:14265 cargo test -- --ignored test_resume_conformance
+#[tokio::test]
+#[ignore]
+pub async fn test_resume_conformance() {
+    use crate::witness_rep::iota_did::keystore::KeystoreConfig;
+
+    let node_url = match std::env::var("NODE_URL") {
+        Ok(url) => url,
+        Err(_)  => panic!("NODE_URL must be set to the url of a node to run the Streams backend against")
+    };
+    let clock = generate_sigs::get_timeout(0);
+    let passphrase = "resume conformance";
+    for (k, faults) in conformance_faults().into_iter().enumerate() {
+        let keystore_path = std::env::temp_dir().join(format!("resume_keystore_{}.json", k));
+        let _ = fs::remove_file(&keystore_path);
+        let sc = SimulationConfig {
+            node_url: node_url.clone(),
+            keystore: Some(KeystoreConfig {
+                path: String::from(keystore_path.to_str().unwrap()),
+                passphrase: String::from(passphrase)
+            }),
+            checkpoint_interval: Some(8),
+            ..conformance_config(faults)
+        };
+        let resumed = run_conformance(&sc, Backend::Streams, &format!("resume_{}", k), clock).await;
+
+        // the results of the uninterrupted run are kept aside, as resuming overwrites them
+        let uninterrupted = format!("{}_uninterrupted", resumed);
+        let _ = fs::remove_dir_all(&uninterrupted);
+        fs::create_dir_all(&uninterrupted).unwrap();
+        for entry in fs::read_dir(&resumed).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_file() {
+                fs::copy(&path, format!("{}/{}", uninterrupted, path.file_name().unwrap().to_str().unwrap())).unwrap();
+            }
+        }
+
+        checkpoint::resume(&resumed, passphrase, true, false).await.unwrap();
+        assert_conformance(&sc, &uninterrupted, &resumed);
+        assert_eq!(
+            fs::read_to_string(format!("{}/faults.txt", uninterrupted)).ok(),
+            fs::read_to_string(format!("{}/faults.txt", resumed)).ok()
+        );
+    }
+}
+
+// The actors cannot run on a ledger, so asking for them on one is an error rather than ignored
+#[tokio::test]
+pub async fn test_actors_need_quick_backend() {
+    let sc = SimulationConfig {
+        actors: true,
+        ..conformance_config(None)
+    };
+    let options = EngineOptions::new(Backend::InMemory, "actors_in_memory", false);
+    assert!(engine_setup(&sc, &options).await.is_err());
+}
+
+// An invalid parameter fails the setup with an error instead of aborting the process
+#[tokio::test]
+pub async fn test_invalid_config_is_an_error() {
+    let sc = SimulationConfig {
+        witness_ceiling: Some(1),
+        ..conformance_config(None)
+    };
+    let options = EngineOptions::new(Backend::Quick, "invalid_config", false);
+    assert!(engine_setup(&sc, &options).await.is_err());
+}
@@ -0,0 +1,425 @@
+use crate::witness_rep::{
+    implementation::generate_contract,
+    implementation::{
+        interaction::interaction,
+        actor_interaction::actor_interaction,
+        ledger::Backend,
+        reputation_model::{update_reputations, user_reputations_string},
+        global_trust::{update_global_trust, is_due},
+        visibility::{Visibility, observes},
+        witness_selection::new_witness_selection,
+        faults::FaultInjector,
+        economy::{contract_compensation, settle_interaction, deny},
+        organization_reputation::{learn_memberships, organization_reputations_string},
+        scheduler::{Scheduler, SchedulerConfig, SimTime, Event, interarrival, delay},
+        user_and_organization::{UserIdentity, get_index_org_with_pubkey}
+    },
+    simulation::{
+        SimulationConfig, DEFAULT_MAX_TRIES, generate_participants_and_witnesses,
+        read_user_pubkeys, changed_user_pubkeys, apply_reliability_changes, first_hand_knowledge
+    },
+    engine::{EngineOptions, engine_setup, write_results},
+    trust_score_generators::select_tsg::{TsgBatch, run_user_tsg_batched}
+};
+
+use wb_reputation_system::data_types::{
+    event_protocol_messages::event_protocol_messages::{Message, Contract},
+    tsg_data_types::message::MessageAndPubkey
+};
+
+use iota_streams::core::Result;
+use rand::Rng;
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Arc;
+
+// An interaction that has started and not yet ended. Its participants and witnesses
+// are busy, so they are kept out of the pool of free users until it ends.
+struct InFlight {
+    participants: Vec<UserIdentity>,
+    witnesses: Vec<UserIdentity>,
+    contract: Contract,
+    tn_honesty: Vec<bool>,
+    wn_honesty: Vec<bool>,
+    // the interaction message, followed by the statements in the order they arrived. Each
+    // message keeps the time the ledger says it reached the channel, relative to the contract.
+    msgs: Vec<(MessageAndPubkey, u32)>,
+    // the statements still on their way, by the position of their witness
+    statements: HashMap<usize, (MessageAndPubkey, u32)>,
+    // sent by the participants when the interaction ends
+    compensation: Vec<(MessageAndPubkey, u32)>,
+}
+
+// The messages of an ended interaction, kept until every observer has processed them,
+// and the verdicts given on them so far
+struct Ended {
+    batch: TsgBatch,
+    first_hand: HashMap<String, bool>,
+    unprocessed: usize,
+}
+
+// A version of the quick simulation in which the interactions overlap in simulated time.
+// Interactions start at random, and last for the duration of the scheduler parameter, during
+// which their participants and witnesses cannot take part in another. The witness statements
+// arrive some time after the start, and are left out if the interaction has ended by then.
+// Every observer processes an ended interaction when its messages reach them, so the users
+// always choose counterparties and witnesses by slightly stale reputations.
+//
+// The runs parameter is the number of interactions started. Besides the output of the quick
+// simulation, the number of interactions completed, the statements that arrived late, the
+// most interactions in progress at once and the simulated time taken are written to schedule.txt.
+pub async fn event_simulation(
+    sc: SimulationConfig,
+    print: bool
+) -> Result<(String, bool)> {
+
+    let timing: SchedulerConfig = match &sc.scheduler {
+        Some(timing) => timing.clone(),
+        None         => panic!("The event simulation needs the 'scheduler' parameter!")
+    };
+
+    // the interactions are played out without a ledger, and their messages delivered by the scheduler
+    let options = EngineOptions::new(Backend::Quick, "Event emmulation run", print);
+    let setup = engine_setup(&sc, &options).await?;
+    let folder_name = setup.folder_name;
+    let mut ledger = setup.ledger;
+    let mut organizations = setup.organizations;
+    let mut pool = setup.participants;
+    let org_trust = setup.org_trust;
+    let lazy_methods = setup.lazy_methods;
+    let mut rand_gen = setup.rand_gen;
+
+    // users' views of the organizations are tracked after every interaction
+    let mut org_pubkeys: Vec<(usize, String)> = setup.org_indices
+        .iter()
+        .cloned()
+        .zip(organizations.iter().map(|org| org.identity.id_info.org_cert.org_pubkey.clone()))
+        .collect();
+    org_pubkeys.sort();
+    let org_pubkeys_list: Vec<String> = org_pubkeys.into_iter().map(|(_, pk)| pk).collect();
+    let mut org_rep_output: String = String::new();
+
+    // the users whose reliability changes, and the reputations they have after each interaction
+    let user_pks = read_user_pubkeys(&folder_name)?;
+    let changed_pks = changed_user_pubkeys(&sc.reliability_changes, &user_pks);
+    let mut changes_output: String = String::new();
+    let mut visibility = Visibility::default();
+    let witness_selection = new_witness_selection(&sc.witness_selection);
+    let mut faults = FaultInjector::seeded(&sc.faults, rand_gen.gen());
+
+    let mut in_flight: HashMap<usize, InFlight> = HashMap::new();
+    let mut ended: HashMap<usize, Ended> = HashMap::new();
+    let mut started: usize = 0;
+    let mut completed: usize = 0;
+    let mut late_statements: usize = 0;
+    let mut max_concurrent: usize = 0;
+
+    let mut scheduler: Scheduler<Event> = Scheduler::new();
+    if sc.runs > 0 {
+        scheduler.schedule(0, Event::Start);
+    }
+
+    let mut ran_fully = true;
+    while let Some((time, event)) = scheduler.next() {
+        match event {
+            Event::Start => {
+                let run = started;
+                started += 1;
+                if started < sc.runs {
+                    scheduler.schedule(interarrival(&timing, &mut rand_gen), Event::Start);
+                }
+                println!("\n\n\n---------------------STARTING INTERACTION {} AT {}---------------------", run, time);
+                apply_reliability_changes(&sc.reliability_changes, run, &mut pool, &user_pks);
+                for interaction in in_flight.values_mut() {
+                    apply_reliability_changes(&sc.reliability_changes, run, &mut interaction.participants, &user_pks);
+                    apply_reliability_changes(&sc.reliability_changes, run, &mut interaction.witnesses, &user_pks);
+                }
+
+                //--------------------------------------------------------------
+                // GENERATE GROUPS OF TRANSACATING NODES AND WITNESSES
+                // FROM THE USERS WHO ARE FREE
+                //--------------------------------------------------------------
+
+                let gen_op = generate_participants_and_witnesses(
+                    &mut pool,
+                    sc.average_proximity,
+                    sc.witness_floor,
+                    sc.witness_ceiling,
+                    witness_selection.as_ref(),
+                    &mut rand_gen,
+                    sc.max_tries.unwrap_or(DEFAULT_MAX_TRIES),
+                    print
+                )?;
+
+                // the pool only holds the free users, in no particular order, so they
+                // are returned to it when the interaction ends instead of being put back
+                let (mut participant_clients, mut witness_clients, _) = match gen_op{
+                    None => {
+                        println!("FAILED TO RUN");
+                        ran_fully = false;
+                        continue;
+                    },
+                    Some(x) => x
+                };
+
+                //--------------------------------------------------------------
+                // GENERATE CONTRACT AND PERFORM THE INTERACTION
+                //--------------------------------------------------------------
+
+                let init_tn_org_pk = &participant_clients[0].id_info.org_cert.org_pubkey;
+                let org_index = get_index_org_with_pubkey(&organizations, init_tn_org_pk);
+
+                let compensation = sc.economy
+                    .as_ref()
+                    .map(|e| contract_compensation(e, participant_clients.len(), witness_clients.len()));
+                let contract = generate_contract::generate_exchange_contract(
+                    &mut participant_clients,
+                    organizations[org_index].ann_msg.clone().unwrap(),
+                    compensation
+                )?;
+
+                let op_ret = match sc.actors {
+                    true  => actor_interaction(
+                        contract.clone(),
+                        &mut participant_clients,
+                        &mut witness_clients,
+                        &mut organizations[org_index],
+                        lazy_methods[run].clone(),
+                        run,
+                        print,
+                        &mut faults
+                    ).await?,
+                    false => interaction(
+                        contract.clone(),
+                        &mut participant_clients,
+                        &mut witness_clients,
+                        &mut organizations[org_index],
+                        lazy_methods[run].clone(),
+                        run,
+                        print,
+                        &mut ledger,
+                        &mut faults,
+                        &mut rand_gen
+                    ).await?
+                };
+
+                let (tn_honesty, wn_honesty, msgs, arrivals) = match op_ret {
+                    // the interaction was given up because of a fault, through no fault of the participants
+                    None if faults.take_abandoned() => {
+                        pool.append(&mut witness_clients);
+                        pool.append(&mut participant_clients);
+                        ran_fully = false;
+                        continue;
+                    },
+                    None => {
+                        // the participants missed out on the interaction because of their reputations
+                        for part in participant_clients.iter_mut() {
+                            deny(part);
+                        }
+                        pool.append(&mut witness_clients);
+                        pool.append(&mut participant_clients);
+                        ran_fully = false;
+                        continue;
+                    },
+                    Some(x) => x
+                };
+
+                //--------------------------------------------------------------
+                // THE STATEMENTS TRAVEL SEPARATELY, AND THE COMPENSATION
+                // IS ONLY SENT ONCE THE INTERACTION ENDS
+                //--------------------------------------------------------------
+
+                let mut interaction = InFlight {
+                    participants: participant_clients,
+                    witnesses: witness_clients,
+                    contract: contract,
+                    tn_honesty: tn_honesty,
+                    wn_honesty: wn_honesty,
+                    msgs: Vec::new(),
+                    statements: HashMap::new(),
+                    compensation: Vec::new()
+                };
+                // statements made after the first compensation were made after the deadline,
+                // so they stay behind it whenever they arrive
+                for (k, msg) in msgs.into_iter().enumerate() {
+                    let arrival = arrivals.get(k).cloned().unwrap_or(0);
+                    if interaction.compensation.len() > 0 {
+                        interaction.compensation.push((msg, arrival));
+                        continue;
+                    }
+                    match &msg.message {
+                        Message::WitnessStatement { .. } => {
+                            let witness = interaction.statements.len();
+                            interaction.statements.insert(witness, (msg, arrival));
+                        },
+                        Message::ApplicationMsg(_) => interaction.compensation.push((msg, arrival)),
+                        _ => interaction.msgs.push((msg, arrival))
+                    }
+                }
+                for witness in interaction.statements.keys() {
+                    let arrival: SimTime = delay(timing.max_statement_delay, &mut rand_gen);
+                    scheduler.schedule(arrival, Event::Statement { interaction: run, witness: *witness });
+                }
+                scheduler.schedule(timing.duration, Event::End { interaction: run });
+
+                in_flight.insert(run, interaction);
+                max_concurrent = max_concurrent.max(in_flight.len());
+            },
+            Event::Statement { interaction, witness } => {
+                match in_flight.get_mut(&interaction) {
+                    Some(in_progress) => {
+                        if let Some(statement) = in_progress.statements.remove(&witness) {
+                            in_progress.msgs.push(statement);
+                        }
+                    },
+                    None => late_statements += 1
+                }
+            },
+            Event::End { interaction: run } => {
+                let mut interaction = match in_flight.remove(&run) {
+                    Some(interaction) => interaction,
+                    None => continue
+                };
+                interaction.msgs.append(&mut interaction.compensation);
+                if let Some(e) = &sc.economy {
+                    settle_interaction(
+                        &interaction.contract,
+                        e.cheating_gain,
+                        &mut interaction.participants,
+                        &mut interaction.witnesses,
+                        &interaction.tn_honesty
+                    );
+                }
+
+                // the participants remember how the others behaved
+                let first_hand = first_hand_knowledge(&interaction.participants, &interaction.tn_honesty);
+                let involved: Vec<String> = interaction.participants
+                    .iter()
+                    .chain(interaction.witnesses.iter())
+                    .map(|u| u.id_info.org_cert.client_pubkey.clone())
+                    .collect();
+                let host_org = interaction.participants[0].id_info.org_cert.org_pubkey.clone();
+                let org_index = get_index_org_with_pubkey(&organizations, &host_org);
+
+                // the participants and witnesses are free again
+                pool.append(&mut interaction.witnesses);
+                pool.append(&mut interaction.participants);
+
+                // decide who learns about the interaction
+                let observers = match &sc.visibility {
+                    None         => None,
+                    Some(config) => {
+                        let keyload = organizations[org_index].policy.keyload_scope();
+                        Some(visibility.record_interaction(
+                            config, run, &involved, &host_org, &keyload, all_users(&pool, &in_flight), &mut rand_gen
+                        ))
+                    }
+                };
+
+                //--------------------------------------------------------------
+                // SAVE THE OUTPUT TO FILE
+                //--------------------------------------------------------------
+
+                let mut output: String = String::new();
+                let mut missing: Vec<&usize> = interaction.statements.keys().collect();
+                missing.sort();
+                let info = format!(
+                    "TN honesty {:?}\nWN honesty: {:?}\nMissing statements: {:?}\n\n",
+                    interaction.tn_honesty, interaction.wn_honesty, missing
+                );
+                output.push_str(&info);
+                for (msg, _) in interaction.msgs.iter() {
+                    output.push_str(&format!("Message {:?}\n\n", msg));
+                }
+                let file_name = format!("{}/{}", &folder_name, &format!("output_{}", run));
+                fs::write(file_name, output).expect("Unable to write file");
+
+                //--------------------------------------------------------------
+                // THE MESSAGES REACH EACH OBSERVER AFTER A DELAY
+                //--------------------------------------------------------------
+
+                let (msgs, arrivals): (Vec<MessageAndPubkey>, Vec<u32>) = interaction.msgs.into_iter().unzip();
+                let recipients: Vec<String> = all_users(&pool, &in_flight)
+                    .map(|u| u.id_info.org_cert.client_pubkey.clone())
+                    .filter(|pk| observes(&observers, pk))
+                    .collect();
+                for observer in recipients.iter() {
+                    let propagation: SimTime = delay(timing.max_propagation_delay, &mut rand_gen);
+                    scheduler.schedule(propagation, Event::Process { interaction: run, observer: observer.clone() });
+                }
+                ended.insert(run, Ended {
+                    batch: TsgBatch::new(Arc::new(msgs), arrivals),
+                    first_hand: first_hand,
+                    unprocessed: recipients.len()
+                });
+                completed += 1;
+
+                // the users who are busy are left out until the next interaction ends
+                org_rep_output.push_str(&organization_reputations_string(run, &pool, &org_pubkeys_list));
+                if let Some(gt) = is_due(&sc.global_trust, run) {
+                    update_global_trust(&mut pool, gt);
+                }
+                if changed_pks.len() > 0 {
+                    changes_output.push_str(&user_reputations_string(run, &pool, &changed_pks));
+                }
+            },
+            Event::Process { interaction, observer } => {
+                let processed = match ended.get_mut(&interaction) {
+                    None => continue,
+                    Some(processed) => processed
+                };
+                if let Some(user) = find_user(&mut pool, &mut in_flight, &observer) {
+                    let (tn_verdicts, wn_verdicts) = run_user_tsg_batched(
+                        user, &mut processed.batch, &org_trust, &processed.first_hand
+                    );
+                    update_reputations(user, tn_verdicts, wn_verdicts);
+                    learn_memberships(&mut user.id_info, &processed.batch.msgs);
+                }
+                processed.unprocessed -= 1;
+                if processed.unprocessed == 0 {
+                    ended.remove(&interaction);
+                }
+            }
+        }
+    }
+
+    if changed_pks.len() > 0 {
+        let file_name = format!("{}/reliability_changes.txt", &folder_name);
+        fs::write(file_name, changes_output).expect("Unable to write file");
+    }
+    let file_name = format!("{}/organization_reputations.txt", &folder_name);
+    fs::write(file_name, org_rep_output).expect("Unable to write file");
+
+    let schedule = format!(
+        "interactions: {}\nfailed: {}\nlate_statements: {}\nmax_concurrent: {}\nduration: {}\n",
+        completed, started - completed, late_statements, max_concurrent, scheduler.now()
+    );
+    let file_name = format!("{}/schedule.txt", &folder_name);
+    fs::write(file_name, schedule).expect("Unable to write file");
+
+    // every interaction has ended by now, so all of the users are back in the pool
+    write_results(&sc, &folder_name, &pool, &organizations, &visibility, &faults);
+
+    return Ok((folder_name, ran_fully));
+}
+
+// Every user, whether free or busy in an interaction
+fn all_users<'a>(
+    pool: &'a Vec<UserIdentity>,
+    in_flight: &'a HashMap<usize, InFlight>
+) -> impl Iterator<Item = &'a UserIdentity> {
+    return pool
+        .iter()
+        .chain(in_flight.values().flat_map(|i| i.participants.iter().chain(i.witnesses.iter())));
+}
+
+fn find_user<'a>(
+    pool: &'a mut Vec<UserIdentity>,
+    in_flight: &'a mut HashMap<usize, InFlight>,
+    pubkey: &str
+) -> Option<&'a mut UserIdentity> {
+    return pool
+        .iter_mut()
+        .chain(in_flight.values_mut().flat_map(|i| i.participants.iter_mut().chain(i.witnesses.iter_mut())))
+        .find(|u| u.id_info.org_cert.client_pubkey == pubkey);
+}
@@ -0,0 +1,518 @@
+use crate::witness_rep::implementation::{
+    generate_sigs,
+    user_and_organization::{UserIdentity, OrganizationIdentity},
+    interaction::{lazy_outcome, LazyMethod},
+    organization_policy::InteractionRequest,
+    reputation_model::{Role, check_participant_as},
+    economy::witness_payments,
+    faults::FaultInjector,
+    witness_abuse::witness_statements
+};
+
+use wb_reputation_system::data_types::{
+    event_protocol_messages::{
+        application_constructs::{
+            application_messages::exchange_app_messages::CompensationMsg,
+            application_contracts::utility_types::{PublicKey, WitnessUsers}
+        },
+        event_protocol_messages::{
+            Message, Contract, ArrayOfWnSignitures,
+            ArrayOfIntSignitures, ApplicationMsg, Outcome
+        },
+        signatures::{
+            witness_sig::WitnessSig,
+            interaction_sig::{InteractionSig, ArrayOfWnSignituresBytes},
+            organization_cert::OrganizationCertificate
+        }
+    },
+    tsg_data_types::message::MessageAndPubkey
+};
+
+use iota_streams::{
+    app_channels::api::tangle::PublicKey as ChannelPublicKey,
+    core::Result
+};
+use identity::{
+    did::MethodData,
+    crypto::KeyPair
+};
+use tokio::{
+    sync::{mpsc, oneshot},
+    task::{self, JoinHandle, LocalSet},
+    time::timeout
+};
+use rand::Rng;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// How long the initiating participant waits for the answers to a request before
+/// giving up on the interaction
+pub const RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+const SIG_DURATION: u32 = 60*2; // 2 mins
+
+// The requests a participant answers
+enum ParticipantMsg {
+    /// Sign the interaction, with the witnesses and their signatures
+    Sign {
+        contract: Contract,
+        witnesses: WitnessUsers,
+        witness_sigs: ArrayOfWnSignituresBytes,
+        reply: oneshot::Sender<Option<InteractionSig>>
+    },
+    /// Carry out the contract in front of the witnesses, and reply whether they were honest
+    Act { witnesses: Vec<mpsc::UnboundedSender<WitnessMsg>>, reply: oneshot::Sender<bool> },
+    /// Pay the witnesses the fees of the contract
+    Compensate { contract: Contract, reply: oneshot::Sender<()> },
+}
+
+// The requests a witness answers
+enum WitnessMsg {
+    /// Sign the contract, if the witness accepts the participants
+    Sign { contract: Contract, participants: Vec<String>, reply: oneshot::Sender<Option<WitnessSig>> },
+    /// What the participant at the position did, as seen by the witness
+    Behaviour { participant: usize, honest: bool },
+    /// Publish a statement once the given number of participants have acted, and reply
+    /// whether it was honest
+    Observe { participants: usize, lazy_method: LazyMethod, reply: oneshot::Sender<bool> },
+}
+
+// The requests an organization answers
+enum OrganizationMsg {
+    /// Let the user read the organization's channel
+    Subscribe { channel_pubkey: ChannelPublicKey, org_cert: OrganizationCertificate, reply: oneshot::Sender<bool> },
+    /// Apply the policy to the interaction, and reply whether it is admitted
+    Admit { request: InteractionRequest, run: usize, reply: oneshot::Sender<bool> },
+}
+
+// A version of interaction, without a ledger, in which every participant and witness is a task with a
+// mailbox, deciding for itself how to answer the requests it gets, and the organization
+// answers its own requests alongside. The initiating participant runs the protocol by
+// sending requests and waiting for the answers, and everything the actors publish goes to
+// a shared channel, in the order it is sent. An interaction is abandoned, returning None,
+// if the organization does not admit it, a witness declines to sign, an actor does not
+// answer within RESPONSE_TIMEOUT, or a fault gives it up (see FaultInjector::deliver).
+//
+// The identities are moved into the tasks for the interaction and handed back, in their
+// original order, when it is over. The tasks run on a LocalSet, as the identities hold
+// channel clients which are not Send.
+pub async fn actor_interaction(
+    contract: Contract,
+    participant_ids: &mut Vec<UserIdentity>,
+    witness_ids: &mut Vec<UserIdentity>,
+    organization_id: &mut OrganizationIdentity,
+    lazy_method: LazyMethod,
+    run: usize,
+    print: bool,
+    faults: &mut FaultInjector
+) -> Result<Option<(Vec<bool>, Vec<bool>, Vec<MessageAndPubkey>, Vec<u32>)>> {
+    let local = LocalSet::new();
+    return local.run_until(run_actors(
+        contract, participant_ids, witness_ids, organization_id, lazy_method, run, print, faults
+    )).await;
+}
+
+async fn run_actors(
+    contract: Contract,
+    participant_ids: &mut Vec<UserIdentity>,
+    witness_ids: &mut Vec<UserIdentity>,
+    organization_id: &mut OrganizationIdentity,
+    lazy_method: LazyMethod,
+    run: usize,
+    print: bool,
+    faults: &mut FaultInjector
+) -> Result<Option<(Vec<bool>, Vec<bool>, Vec<MessageAndPubkey>, Vec<u32>)>> {
+    let (organization, organization_mailbox) = mpsc::unbounded_channel();
+    let (channel, mut published) = mpsc::unbounded_channel();
+
+    let participant_certs: Vec<OrganizationCertificate> = participant_ids
+        .iter()
+        .map(|p| p.id_info.org_cert.clone())
+        .collect();
+    let witness_certs: Vec<OrganizationCertificate> = witness_ids
+        .iter()
+        .map(|w| w.id_info.org_cert.clone())
+        .collect();
+
+    //--------------------------------------------------------------
+    // START AN ACTOR FOR EACH PARTICIPANT AND WITNESS
+    //--------------------------------------------------------------
+
+    let mut participants: Vec<mpsc::UnboundedSender<ParticipantMsg>> = Vec::new();
+    let mut participant_handles: Vec<JoinHandle<UserIdentity>> = Vec::new();
+    for (position, id) in participant_ids.drain(..).enumerate() {
+        let (mailbox, receiver) = mpsc::unbounded_channel();
+        participants.push(mailbox);
+        participant_handles.push(task::spawn_local(
+            participant_actor(id, position, receiver, organization.clone(), channel.clone())
+        ));
+    }
+
+    let mut witnesses: Vec<mpsc::UnboundedSender<WitnessMsg>> = Vec::new();
+    let mut witness_handles: Vec<JoinHandle<UserIdentity>> = Vec::new();
+    for id in witness_ids.drain(..) {
+        let (mailbox, receiver) = mpsc::unbounded_channel();
+        witnesses.push(mailbox);
+        witness_handles.push(task::spawn_local(
+            witness_actor(id, receiver, organization.clone(), channel.clone())
+        ));
+    }
+
+    //--------------------------------------------------------------
+    // THE INITIATING PARTICIPANT RUNS THE PROTOCOL WHILE THE
+    // ORGANIZATION ANSWERS ITS REQUESTS
+    //--------------------------------------------------------------
+
+    let request = InteractionRequest {
+        participants: participant_certs.clone(),
+        witnesses: witness_certs
+    };
+    let protocol = async move {
+        let honesty = initiate(
+            contract, request, &participants, &witnesses, &organization,
+            &participant_certs[0].client_pubkey, &channel, lazy_method, run, print
+        ).await;
+
+        // closing the mailboxes lets the actors finish
+        drop(participants);
+        drop(witnesses);
+        drop(organization);
+        drop(channel);
+        honesty
+    };
+    let (honesty, _) = tokio::join!(protocol, organization_actor(organization_id, organization_mailbox, print));
+
+    //--------------------------------------------------------------
+    // THE ACTORS HAND BACK THEIR IDENTITIES
+    //--------------------------------------------------------------
+
+    for handle in participant_handles {
+        participant_ids.push(handle.await?);
+    }
+    for handle in witness_handles {
+        witness_ids.push(handle.await?);
+    }
+
+    let mut messages: Vec<MessageAndPubkey> = Vec::new();
+    while let Some(msg) = published.recv().await {
+        messages.push(msg);
+    }
+
+    // the faults decide which of the published messages reach the channel
+    return Ok(honesty.and_then(|(tn_honesty, wn_honesty)| {
+        faults.deliver(messages).map(|(delivered, arrivals)| (tn_honesty, wn_honesty, delivered, arrivals))
+    }));
+}
+
+// The initiating participant's side of the protocol. Returns the honesty of the
+// participants and the witnesses, or None if the interaction was abandoned.
+async fn initiate(
+    contract: Contract,
+    request: InteractionRequest,
+    participants: &Vec<mpsc::UnboundedSender<ParticipantMsg>>,
+    witnesses: &Vec<mpsc::UnboundedSender<WitnessMsg>>,
+    organization: &mpsc::UnboundedSender<OrganizationMsg>,
+    initiator_pubkey: &str,
+    channel: &mpsc::UnboundedSender<MessageAndPubkey>,
+    lazy_method: LazyMethod,
+    run: usize,
+    print: bool
+) -> Option<(Vec<bool>, Vec<bool>)> {
+    let participant_pks: Vec<String> = request.participants
+        .iter()
+        .map(|cert| cert.client_pubkey.clone())
+        .collect();
+
+    // the organization applies its policy
+    let admitted = ask(organization, |reply| OrganizationMsg::Admit { request, run, reply }).await?;
+    if !admitted {
+        return None;
+    }
+
+    // every witness must sign the contract
+    let witness_sigs: Vec<WitnessSig> = ask_all(witnesses, |reply| WitnessMsg::Sign {
+        contract: contract.clone(),
+        participants: participant_pks.clone(),
+        reply
+    }).await?.into_iter().collect::<Option<Vec<WitnessSig>>>()?;
+    if print {
+        println!("-- Witness signatures collected\n");
+    }
+
+    // then every participant signs the interaction
+    let witness_pks: Vec<PublicKey> = witness_sigs.iter().map(|sig| sig.signer_did_pubkey.clone()).collect();
+    let witness_sigs_bytes: Vec<Vec<u8>> = witness_sigs.iter().map(|sig| sig.signature.clone()).collect();
+    let participant_sigs: Vec<InteractionSig> = ask_all(participants, |reply| ParticipantMsg::Sign {
+        contract: contract.clone(),
+        witnesses: WitnessUsers(witness_pks.clone()),
+        witness_sigs: ArrayOfWnSignituresBytes(witness_sigs_bytes.clone()),
+        reply
+    }).await?.into_iter().collect::<Option<Vec<InteractionSig>>>()?;
+    if print {
+        println!("-- Participant signatures collected\n");
+    }
+
+    let interaction_msg = Message::InteractionMsg {
+        contract: contract.clone(),
+        witnesses: WitnessUsers(witness_pks),
+        witness_sigs: ArrayOfWnSignitures(witness_sigs),
+        interaction_sigs: ArrayOfIntSignitures(participant_sigs),
+    };
+    channel.send(MessageAndPubkey {
+        message: interaction_msg,
+        sender_did: String::from(initiator_pubkey)
+    }).ok()?;
+
+    // the participants act in front of the witnesses, who then make their statements
+    let tn_honesty = ask_all(participants, |reply| ParticipantMsg::Act {
+        witnesses: witnesses.clone(),
+        reply
+    }).await?;
+    let wn_honesty = ask_all(witnesses, |reply| WitnessMsg::Observe {
+        participants: participants.len(),
+        lazy_method: lazy_method.clone(),
+        reply
+    }).await?;
+
+    ask_all(participants, |reply| ParticipantMsg::Compensate {
+        contract: contract.clone(),
+        reply
+    }).await?;
+
+    return Some((tn_honesty, wn_honesty));
+}
+
+async fn participant_actor(
+    id: UserIdentity,
+    position: usize,
+    mut mailbox: mpsc::UnboundedReceiver<ParticipantMsg>,
+    organization: mpsc::UnboundedSender<OrganizationMsg>,
+    channel: mpsc::UnboundedSender<MessageAndPubkey>
+) -> UserIdentity {
+    let subscribed = subscribe(&id, organization).await;
+    let pubkey = id.id_info.org_cert.client_pubkey.clone();
+
+    while let Some(msg) = mailbox.recv().await {
+        match msg {
+            ParticipantMsg::Sign { contract, witnesses, witness_sigs, reply } => {
+                let sig = match subscribed {
+                    true  => participant_sig(&id, contract, witnesses, witness_sigs).ok(),
+                    false => None
+                };
+                let _ = reply.send(sig);
+            },
+            ParticipantMsg::Act { witnesses, reply } => {
+                let honest = acts_honestly(&id);
+                for witness in witnesses.iter() {
+                    let _ = witness.send(WitnessMsg::Behaviour { participant: position, honest: honest });
+                }
+                let _ = reply.send(honest);
+            },
+            ParticipantMsg::Compensate { contract, reply } => {
+                let compensation_msg = CompensationMsg {
+                    payments: witness_payments(&contract)
+                };
+                let _ = channel.send(MessageAndPubkey {
+                    message: Message::ApplicationMsg(ApplicationMsg::ExchangeApplication(compensation_msg)),
+                    sender_did: pubkey.clone()
+                });
+                let _ = reply.send(());
+            }
+        }
+    }
+    return id;
+}
+
+async fn witness_actor(
+    id: UserIdentity,
+    mut mailbox: mpsc::UnboundedReceiver<WitnessMsg>,
+    organization: mpsc::UnboundedSender<OrganizationMsg>,
+    channel: mpsc::UnboundedSender<MessageAndPubkey>
+) -> UserIdentity {
+    let subscribed = subscribe(&id, organization).await;
+    let pubkey = id.id_info.org_cert.client_pubkey.clone();
+
+    // what each participant did, by position, and the request for a statement, which
+    // may come before the witness has seen everyone act
+    let mut behaviour: BTreeMap<usize, bool> = BTreeMap::new();
+    let mut observing: Option<(usize, LazyMethod, oneshot::Sender<bool>)> = None;
+    let mut late_statements: Vec<Vec<bool>> = Vec::new();
+
+    while let Some(msg) = mailbox.recv().await {
+        match msg {
+            WitnessMsg::Sign { contract, participants, reply } => {
+                // witnesses only vouch for interactions between participants they accept
+                let willing = subscribed && participants
+                    .iter()
+                    .all(|pk| check_participant_as(&id, pk, Role::Participant));
+                let sig = match willing {
+                    true  => witness_sig(&id, contract).ok(),
+                    false => None
+                };
+                let _ = reply.send(sig);
+            },
+            WitnessMsg::Behaviour { participant, honest } => {
+                behaviour.insert(participant, honest);
+            },
+            WitnessMsg::Observe { participants, lazy_method, reply } => {
+                observing = Some((participants, lazy_method, reply));
+            }
+        }
+
+        if observing.as_ref().map_or(false, |(participants, _, _)| behaviour.len() >= *participants) {
+            let (_, lazy_method, reply) = observing.take().unwrap();
+            let honest = acts_honestly(&id);
+            let outcomes: Vec<bool> = behaviour
+                .values()
+                .map(|participant_honest| match honest {
+                    true  => *participant_honest,
+                    false => lazy_outcome(&lazy_method, &mut rand::thread_rng())
+                })
+                .collect();
+            // a witness does not see the statements of the others, so an abusive one
+            // has nobody to copy and falls back on their own outcome
+            let (on_time, mut late) = witness_statements(&id.id_info.state.witness_abuse, &outcomes, &Vec::new());
+            for outcome in on_time {
+                let _ = channel.send(MessageAndPubkey {
+                    message: Message::WitnessStatement { outcome: Outcome::ExchangeApplication(outcome) },
+                    sender_did: pubkey.clone()
+                });
+            }
+            late_statements.append(&mut late);
+            let _ = reply.send(honest);
+        }
+    }
+
+    // the mailbox closes once the participants have compensated
+    for outcome in late_statements {
+        let _ = channel.send(MessageAndPubkey {
+            message: Message::WitnessStatement { outcome: Outcome::ExchangeApplication(outcome) },
+            sender_did: pubkey.clone()
+        });
+    }
+    return id;
+}
+
+async fn organization_actor(
+    organization_id: &mut OrganizationIdentity,
+    mut mailbox: mpsc::UnboundedReceiver<OrganizationMsg>,
+    print: bool
+) {
+    while let Some(msg) = mailbox.recv().await {
+        match msg {
+            OrganizationMsg::Subscribe { channel_pubkey, org_cert, reply } => {
+                // users whose certificates have run out are turned away
+                let accepted = org_cert.timeout > generate_sigs::get_timeout(0);
+                if accepted && !organization_id.readers.contains(&channel_pubkey) {
+                    organization_id.readers.push(channel_pubkey);
+                }
+                let _ = reply.send(accepted);
+            },
+            OrganizationMsg::Admit { request, run, reply } => {
+                let decision = organization_id.policy.decide(&organization_id.identity, &request, run);
+                if print {
+                    println!("Organization applies the {} policy:", decision.policy);
+                    println!("-- Admitted: {}. {}\n", decision.admitted, decision.reason);
+                }
+                let admitted = decision.admitted;
+                organization_id.decisions.push(decision);
+                let _ = reply.send(admitted);
+            }
+        }
+    }
+}
+
+async fn subscribe(id: &UserIdentity, organization: mpsc::UnboundedSender<OrganizationMsg>) -> bool {
+    let channel_pubkey = id.channel_client.get_public_key().clone();
+    let org_cert = id.id_info.org_cert.clone();
+    return ask(&organization, |reply| OrganizationMsg::Subscribe { channel_pubkey, org_cert, reply })
+        .await
+        .unwrap_or(false);
+}
+
+// Sends the actor a request and waits for the answer. Returns None if the actor has
+// stopped or does not answer within RESPONSE_TIMEOUT.
+async fn ask<M, T>(
+    mailbox: &mpsc::UnboundedSender<M>,
+    request: impl FnOnce(oneshot::Sender<T>) -> M
+) -> Option<T> {
+    let (reply, answer) = oneshot::channel();
+    mailbox.send(request(reply)).ok()?;
+    return timeout(RESPONSE_TIMEOUT, answer).await.ok()?.ok();
+}
+
+// Sends every actor the request, and then waits for all of the answers, so that the
+// actors work on it at the same time. Returns None if any actor does not answer.
+async fn ask_all<M, T>(
+    mailboxes: &Vec<mpsc::UnboundedSender<M>>,
+    request: impl Fn(oneshot::Sender<T>) -> M
+) -> Option<Vec<T>> {
+    let mut answers: Vec<oneshot::Receiver<T>> = Vec::new();
+    for mailbox in mailboxes.iter() {
+        let (reply, answer) = oneshot::channel();
+        mailbox.send(request(reply)).ok()?;
+        answers.push(answer);
+    }
+
+    let mut results: Vec<T> = Vec::new();
+    for answer in answers {
+        results.push(timeout(RESPONSE_TIMEOUT, answer).await.ok()?.ok()?);
+    }
+    return Some(results);
+}
+
+// Whether the user acts honestly this time, according to their reliability
+fn acts_honestly(id: &UserIdentity) -> bool {
+    let rand: f32 = rand::thread_rng().gen();
+    return id.id_info.reliability.unwrap_or(1.0) > rand;
+}
+
+fn channel_pk_as_multibase(id: &UserIdentity) -> String {
+    if let MethodData::PublicKeyMultibase(mbpub) = MethodData::new_multibase(id.channel_client.get_public_key()) {
+        return mbpub;
+    }
+    panic!("Could not encode public key as multibase")
+}
+
+fn witness_sig(id: &UserIdentity, contract: Contract) -> Result<WitnessSig> {
+    return generate_sigs::generate_witness_sig(
+        contract,
+        channel_pk_as_multibase(id),
+        KeyPair::try_from_ed25519_bytes(&id.id_info.did_key)?,
+        id.id_info.org_cert.clone(),
+        generate_sigs::get_timeout(SIG_DURATION)
+    );
+}
+
+fn participant_sig(
+    id: &UserIdentity,
+    contract: Contract,
+    witnesses: WitnessUsers,
+    witness_sigs: ArrayOfWnSignituresBytes
+) -> Result<InteractionSig> {
+    return generate_sigs::generate_participant_sig(
+        contract,
+        channel_pk_as_multibase(id),
+        KeyPair::try_from_ed25519_bytes(&id.id_info.did_key)?,
+        witnesses,
+        witness_sigs,
+        id.id_info.org_cert.clone(),
+        generate_sigs::get_timeout(SIG_DURATION)
+    );
+}
+
+#[tokio::test]
+pub async fn test_ask_all() {
+    let (mailbox, mut receiver) = mpsc::unbounded_channel::<oneshot::Sender<usize>>();
+    tokio::spawn(async move {
+        while let Some(reply) = receiver.recv().await {
+            let _ = reply.send(7);
+        }
+    });
+    let mailboxes = vec![mailbox.clone(), mailbox];
+    assert_eq!(ask_all(&mailboxes, |reply| reply).await, Some(vec![7, 7]));
+
+    // an actor that has stopped gets the interaction abandoned
+    let (stopped, receiver) = mpsc::unbounded_channel::<oneshot::Sender<usize>>();
+    drop(receiver);
+    assert_eq!(ask(&stopped, |reply| reply).await, None);
+}
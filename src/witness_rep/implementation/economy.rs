@@ -0,0 +1,183 @@
+use crate::witness_rep::implementation::user_and_organization::UserIdentity;
+
+use wb_reputation_system::data_types::event_protocol_messages::{
+    event_protocol_messages::Contract,
+    application_constructs::application_contracts::utility_types::{
+        UserOrWitnesses, CompensationJson
+    }
+};
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The value that changes hands in the interactions
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EconomyConfig {
+    /// What each participant gains when their counterparty is honest
+    pub contract_value: f32,
+    /// What a dishonest participant takes from an honest counterparty, who loses as much
+    pub cheating_gain: f32,
+    /// What each participant pays each witness
+    pub witness_fee: f32,
+}
+
+/// A user's balance, and where it came from
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Account {
+    pub balance: f32,
+    pub interactions: usize,
+    pub fees_paid: f32,
+    pub fees_earned: f32,
+    /// How many times the user was turned down as a counterparty, or had an interaction
+    /// rejected by the organization, because of reputations
+    pub denied: usize,
+}
+
+// The names given to the participants and witnesses in the contract
+fn participant_name(i: usize) -> String {
+    return format!("p{}", i + 1);
+}
+
+fn witness_name(i: usize) -> String {
+    return format!("wn_{}", i);
+}
+
+// The value of the contract for each participant, and the fee of each witness
+pub fn contract_compensation(config: &EconomyConfig, participants: usize, witnesses: usize) -> CompensationJson {
+    let mut compensation: CompensationJson = (0..participants)
+        .map(|i| (UserOrWitnesses::User(participant_name(i)), config.contract_value))
+        .collect();
+    for i in 0..witnesses {
+        compensation.push((UserOrWitnesses::User(witness_name(i)), config.witness_fee));
+    }
+    return compensation;
+}
+
+// The compensation for each of the named users in the contract
+fn named_amounts(contract: &Contract) -> Vec<(String, f32)> {
+    let exchange = match contract {
+        Contract::ExchangeApplication(exchange) => exchange,
+        _ => return Vec::new()
+    };
+    return exchange.compensation
+        .iter()
+        .filter_map(|(user, amount)| match user {
+            UserOrWitnesses::User(name) => Some((name.clone(), *amount)),
+            _ => None
+        })
+        .collect();
+}
+
+// The payments each participant makes to the witnesses, in the format of CompensationMsg.
+// Contracts without witness fees keep the flat payments the messages always carried.
+pub fn witness_payments(contract: &Contract) -> Vec<String> {
+    let payments: Vec<String> = named_amounts(contract)
+        .into_iter()
+        .filter(|(name, _)| name.starts_with("wn_"))
+        .map(|(name, amount)| format!("{}: {}", name, amount))
+        .collect();
+    if payments.len() == 0 {
+        return vec![String::from("wn_a: 0.01"), String::from("wn_b: 0.01")];
+    }
+    return payments;
+}
+
+// What each participant gains from the exchange: the contract value if their counterparties
+// were honest, less what they were cheated of, plus what they cheated the others of
+pub fn payoffs(values: &Vec<f32>, tn_honesty: &Vec<bool>, cheating_gain: f32) -> Vec<f32> {
+    let mut payoffs: Vec<f32> = vec![0.0; tn_honesty.len()];
+    for i in 0..tn_honesty.len() {
+        for j in 0..tn_honesty.len() {
+            if i == j {
+                continue;
+            }
+            payoffs[i] += match (tn_honesty[i], tn_honesty[j]) {
+                (true, true)   => values[i],
+                (false, true)  => values[i] + cheating_gain,
+                (true, false)  => -cheating_gain,
+                (false, false) => 0.0
+            };
+        }
+    }
+    return payoffs;
+}
+
+// Moves the value of the interaction between the accounts of its participants and witnesses
+pub fn settle_interaction(
+    contract: &Contract,
+    cheating_gain: f32,
+    participants: &mut Vec<UserIdentity>,
+    witnesses: &mut Vec<UserIdentity>,
+    tn_honesty: &Vec<bool>
+) {
+    let amounts: BTreeMap<String, f32> = named_amounts(contract).into_iter().collect();
+    let values: Vec<f32> = (0..participants.len())
+        .map(|i| amounts.get(&participant_name(i)).cloned().unwrap_or(0.0))
+        .collect();
+    let fees: Vec<f32> = (0..witnesses.len())
+        .map(|i| amounts.get(&witness_name(i)).cloned().unwrap_or(0.0))
+        .collect();
+    let total_fees: f32 = fees.iter().sum();
+
+    for (i, payoff) in payoffs(&values, tn_honesty, cheating_gain).into_iter().enumerate() {
+        if let Some(account) = participants[i].id_info.state.account.as_mut() {
+            account.balance += payoff - total_fees;
+            account.fees_paid += total_fees;
+            account.interactions += 1;
+        }
+    }
+    let payers = participants.len() as f32;
+    for (i, witness) in witnesses.iter_mut().enumerate() {
+        if let Some(account) = witness.id_info.state.account.as_mut() {
+            account.balance += fees[i] * payers;
+            account.fees_earned += fees[i] * payers;
+            account.interactions += 1;
+        }
+    }
+}
+
+pub fn deny(user: &mut UserIdentity) {
+    if let Some(account) = user.id_info.state.account.as_mut() {
+        account.denied += 1;
+    }
+}
+
+// The balance of each user, followed by the value of the interactions they were denied
+pub fn utilities_string(users: &Vec<UserIdentity>, config: &EconomyConfig) -> String {
+    let mut balances: String = String::new();
+    let mut lost: String = String::from("\n");
+    for user in users.iter() {
+        if let Some(account) = &user.id_info.state.account {
+            let pk = &user.id_info.org_cert.client_pubkey;
+            balances.push_str(&format!("{}: {}\n", pk, account.balance));
+            lost.push_str(&format!("{}: {}\n", pk, account.denied as f32 * config.contract_value));
+        }
+    }
+    balances.push_str(&lost);
+    return balances;
+}
+
+// The average balance of the users behaving alike, i.e. with the same reliability, so
+// it can be checked that cheating does not pay
+pub fn strategy_utilities_string(users: &Vec<UserIdentity>) -> String {
+    let mut strategies: BTreeMap<String, (f32, usize)> = BTreeMap::new();
+    for user in users.iter() {
+        if let (Some(account), Some(reliability)) = (&user.id_info.state.account, user.id_info.reliability) {
+            let entry = strategies.entry(format!("reliability {:.2}", reliability)).or_insert((0.0, 0));
+            entry.0 += account.balance;
+            entry.1 += 1;
+        }
+    }
+    return strategies
+        .iter()
+        .map(|(strategy, (total, count))| format!("{}: {}\n", strategy, total / *count as f32))
+        .collect();
+}
+
+#[test]
+pub fn test_payoffs() {
+    let values = vec![1.0, 1.0];
+    assert_eq!(payoffs(&values, &vec![true, true], 0.5), vec![1.0, 1.0]);
+    assert_eq!(payoffs(&values, &vec![false, true], 0.5), vec![1.5, -0.5]);
+    assert_eq!(payoffs(&values, &vec![false, false], 0.5), vec![0.0, 0.0]);
+}
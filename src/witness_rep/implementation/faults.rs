@@ -0,0 +1,349 @@
+use crate::witness_rep::trust_score_generators::statement_rules::contract_times;
+
+use wb_reputation_system::data_types::{
+    event_protocol_messages::event_protocol_messages::Message,
+    tsg_data_types::message::MessageAndPubkey
+};
+
+use serde::{Deserialize, Serialize};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use std::collections::{BTreeMap, HashMap};
+
+/// How many times a call the node failed is retried, and a dropped InteractionMsg
+/// resent, before the interaction is given up
+pub const MAX_NODE_RETRIES: usize = 3;
+
+/// How long a delayed packet is held back at most, in seconds, unless the config says
+/// otherwise. Twice as long as a contract runs, so about half of the delayed statements
+/// reach the channel after the contract has timed out.
+pub const DEFAULT_MAX_DELAY: u32 = 2000;
+
+/// The probability of each fault, at every point of the protocol it can occur
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct FaultConfig {
+    /// A signed packet never reaches the channel
+    pub drop_packet: f32,
+    /// A signed packet is held back, and reaches the channel up to max_delay seconds later
+    pub delay_packet: f32,
+    /// A witness who signed the interaction goes offline before sending their statement
+    pub witness_offline: f32,
+    /// The organization fails to send the keyload of an interaction
+    pub keyload_failure: f32,
+    /// A participant crashes once the InteractionMsg is sent, and never compensates
+    pub participant_crash: f32,
+    /// A call to the node, syncing a client or subscribing it, fails and has to be retried.
+    /// Only Streams talks to a node, but the errors are injected on every backend alike.
+    pub node_error: f32,
+    /// The longest a delayed packet is held back, in seconds (DEFAULT_MAX_DELAY if not set).
+    /// A statement held back past the timeout of the contract is late.
+    #[serde(default)]
+    pub max_delay: Option<u32>,
+}
+
+/// The faults that can be injected, counted in faults.txt
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Fault {
+    DroppedPacket,
+    DelayedPacket,
+    OfflineWitness,
+    KeyloadFailure,
+    ParticipantCrash,
+    NodeError,
+}
+
+/// How often each fault was injected so far, and how many interactions were given up,
+/// as saved in a checkpoint so that a resumed simulation carries on counting
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct FaultCounts {
+    pub injected: Vec<(Fault, usize)>,
+    pub abandoned: usize,
+}
+
+// Decides when the faults occur, and keeps count of them. Faults are drawn from their own
+// generator, so that injecting them does not change the rest of the simulation.
+pub struct FaultInjector {
+    config: FaultConfig,
+    rand_gen: StdRng,
+    /// How often each fault was injected
+    pub injected: BTreeMap<Fault, usize>,
+    /// The number of interactions given up because of the faults
+    pub abandoned: usize,
+    /// Whether the interaction last given up has not yet been taken note of
+    last_abandoned: bool,
+}
+
+impl FaultInjector {
+    // Without a config, no faults are injected
+    pub fn new(config: &Option<FaultConfig>) -> FaultInjector {
+        return FaultInjector::with_rng(config, StdRng::from_entropy());
+    }
+
+    // Injects the same faults whenever it is given the same seed
+    pub fn seeded(config: &Option<FaultConfig>, seed: u64) -> FaultInjector {
+        return FaultInjector::with_rng(config, StdRng::seed_from_u64(seed));
+    }
+
+    // Draws the faults from now on from the seed, keeping the counts so far
+    pub fn reseed(&mut self, seed: u64) {
+        self.rand_gen = StdRng::seed_from_u64(seed);
+    }
+
+    // Carries on from the counts of an earlier injector (see counts)
+    pub fn with_counts(mut self, counts: &FaultCounts) -> FaultInjector {
+        self.injected = counts.injected.iter().cloned().collect();
+        self.abandoned = counts.abandoned;
+        return self;
+    }
+
+    pub fn counts(&self) -> FaultCounts {
+        return FaultCounts {
+            injected: self.injected.iter().map(|(fault, count)| (*fault, *count)).collect(),
+            abandoned: self.abandoned
+        };
+    }
+
+    fn with_rng(config: &Option<FaultConfig>, rand_gen: StdRng) -> FaultInjector {
+        return FaultInjector {
+            config: config.clone().unwrap_or_default(),
+            rand_gen: rand_gen,
+            injected: BTreeMap::new(),
+            abandoned: 0,
+            last_abandoned: false
+        };
+    }
+
+    pub fn probability(&self, fault: Fault) -> f32 {
+        return match fault {
+            Fault::DroppedPacket    => self.config.drop_packet,
+            Fault::DelayedPacket    => self.config.delay_packet,
+            Fault::OfflineWitness   => self.config.witness_offline,
+            Fault::KeyloadFailure   => self.config.keyload_failure,
+            Fault::ParticipantCrash => self.config.participant_crash,
+            Fault::NodeError        => self.config.node_error,
+        };
+    }
+
+    // Whether the fault occurs at this point of the protocol
+    pub fn inject(&mut self, fault: Fault) -> bool {
+        let probability = self.probability(fault);
+        if probability <= 0.0 {
+            return false;
+        }
+        let occurs = self.rand_gen.gen::<f32>() < probability;
+        if occurs {
+            *self.injected.entry(fault).or_insert(0) += 1;
+        }
+        return occurs;
+    }
+
+    // How many seconds a delayed packet is held back
+    pub fn delay(&mut self) -> u32 {
+        return self.rand_gen.gen_range(0..=self.config.max_delay.unwrap_or(DEFAULT_MAX_DELAY));
+    }
+
+    // Whether the fault occurs at any of the attempts, each of which is retried
+    // while it does, up to MAX_NODE_RETRIES times
+    pub fn inject_with_retries(&mut self, fault: Fault) -> bool {
+        for _ in 0..MAX_NODE_RETRIES + 1 {
+            if !self.inject(fault) {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    pub fn abandon(&mut self) {
+        self.abandoned += 1;
+        self.last_abandoned = true;
+    }
+
+    // Whether the interaction was given up because of a fault since this was last asked,
+    // as opposed to not being admitted
+    pub fn take_abandoned(&mut self) -> bool {
+        let abandoned = self.last_abandoned;
+        self.last_abandoned = false;
+        return abandoned;
+    }
+
+    // Applies the faults to the messages of an emulated interaction, given in the order they
+    // were sent, and returns the messages that reach the channel, in the order they arrive,
+    // with the time each of them arrives (see send_times). Nobody acts on an interaction whose
+    // InteractionMsg they have not seen, so its initiator resends it until it is on the channel,
+    // and the interaction is given up, returning None, if it never is or the keyload fails.
+    // Once it is there, the interaction is never given up, and faults only cost it statements
+    // and compensations, or hold them up.
+    pub fn deliver(&mut self, msgs: Vec<MessageAndPubkey>) -> Option<(Vec<MessageAndPubkey>, Vec<u32>)> {
+        if self.inject(Fault::KeyloadFailure) {
+            self.abandon();
+            return None;
+        }
+
+        let sent_at = send_times(&msgs);
+        let mut delivered: Vec<(MessageAndPubkey, u32)> = Vec::new();
+        let mut crashed: HashMap<String, bool> = HashMap::new();
+        for (msg, sent) in msgs.into_iter().zip(sent_at.into_iter()) {
+            match &msg.message {
+                Message::InteractionMsg { .. } => {
+                    if self.inject_with_retries(Fault::DroppedPacket) {
+                        self.abandon();
+                        return None;
+                    }
+                    delivered.push((msg, sent));
+                    continue;
+                },
+                Message::WitnessStatement { .. } => {
+                    if self.inject(Fault::OfflineWitness) {
+                        continue;
+                    }
+                },
+                Message::ApplicationMsg(_) => {
+                    let sender = msg.sender_did.clone();
+                    let has_crashed = match crashed.get(&sender) {
+                        Some(has_crashed) => *has_crashed,
+                        None => self.inject(Fault::ParticipantCrash)
+                    };
+                    crashed.insert(sender, has_crashed);
+                    if has_crashed {
+                        continue;
+                    }
+                }
+            }
+
+            if self.inject(Fault::DroppedPacket) {
+                continue;
+            }
+            let arrival = match self.inject(Fault::DelayedPacket) {
+                true  => sent.saturating_add(self.delay()),
+                false => sent
+            };
+            delivered.push((msg, arrival));
+        }
+
+        // the channel holds the messages in the order they arrive
+        delivered.sort_by_key(|(_, arrival)| *arrival);
+        return Some(delivered.into_iter().unzip());
+    }
+}
+
+// When each message of an emulated interaction is sent, by the times of its contract (see
+// statement_rules::contract_times): the InteractionMsg and the statements once the contract
+// is made, and the compensations once it times out. Anything sent after a compensation,
+// like the statements of a witness who holds theirs back, is sent after the deadline.
+fn send_times(msgs: &Vec<MessageAndPubkey>) -> Vec<u32> {
+    let (made, deadline) = msgs
+        .iter()
+        .find_map(|msg| match &msg.message {
+            Message::InteractionMsg { contract, .. } => Some(contract_times(contract)),
+            _ => None
+        })
+        .unwrap_or((0, u32::MAX));
+    let mut compensated = false;
+    return msgs
+        .iter()
+        .map(|msg| match &msg.message {
+            Message::ApplicationMsg(_) => {
+                compensated = true;
+                deadline
+            },
+            _ if compensated => deadline.saturating_add(1),
+            _ => made
+        })
+        .collect();
+}
+
+// The number of each fault injected and of the interactions given up, as read by stats::read_summary
+pub fn faults_summary_string(faults: &FaultInjector) -> String {
+    let mut output: String = String::new();
+    for (fault, count) in faults.injected.iter() {
+        output.push_str(&format!("{:?}: {}\n", fault, count));
+    }
+    output.push_str(&format!("abandoned: {}\n", faults.abandoned));
+    return output;
+}
+
+#[cfg(test)]
+fn test_msgs() -> Vec<MessageAndPubkey> {
+    use wb_reputation_system::data_types::event_protocol_messages::{
+        application_constructs::application_messages::exchange_app_messages::CompensationMsg,
+        event_protocol_messages::{ApplicationMsg, Outcome}
+    };
+
+    let statement = |witness: &str| MessageAndPubkey {
+        message: Message::WitnessStatement { outcome: Outcome::ExchangeApplication(vec![true, false]) },
+        sender_did: String::from(witness)
+    };
+    let compensation = |participant: &str| MessageAndPubkey {
+        message: Message::ApplicationMsg(ApplicationMsg::ExchangeApplication(CompensationMsg {
+            payments: vec![String::from("wn_a: 0.01")]
+        })),
+        sender_did: String::from(participant)
+    };
+    return vec![statement("w0"), statement("w1"), compensation("p0"), compensation("p1")];
+}
+
+#[test]
+pub fn test_deliver() {
+    use crate::witness_rep::trust_score_generators::statement_rules::{check_interaction, test_interaction_msg};
+
+    let senders = |msgs: &Vec<MessageAndPubkey>| -> Vec<String> {
+        msgs.iter().map(|msg| msg.sender_did.clone()).collect()
+    };
+
+    // without faults, every message arrives in the order it was sent
+    let mut faults = FaultInjector::new(&None);
+    let (delivered, _) = faults.deliver(test_msgs()).unwrap();
+    assert_eq!(senders(&delivered), vec!["w0", "w1", "p0", "p1"]);
+    assert!(faults.injected.is_empty());
+
+    // statements held back for less than the contract runs still arrive before it times
+    // out, which is when the participants compensate, so they are in time
+    let mut msgs = test_msgs();
+    msgs.insert(0, MessageAndPubkey {
+        message: test_interaction_msg(100, 200),
+        sender_did: String::from("p0")
+    });
+    let mut faults = FaultInjector::new(&Some(FaultConfig {
+        delay_packet: 1.0,
+        max_delay: Some(50),
+        ..FaultConfig::default()
+    }));
+    let (delivered, arrivals) = faults.deliver(msgs).unwrap();
+    assert_eq!(senders(&delivered)[3..], ["p0", "p1"]);
+    assert!(arrivals[1] <= 150 && arrivals[2] <= 150 && arrivals[3] >= 200);
+    let report = check_interaction(&delivered, &arrivals);
+    assert_eq!(report.statements.len(), 2);
+    assert!(!report.breached());
+
+    // offline witnesses and crashed participants send nothing
+    let mut faults = FaultInjector::new(&Some(FaultConfig {
+        witness_offline: 1.0,
+        participant_crash: 1.0,
+        ..FaultConfig::default()
+    }));
+    assert_eq!(faults.deliver(test_msgs()).unwrap().0.len(), 0);
+    assert_eq!(faults.injected[&Fault::OfflineWitness], 2);
+    assert_eq!(faults.injected[&Fault::ParticipantCrash], 2);
+    assert!(!faults.take_abandoned());
+
+    // a failed keyload gives the interaction up, which is only taken note of once
+    let mut faults = FaultInjector::new(&Some(FaultConfig {
+        keyload_failure: 1.0,
+        ..FaultConfig::default()
+    }));
+    assert!(faults.deliver(test_msgs()).is_none());
+    assert!(faults.take_abandoned());
+    assert!(!faults.take_abandoned());
+    assert_eq!(faults.abandoned, 1);
+    assert_eq!(faults_summary_string(&faults), "KeyloadFailure: 1\nabandoned: 1\n");
+}
+
+#[test]
+pub fn test_inject_with_retries() {
+    let mut faults = FaultInjector::new(&Some(FaultConfig {
+        node_error: 1.0,
+        ..FaultConfig::default()
+    }));
+    assert!(faults.inject_with_retries(Fault::NodeError));
+    assert_eq!(faults.injected[&Fault::NodeError], MAX_NODE_RETRIES + 1);
+    assert!(!faults.inject_with_retries(Fault::DroppedPacket));
+}
@@ -29,7 +29,8 @@ use identity::{
 // informally requires the participant_ids vector to be of length 2
 pub fn generate_exchange_contract(
     participant_ids: &mut Vec<UserIdentity>,
-    channel_address: String
+    channel_address: String,
+    compensation: Option<CompensationJson>
 ) -> Result<Contract> {
     // get the did pubkeys from the ids
     let did_pubkeys_res : Result<Vec<String>> = participant_ids
@@ -48,9 +49,10 @@ pub fn generate_exchange_contract(
         .collect();
     let did_pubkeys = did_pubkeys_res?;
     
-    let compensation_json: CompensationJson =  vec![
+    // without an economy, the contract carries a nominal compensation
+    let compensation_json: CompensationJson = compensation.unwrap_or(vec![
         (UserOrWitnesses::User(String::from("u1")), 0.1)
-    ];
+    ]);
 
     // generate the contract
     let contract_hardcoded = ExchangeContract {
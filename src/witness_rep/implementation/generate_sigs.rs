@@ -30,12 +30,11 @@ pub fn generate_witness_sig(
     channel_pk_as_multibase: String,
     did_keypair: KeyPair,
     org_cert: OrganizationCertificate,
-    duration: u32
+    timeout: u32
 ) -> Result<witness_sig::WitnessSig> {
 
 
     let did_pk_as_multibase: String = get_multibase(&did_keypair);
-    let timeout = get_timeout(duration);
 
     // WN signs their response
     let wn_pre_sig = witness_sig::WitnessPreSig {
@@ -67,11 +66,10 @@ pub fn generate_participant_sig(
     witnesses: WitnessUsers,
     witness_sigs: interaction_sig::ArrayOfWnSignituresBytes,
     org_cert: OrganizationCertificate,
-    duration: u32
+    timeout: u32
 ) -> Result<interaction_sig::InteractionSig> {
 
     let did_pk_as_multibase: String = get_multibase(&did_keypair);
-    let timeout = get_timeout(duration);
 
     // TN_A signs the interaction
     let tn_a_tx_msg_pre_sig = interaction_sig::InteractionPreSig {
@@ -0,0 +1,256 @@
+use crate::witness_rep::implementation::{
+    user_and_organization::UserIdentity,
+    reputation_model::reputation_scores
+};
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, BTreeSet};
+
+/// Who the power iteration falls back to, and who anchors the global trust
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum PreTrusted {
+    /// A single global trust vector, with every user equally pre-trusted
+    Everyone,
+    /// Each organization computes its own global trust vector, pre-trusting its
+    /// members, and its members use that organization's view
+    OrganizationMembers,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GlobalTrustConfig {
+    /// The number of runs between two computations of the global trust
+    pub interval: usize,
+    pub pre_trusted: PreTrusted,
+    /// The weight, in [0,1], given to the pre-trusted users in each iteration
+    pub pre_trust_weight: f32,
+    pub max_iterations: usize,
+    /// The iteration stops once no entry of the trust vector moves more than this
+    pub tolerance: f32,
+    /// Whether users check counterparties and witnesses against their global
+    /// reputation instead of their local one
+    pub use_for_checks: bool,
+}
+
+/// A user's view of the global reputations
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GlobalView {
+    pub reputations: HashMap<String, f32>,
+    pub use_for_checks: bool,
+}
+
+// The local reputation maps of the users, restricted to the other users. Organizations
+// and unknown keys are ignored, as they cannot recommend anyone.
+fn local_scores(users: &Vec<UserIdentity>) -> (Vec<String>, Vec<HashMap<String, f32>>) {
+    let pks: Vec<String> = users
+        .iter()
+        .map(|user| user.id_info.org_cert.client_pubkey.clone())
+        .collect();
+    let known: BTreeSet<&String> = pks.iter().collect();
+
+    let scores: Vec<HashMap<String, f32>> = users
+        .iter()
+        .zip(pks.iter())
+        .map(|(user, pk)| reputation_scores(user)
+            .into_iter()
+            .filter(|(other, _)| other != pk && known.contains(other))
+            .collect()
+        )
+        .collect();
+    return (pks, scores);
+}
+
+/// The normalised trust a user places in each of the users they trust, by index among
+/// the users, ordered by index. Users only hold reputations for those they came across,
+/// so the rows are kept sparse.
+pub type TrustRow = Vec<(usize, f32)>;
+
+// Row i holds the normalised trust user i places in each other user. Users who
+// trust nobody are left with an empty row, and defer to the pre-trusted users.
+pub fn trust_matrix(pks: &Vec<String>, scores: &Vec<HashMap<String, f32>>) -> Vec<TrustRow> {
+    let index: HashMap<&String, usize> = pks.iter().enumerate().map(|(i, pk)| (pk, i)).collect();
+    return scores
+        .iter()
+        .map(|map| {
+            let total: f32 = map.values().map(|score| score.max(0.0)).sum();
+            if total <= 0.0 {
+                return Vec::new();
+            }
+            let mut row: TrustRow = map
+                .iter()
+                .filter(|(_, score)| **score > 0.0)
+                .filter_map(|(pk, score)| index.get(pk).map(|j| (*j, score / total)))
+                .collect();
+            // summed in the same order whatever the order of the map
+            row.sort_by_key(|(j, _)| *j);
+            row
+        })
+        .collect();
+}
+
+// Power iteration of t = (1-a) C^T t + a p, starting from the pre-trust vector p.
+// Each iteration takes time in the number of entries of the matrix, not its size.
+pub fn eigen_trust(
+    matrix: &Vec<TrustRow>,
+    pre_trust: &Vec<f32>,
+    pre_trust_weight: f32,
+    max_iterations: usize,
+    tolerance: f32
+) -> Vec<f32> {
+    let mut trust = pre_trust.clone();
+    for _ in 0..max_iterations {
+        let mut next: Vec<f32> = pre_trust.iter().map(|p| pre_trust_weight * p).collect();
+        // the trust of users with an empty row flows to the pre-trusted users
+        let mut deferred = 0.0;
+        for (i, row) in matrix.iter().enumerate() {
+            if row.is_empty() {
+                deferred += trust[i];
+                continue;
+            }
+            for (j, c_ij) in row.iter() {
+                next[*j] += (1.0 - pre_trust_weight) * c_ij * trust[i];
+            }
+        }
+        for (j, p) in pre_trust.iter().enumerate() {
+            next[j] += (1.0 - pre_trust_weight) * p * deferred;
+        }
+        let change = next
+            .iter()
+            .zip(trust.iter())
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f32::max);
+        trust = next;
+        if change < tolerance {
+            break;
+        }
+    }
+    return trust;
+}
+
+// The global trust vector sums to one, so is not comparable to reliabilities. Instead
+// the global reputation of a user is the average of their local reputations, each
+// weighted by the global trust in the user holding it.
+pub fn global_reputations(
+    pks: &Vec<String>,
+    scores: &Vec<HashMap<String, f32>>,
+    trust: &Vec<f32>
+) -> HashMap<String, f32> {
+    let known: BTreeSet<&String> = pks.iter().collect();
+    let mut sums: HashMap<&String, (f32, f32)> = HashMap::new();
+    for (i, map) in scores.iter().enumerate() {
+        for (pk, score) in map.iter().filter(|(pk, _)| known.contains(pk)) {
+            let (weighted, weights) = sums.entry(pk).or_insert((0.0, 0.0));
+            *weighted += trust[i] * score;
+            *weights += trust[i];
+        }
+    }
+    return sums
+        .into_iter()
+        .filter(|(_, (_, weights))| *weights > 0.0)
+        .map(|(pk, (weighted, weights))| (pk.clone(), weighted / weights))
+        .collect();
+}
+
+fn uniform_over(members: &Vec<bool>) -> Vec<f32> {
+    let count = members.iter().filter(|m| **m).count().max(1) as f32;
+    return members.iter().map(|m| if *m { 1.0 / count } else { 0.0 }).collect();
+}
+
+// Combines all of the users' local maps, and gives each user their view of the global reputations
+pub fn update_global_trust(users: &mut Vec<UserIdentity>, config: &GlobalTrustConfig) {
+    let (pks, scores) = local_scores(users);
+    let matrix = trust_matrix(&pks, &scores);
+    let compute = |pre_trust: Vec<f32>| {
+        let trust = eigen_trust(&matrix, &pre_trust, config.pre_trust_weight, config.max_iterations, config.tolerance);
+        return global_reputations(&pks, &scores, &trust);
+    };
+
+    let views: Vec<HashMap<String, f32>> = match config.pre_trusted {
+        PreTrusted::Everyone => {
+            let reputations = compute(uniform_over(&vec![true; users.len()]));
+            vec![reputations; users.len()]
+        },
+        PreTrusted::OrganizationMembers => {
+            let mut org_views: HashMap<String, HashMap<String, f32>> = HashMap::new();
+            for user in users.iter() {
+                let org_pk = &user.id_info.org_cert.org_pubkey;
+                if org_views.contains_key(org_pk) {
+                    continue;
+                }
+                let members: Vec<bool> = users
+                    .iter()
+                    .map(|other| &other.id_info.org_cert.org_pubkey == org_pk)
+                    .collect();
+                org_views.insert(org_pk.clone(), compute(uniform_over(&members)));
+            }
+            users
+                .iter()
+                .map(|user| org_views[&user.id_info.org_cert.org_pubkey].clone())
+                .collect()
+        }
+    };
+
+    for (user, reputations) in users.iter_mut().zip(views.into_iter()) {
+        user.id_info.state.global_trust = Some(GlobalView {
+            reputations: reputations,
+            use_for_checks: config.use_for_checks
+        });
+    }
+}
+
+// Whether the global trust is due to be recomputed after the run
+pub fn is_due(config: &Option<GlobalTrustConfig>, run: usize) -> Option<&GlobalTrustConfig> {
+    return config.as_ref().filter(|gt| gt.interval > 0 && (run + 1) % gt.interval == 0);
+}
+
+// In the format of reputation_maps.txt, so it can be evaluated the same way
+pub fn global_reputations_string(users: &Vec<UserIdentity>) -> String {
+    let mut output: String = String::new();
+    for user in users.iter() {
+        let mut reputations: Vec<(String, f32)> = user.id_info.state.global_trust
+            .as_ref()
+            .map(|view| view.reputations.clone().into_iter().collect())
+            .unwrap_or(Vec::new());
+        reputations.sort_by(|a, b| a.0.cmp(&b.0));
+        output.push_str(&format!("{}\n", user.id_info.org_cert.client_pubkey));
+        for (pk, reputation) in reputations.iter() {
+            output.push_str(&format!("{}: {}\n", pk, reputation));
+        }
+        output.push_str("\n\n");
+    }
+    return output;
+}
+
+#[test]
+pub fn test_eigen_trust() {
+    let pks: Vec<String> = vec![String::from("a"), String::from("b"), String::from("c")];
+    // a and b agree that c is unreliable, c claims the opposite
+    let scores: Vec<HashMap<String, f32>> = vec![
+        vec![(String::from("b"), 0.9), (String::from("c"), 0.1)].into_iter().collect(),
+        vec![(String::from("a"), 0.9), (String::from("c"), 0.1)].into_iter().collect(),
+        vec![(String::from("a"), 0.1), (String::from("b"), 0.1)].into_iter().collect(),
+    ];
+    let matrix = trust_matrix(&pks, &scores);
+    for row in matrix.iter() {
+        assert!((row.iter().map(|(_, c)| c).sum::<f32>() - 1.0).abs() < 0.0001);
+    }
+    // nobody holds a reputation for themselves, so only the other two users are in each row
+    assert!(matrix.iter().all(|row| row.len() == 2));
+
+    let trust = eigen_trust(&matrix, &vec![0.5, 0.5, 0.0], 0.2, 100, 0.00001);
+    assert!((trust.iter().sum::<f32>() - 1.0).abs() < 0.001);
+    assert!(trust[2] < trust[0] && trust[2] < trust[1]);
+
+    // c's opinion of a and b counts for little
+    let reputations = global_reputations(&pks, &scores, &trust);
+    assert!(reputations["a"] > 0.75);
+    assert!(reputations["c"] < 0.2);
+}
+
+// A user who trusts nobody defers to the pre-trusted users, as if their row were the pre-trust vector
+#[test]
+pub fn test_eigen_trust_empty_row() {
+    let matrix: Vec<TrustRow> = vec![vec![(1, 1.0)], vec![(0, 1.0)], Vec::new()];
+    let trust = eigen_trust(&matrix, &vec![0.5, 0.5, 0.0], 0.2, 100, 0.00001);
+    assert!((trust.iter().sum::<f32>() - 1.0).abs() < 0.001);
+    assert!(trust[2] < 0.001);
+}
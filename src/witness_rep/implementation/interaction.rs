@@ -2,9 +2,15 @@ use crate::witness_rep::{
     implementation::{
         generate_sigs, 
         user_and_organization::{
-            UserIdentity, OrganizationIdentity, IdInfo, get_public_keys
-        }
+            UserIdentity, OrganizationIdentity, IdInfo
+        },
+        organization_policy::{OrganizationPolicy, InteractionRequest},
+        economy::witness_payments,
+        faults::{FaultInjector, Fault},
+        witness_abuse::{WitnessAbuse, witness_statements},
+        ledger::{Ledger, Transmission, channel_pk_as_multibase}
     },
+    trust_score_generators::statement_rules::contract_times
 };
 
 use wb_reputation_system::data_types::{
@@ -30,25 +36,23 @@ use wb_reputation_system::data_types::{
                 OrganizationCertificate
             }, 
         },
-    }
+    },
+    tsg_data_types::message::MessageAndPubkey
 };
 
 use iota_streams::{
-    app::transport::tangle::{TangleAddress, client::Client},
-    app_channels::api::tangle::{
-        Address, Author, Bytes, Subscriber
-    },
-    core::{println, Result},
-    app::message::HasLink
+    app::transport::tangle::client::Client,
+    app_channels::api::tangle::Subscriber,
+    core::{println, Result}
 };
 use identity::{
     did::MethodData,
     crypto::KeyPair
 };
 use rand::Rng;
-use core::str::FromStr;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub enum LazyMethod {
     Constant(bool),
     Random,
@@ -56,6 +60,13 @@ pub enum LazyMethod {
 
 //pub type OrganizationIdentity = Identity<Author<Client>>;
 
+// Who sent a packet which was held back, by their index among the witnesses or participants
+#[derive(Clone, Copy, Debug)]
+enum HeldBack {
+    Witness(usize),
+    Participant(usize)
+}
+
 pub fn extract_from_id(
     id: &mut UserIdentity
 ) -> Result<(&mut Subscriber<Client>, KeyPair, f32, OrganizationCertificate)> {
@@ -64,7 +75,8 @@ pub fn extract_from_id(
             did_key,
             reliability,
             org_cert,
-            seed: _
+            seed: _,
+            ..
         } => {
             let did_keypair = KeyPair::try_from_ed25519_bytes(did_key)?;
             return Ok((&mut id.channel_client, did_keypair,reliability.clone().unwrap(), org_cert.clone()));
@@ -90,22 +102,16 @@ pub fn extract_from_ids(
     return Ok((subs, kps,rels,orgs));
 }
 
-pub async fn sync_all(subs: &mut Vec<&mut Subscriber<Client>>) -> Result<()> {
-    for sub in subs {
-        sub.sync_state().await?;
-    }
-    return Ok(());
-}
 
 /// Assigns users as being dishonest or honest, depending on their reliability
-pub fn get_honest_users(users_reliablity: Vec<f32>) -> Vec<bool>{
+pub fn get_honest_users(users_reliablity: Vec<f32>, rand_gen: &mut impl Rng) -> Vec<bool>{
     let mut honest_nodes: Vec<bool> = vec![true; users_reliablity.len()];
 
     // determine honesty/dishonesty for each user
     for i in 0..users_reliablity.len() {
 
         // randomly assert if they are acting honest based on their reliability
-        let rand: f32 = rand::thread_rng().gen();
+        let rand: f32 = rand_gen.gen();
         println!("-- Trying participant {}. Rand={}", i, rand);
         let acting_honest: bool = users_reliablity[i] > rand;
         if !acting_honest {
@@ -120,11 +126,11 @@ pub fn get_honest_users(users_reliablity: Vec<f32>) -> Vec<bool>{
     return honest_nodes;
 }
 
-pub fn lazy_outcome(lazy_method: &LazyMethod) -> bool {
+pub fn lazy_outcome(lazy_method: &LazyMethod, rand_gen: &mut impl Rng) -> bool {
     return match lazy_method {
         LazyMethod::Constant(output) => output.clone(),
         LazyMethod::Random => {
-            let rand: f32 = rand::thread_rng().gen();
+            let rand: f32 = rand_gen.gen();
             println!("-- Trying lazy outcome. Rand={}", rand);
             if rand > 0.5 {
                 true
@@ -135,102 +141,100 @@ pub fn lazy_outcome(lazy_method: &LazyMethod) -> bool {
     }
 }
 
-
+// Runs the interaction on the organization's channel, whichever ledger it is kept on, and
+// returns the honesty of the participants and the witnesses with the messages of the
+// interaction as the ledger gives them to its readers, and when each reached the channel.
+// The witnesses state the outcome when the contract is made, and the participants compensate
+// once it times out, which is the deadline for the statements. Returns None if the organization
+// does not admit it, or if it is given up because of a fault, which the injector records.
+// Faults can only give the interaction up before its InteractionMsg is on the channel, as
+// nobody acts on an interaction they have not seen, so the initiator resends a dropped
+// InteractionMsg. After that, an offline witness, a crashed participant or a dropped
+// packet only cost the interaction a message, and delayed packets are sent once they arrive.
 pub async fn interaction(
     contract: Contract,
     participant_ids: &mut Vec<UserIdentity>,
     witness_ids: &mut Vec<UserIdentity>,
     organization_id: &mut OrganizationIdentity,
     lazy_method: LazyMethod,
-    run: usize
-) -> Result<Option<(Vec<bool>, Vec<bool>)>> {
+    run: usize,
+    print: bool,
+    ledger: &mut Ledger,
+    faults: &mut FaultInjector,
+    rand_gen: &mut impl Rng
+) -> Result<Option<(Vec<bool>, Vec<bool>, Vec<MessageAndPubkey>, Vec<u32>)>> {
     const DEFAULT_TIMEOUT : u32 = 60*10; // 10 mins
-    let ann_str = organization_id.ann_msg.as_ref().unwrap();
-    let announcement_link = Address::from_str(ann_str)?;
+    let (made, deadline) = contract_times(&contract);
+    let witness_abuse: Vec<Option<WitnessAbuse>> = witness_ids
+        .iter()
+        .map(|w| w.id_info.state.witness_abuse.clone())
+        .collect();
+    let participant_dids: Vec<String> = participant_ids
+        .iter()
+        .map(|p| p.id_info.org_cert.client_pubkey.clone())
+        .collect();
+    let witness_dids: Vec<String> = witness_ids
+        .iter()
+        .map(|w| w.id_info.org_cert.client_pubkey.clone())
+        .collect();
 
     //--------------------------------------------------------------
     //--------------------------------------------------------------
     // EXTRACT CLIENTS AND KEYPAIRS FROM IDENTITIES
     //--------------------------------------------------------------
+    ledger.attach_clients(participant_ids, organization_id);
+    ledger.attach_clients(witness_ids, organization_id);
     let (mut participant_clients, participant_did_kp, participant_reliablity, participant_org_certs) = extract_from_ids(participant_ids)?;
     let (mut witness_clients, witness_did_kp, witness_reliability, witness_org_certs) = extract_from_ids(witness_ids)?;
 
     //--------------------------------------------------------------
-    // ORGANIZATION CHECKS THE RELIABILITIES OF THE PARTICIPANTS
+    // ORGANIZATION APPLIES ITS POLICY TO THE INTERACTION
     //--------------------------------------------------------------
     
-    // get the public keys of all the participants
-    let mut tn_pks = get_public_keys(&participant_org_certs);
-    let mut wn_pks = get_public_keys(&witness_org_certs);
-    let mut participant_pks = Vec::new();
-    participant_pks.append(&mut tn_pks); participant_pks.append(&mut wn_pks);
+    let request = InteractionRequest {
+        participants: participant_org_certs.clone(),
+        witnesses: witness_org_certs.clone()
+    };
+    let decision = organization_id.policy.decide(&organization_id.identity, &request, run);
+    if print {
+        println!("Organization applies the {} policy:", decision.policy);
+        println!("-- Admitted: {}. {}\n", decision.admitted, decision.reason);
+    }
+    organization_id.decisions.push(decision.clone());
 
-    if !organization_id.identity.check_avg_participants(&participant_pks){
+    if !decision.admitted {
+        ledger.reject(organization_id, &decision, run).await?;
         return Ok(None);
     }
 
-
     //--------------------------------------------------------------
     // ORGANIZATION SENDS KEYLOAD
     //--------------------------------------------------------------
 
-
-    // participants process the channel announcement
-    println!("Participants subscribe to channel if not already subscribed:");
-    let ann_address = Address::try_from_bytes(&announcement_link.to_bytes())?;
-    for i in 0..participant_clients.len() {
-        participant_clients[i].receive_announcement(&ann_address).await?;
-        let subscribe_msg = participant_clients[i].send_subscribe(&ann_address).await?;
-        let sub_result = organization_id.identity.channel_client.receive_subscribe(&subscribe_msg).await;
-
-        // either the subscribe works and the program continues, or it doesnt because
-        // the author already has the tn as a subscriber and the program continues
-        match sub_result {
-            Ok(()) => {println!("-- Participant {} is now subscribed", i);},
-            Err(_) => {},
-        };
-    }
-    for i in 0..witness_clients.len() {
-        witness_clients[i].receive_announcement(&ann_address).await?;
-        let subscribe_msg = witness_clients[i].send_subscribe(&ann_address).await?;
-        let sub_result = organization_id.identity.channel_client.receive_subscribe(&subscribe_msg).await;
-
-        match sub_result {
-            Ok(()) => {println!("-- Witness {} is now subscribed", i);},
-            Err(_) => {},
-        };
+    let opened = ledger.open_interaction(
+        organization_id, &mut participant_clients, &mut witness_clients, &decision, run, faults
+    ).await?;
+    if !opened {
+        return Ok(None);
     }
-    println!("");
-
-    println!("Organization sends keyload message to these clients:");
-    let (keyload_a_link, _seq_a_link) =
-    organization_id.identity.channel_client.send_keyload_for_everyone(&announcement_link).await?;
-    println!("-- Keyload sent\n");
 
     //--------------------------------------------------------------
     // WITNESSES GENERATE SIGS
     //--------------------------------------------------------------
 
-    println!("Witnesses generate their signatures:");
+    if print {
+        println!("Witnesses generate their signatures:");
+    }
     let mut witness_sigs: Vec<witness_sig::WitnessSig> = Vec::new();
     let mut witness_sigs_bytes: Vec<Vec<u8>> = Vec::new();
 
     for i in 0..witness_clients.len() {
-        let multibase_pub = MethodData::new_multibase(witness_clients[i].get_public_key());
-        let channel_pk_as_multibase: String;
-        if let MethodData::PublicKeyMultibase(mbpub) = multibase_pub {
-            channel_pk_as_multibase = mbpub;
-        }
-        else {
-            panic!("Could not encode public key as multibase")
-        }
-
         let sig = generate_sigs::generate_witness_sig(
             contract.clone(),
-            channel_pk_as_multibase,
+            channel_pk_as_multibase(witness_clients[i]),
             witness_did_kp[i].clone(),
             witness_org_certs[i].clone(),
-            DEFAULT_TIMEOUT
+            ledger.timeout(DEFAULT_TIMEOUT)
         )?;
         witness_sigs.push(sig.clone());
 
@@ -238,7 +242,9 @@ pub async fn interaction(
         let sig_bytes = sig.signature;
         witness_sigs_bytes.push(sig_bytes);
     }
-    println!("-- Witness signatures generated\n");
+    if print {
+        println!("-- Witness signatures generated\n");
+    }
 
     //--------------------------------------------------------------
     // TRANSACTING NODES GENERATE SIGS
@@ -257,69 +263,61 @@ pub async fn interaction(
         })
         .collect();
 
-    println!("Participants generate their signatures:");
+    if print {
+        println!("Participants generate their signatures:");
+    }
     let mut participant_sigs: Vec<interaction_sig::InteractionSig> = Vec::new();
     for i in 0..participant_clients.len() {
-        let multibase_pub = MethodData::new_multibase(participant_clients[i].get_public_key());
-        let channel_pk_as_multibase: String;
-        if let MethodData::PublicKeyMultibase(mbpub) = multibase_pub {
-            channel_pk_as_multibase = mbpub;
-        }
-        else {
-            panic!("Could not encode public key as multibase")
-        }
         let sig = generate_sigs::generate_participant_sig(
             contract.clone(),
-            channel_pk_as_multibase,
+            channel_pk_as_multibase(participant_clients[i]),
             participant_did_kp[i].clone(),
             WitnessUsers(witnesses.clone()),
             interaction_sig::ArrayOfWnSignituresBytes(witness_sigs_bytes.clone()),
             participant_org_certs[i].clone(),
-            DEFAULT_TIMEOUT
+            ledger.timeout(DEFAULT_TIMEOUT)
         )?;
         participant_sigs.push(sig);
     }
-    println!("-- Participant signatures generated\n");
+    if print {
+        println!("-- Participant signatures generated\n");
+    }
 
     //--------------------------------------------------------------
     // INITIATING TN, HAVING REVEIVED THE SIGNATURES, 
-    // BUILD FINAL TRANSACTION (TN = TRANSACTING NODE)
+    // BUILD FINAL INTERACTION (TN = TRANSACTING NODE)
     //--------------------------------------------------------------
 
-    println!("Initiating participant generates InteractionMessage:");
+    if print {
+        println!("Initiating participant generates InteractionMessage:");
+    }
     let interaction_msg = Message::InteractionMsg {
         contract: contract.clone(),
         witnesses: WitnessUsers(witnesses.clone()),
         witness_sigs: ArrayOfWnSignitures(witness_sigs.clone()),
         interaction_sigs: ArrayOfIntSignitures(participant_sigs.clone()),
     };
-    println!("-- InteractionMessage generated");
     
     //--------------------------------------------------------------
     // INITIATING TN SENDS THE INTERACTION MESSAGE
     //--------------------------------------------------------------
 
-    // serialise the tx
-    let mut tx_msg_str = serde_json::to_string(&interaction_msg)?; 
-    tx_msg_str = workaround_channel_bug(run, tx_msg_str);
-    let tx_message = vec![
-        tx_msg_str
-    ];
-    println!("-- InteractionMessage serialized\n");
-
-
-    // TN_A sends the interaction
-    println!("Initiating participant sends InteractionMessage:");
-    let mut prev_msg_link = keyload_a_link;
-    sync_all(&mut participant_clients).await?;
-    sync_all(&mut witness_clients).await?;
-    let (msg_link, _) = participant_clients[0].send_signed_packet(
-        &prev_msg_link,
-        &Bytes(tx_message[0].as_bytes().to_vec()),
-        &Bytes::default(),
+    if print {
+        println!("Initiating participant sends InteractionMessage:");
+    }
+    let synced = ledger.sync(&mut participant_clients, &mut witness_clients, faults).await?;
+    let sent = synced && ledger.publish_interaction_msg(
+        participant_clients[0], &participant_dids[0], &interaction_msg, run, organization_id, faults, made
     ).await?;
-    println!("-- InteractionMessage sent. ID: {}, tangle index: {:#}\n", msg_link, msg_link.to_msg_index());
-    prev_msg_link = msg_link;
+    if !sent {
+        if synced {
+            println!("-- InteractionMessage was dropped every time it was sent\n");
+        } else {
+            println!("-- InteractionMessage could not be sent\n");
+            faults.abandon();
+        }
+        return Ok(None);
+    }
 
     //--------------------------------------------------------------
     // THE EVENT IN QUESTION ON THE CONTRACT PLAYS OUT
@@ -332,14 +330,20 @@ pub async fn interaction(
     // being, the counterparty may still compensate them even if they act dishonestly,
     // but only if the witnesses side with the dishonest node, thus jepordising the 
     // the conterparties trust score.
-    println!("Assigning tranascting nodes as (dis)honest according to their reliability:");
-    let honest_tranascting_ids = get_honest_users(participant_reliablity);
-    println!("Assigning witnesses as (dis)honest according to their reliability:");
-    let honest_witness_ids = get_honest_users(witness_reliability);
+    if print {
+        println!("Assigning tranascting nodes as (dis)honest according to their reliability:");
+    }
+    let honest_tranascting_ids = get_honest_users(participant_reliablity, rand_gen);
+    if print {
+        println!("Assigning witnesses as (dis)honest according to their reliability:");
+    }
+    let honest_witness_ids = get_honest_users(witness_reliability, rand_gen);
 
     // A vector of vectors, the inner a list of the outcomes per participant from
     // the witnesses point of view.
-    println!("Witnesses decide on the outcome:");
+    if print {
+        println!("Witnesses decide on the outcome:");
+    }
     let mut outcomes: Vec<Vec<bool>> = vec![Vec::new(); honest_witness_ids.len()];
     for i in 0..honest_witness_ids.len() {
         let honesty_of_wn = honest_witness_ids[i];
@@ -356,42 +360,75 @@ pub async fn interaction(
                 outcomes[i].push(honesty_of_tn);
                 println!("-- Witnesses {} responds honestly about participant {}", i, j);
             } else {
-                outcomes[i].push(lazy_outcome(&lazy_method));
+                outcomes[i].push(lazy_outcome(&lazy_method, rand_gen));
                 println!("-- Witnesses {} responds dishonestly about participant {}", i, j);
             }
         }
     }
-    println!("");
+    if print {
+        println!("");
+    }
 
     //--------------------------------------------------------------
     // WITNESSES SEND THEIR STATMENTS
     //--------------------------------------------------------------
 
-    println!("Witnesses generate and send their witness statements:");
+    if print {
+        println!("Witnesses generate and send their witness statements:");
+    }
+    let mut held_back: Vec<(HeldBack, Message, u32)> = Vec::new();
     for i in 0..witness_clients.len(){
-        // WN's prepares their statement
-        let wn_statement = Message::WitnessStatement {
-            outcome: Outcome::ExchangeApplication(outcomes[i].clone())
-        };
-        let mut wn_statement_string = serde_json::to_string(&wn_statement)?;
-
-        wn_statement_string = workaround_channel_bug(run, wn_statement_string);
-        let witness_message = vec![
-            wn_statement_string
-        ];
-
-        // WN sends their witness statement
-        sync_all(&mut participant_clients).await?;
-        sync_all(&mut witness_clients).await?;
-        let (msg_link, _) = witness_clients[i].send_signed_packet(
-            &prev_msg_link,
-            &Bytes(witness_message[0].as_bytes().to_vec()),
-            &Bytes::default(),
-        ).await?;
-        println!("-- Witness {} sent statement: ID: {}, tangle index: {:#}", i, msg_link, msg_link.to_msg_index());
-        prev_msg_link = msg_link;
+        if faults.inject(Fault::OfflineWitness) {
+            println!("-- Witness {} went offline", i);
+            continue;
+        }
+
+        // WN's prepares their statements, which abusive witnesses make more or fewer of.
+        // Those made after the participants compensate reach the channel after the deadline.
+        let others: Vec<Vec<bool>> = outcomes
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .map(|(_, outcome)| outcome.clone())
+            .collect();
+        let (on_time, late) = witness_statements(&witness_abuse[i], &outcomes[i], &others);
+        for outcome in late {
+            let statement = Message::WitnessStatement { outcome: Outcome::ExchangeApplication(outcome) };
+            held_back.push((HeldBack::Witness(i), statement, deadline.saturating_add(1)));
+        }
+
+        // WN sends their witness statements
+        for outcome in on_time {
+            let wn_statement = Message::WitnessStatement {
+                outcome: Outcome::ExchangeApplication(outcome)
+            };
+            if !ledger.sync(&mut participant_clients, &mut witness_clients, faults).await? {
+                println!("-- Witness {} could not reach the node", i);
+                continue;
+            }
+            match ledger.publish(witness_clients[i], &witness_dids[i], &wn_statement, run, organization_id, faults, made).await? {
+                Transmission::Published(id) => {
+                    if print {
+                        println!("-- Witness {} sent statement: ID: {}", i, id);
+                    }
+                },
+                Transmission::Dropped => println!("-- Witness {} statement was dropped", i),
+                Transmission::Delayed(arrival) => held_back.push((HeldBack::Witness(i), wn_statement, arrival))
+            };
+        }
+    }
+    if print {
+        println!("");
     }
-    println!("");
+
+    // the delayed statements which arrive before the deadline reach the channel
+    // before the participants compensate
+    held_back.sort_by_key(|(_, _, arrival)| *arrival);
+    let after_deadline = held_back.iter().position(|(_, _, arrival)| *arrival > deadline).unwrap_or(held_back.len());
+    let in_time: Vec<(HeldBack, Message, u32)> = held_back.drain(..after_deadline).collect();
+    send_held_back(
+        in_time, &mut participant_clients, &mut witness_clients, &participant_dids, &witness_dids, run, organization_id, ledger
+    ).await?;
 
     //--------------------------------------------------------------
     // THE PARTICIPANTS READ THE STATEMENTS AND DECIDE TO COMPENSATE
@@ -400,17 +437,19 @@ pub async fn interaction(
 
     // TODO - add read and choice
 
-    println!("Participants send compensation:");
+    if print {
+        println!("Participants send compensation:");
+    }
     for i in 0..participant_clients.len(){
+        if faults.inject(Fault::ParticipantCrash) {
+            println!("-- Participant {} crashed", i);
+            continue;
+        }
 
         // TODO - certain TNs need to compensate other TNs
 
-        // TN prepares the compensation transaction 
-        let payments_tn_a = vec![
-            //"tn_b: 0.1".to_string(),
-            "wn_a: 0.01".to_string(),
-            "wn_b: 0.01".to_string()
-        ];
+        // TN prepares the compensation transaction, paying the witness fees of the contract
+        let payments_tn_a = witness_payments(&contract);
         let compensation_msg = CompensationMsg {
             payments: payments_tn_a
         };
@@ -418,31 +457,62 @@ pub async fn interaction(
         // because CompensationMsg is application specific, it must be wrapped
         let wrapped_compensation_msg = Message::ApplicationMsg(ApplicationMsg::ExchangeApplication(compensation_msg));
 
-        let mut compensation_msg_str = serde_json::to_string(&wrapped_compensation_msg)?;
-
-        compensation_msg_str = workaround_channel_bug(run, compensation_msg_str);
-        let compensation_tx = vec![
-            compensation_msg_str
-        ];
-
         // TN sends the compensation transaction
-        sync_all(&mut participant_clients).await?;
-        sync_all(&mut witness_clients).await?;
-        let (msg_link, _) = participant_clients[i].send_signed_packet(
-            &prev_msg_link,
-            &Bytes(compensation_tx[0].as_bytes().to_vec()),
-            &Bytes::default(),
-        ).await?;
-        println!("-- Participant {} sent compensation: ID: {}, tangle index: {:#}", i, msg_link, msg_link.to_msg_index());
-        prev_msg_link = msg_link;
+        if !ledger.sync(&mut participant_clients, &mut witness_clients, faults).await? {
+            println!("-- Participant {} could not reach the node", i);
+            continue;
+        }
+        match ledger.publish(participant_clients[i], &participant_dids[i], &wrapped_compensation_msg, run, organization_id, faults, deadline).await? {
+            Transmission::Published(id) => {
+                if print {
+                    println!("-- Participant {} sent compensation: ID: {}", i, id);
+                }
+            },
+            Transmission::Dropped => println!("-- Participant {} compensation was dropped", i),
+            Transmission::Delayed(arrival) => held_back.push((HeldBack::Participant(i), wrapped_compensation_msg, arrival))
+        };
     }
-    println!("");
-    
-    return Ok(Some((honest_tranascting_ids, honest_witness_ids)));
+    if print {
+        println!("");
+    }
+
+    //--------------------------------------------------------------
+    // THE REMAINING DELAYED PACKETS REACH THE CHANNEL
+    //--------------------------------------------------------------
+
+    held_back.sort_by_key(|(_, _, arrival)| *arrival);
+    send_held_back(
+        held_back, &mut participant_clients, &mut witness_clients, &participant_dids, &witness_dids, run, organization_id, ledger
+    ).await?;
+
+    let (messages, arrivals) = ledger.interaction_msgs(organization_id, run).await?;
+    return Ok(Some((honest_tranascting_ids, honest_witness_ids, messages, arrivals)));
 }
 
-pub fn workaround_channel_bug(run: usize, tx: String) -> String {
-    let mut st = format!("{}", run);
-    st.push_str(&tx);
-    return st;
-}
\ No newline at end of file
+// Sends the packets which were held back, in the order given, each at the time it arrives.
+// They were already sent once, so no further faults are injected.
+async fn send_held_back(
+    packets: Vec<(HeldBack, Message, u32)>,
+    participant_clients: &mut Vec<&mut Subscriber<Client>>,
+    witness_clients: &mut Vec<&mut Subscriber<Client>>,
+    participant_dids: &Vec<String>,
+    witness_dids: &Vec<String>,
+    run: usize,
+    organization_id: &mut OrganizationIdentity,
+    ledger: &mut Ledger
+) -> Result<()> {
+    for (sender, packet, arrival) in packets {
+        ledger.sync(participant_clients, witness_clients, &mut FaultInjector::new(&None)).await?;
+        match sender {
+            HeldBack::Witness(i) => {
+                let id = ledger.send(witness_clients[i], &witness_dids[i], &packet, run, organization_id, arrival).await?;
+                println!("-- Witness {} statement arrived at {}: ID: {}", i, arrival, id);
+            },
+            HeldBack::Participant(i) => {
+                let id = ledger.send(participant_clients[i], &participant_dids[i], &packet, run, organization_id, arrival).await?;
+                println!("-- Participant {} compensation arrived at {}: ID: {}", i, arrival, id);
+            }
+        }
+    }
+    return Ok(());
+}
@@ -0,0 +1,482 @@
+use crate::witness_rep::{
+    iota_did::did_registry::DidResolver,
+    implementation::{
+        generate_sigs,
+        user_and_organization::{UserIdentity, OrganizationIdentity},
+        organization_policy::{KeyloadScope, PolicyDecision, PolicyMessage},
+        faults::{FaultInjector, Fault}
+    },
+    utility::{verify_interaction, read_msgs::ChannelReader, batch_verify::SignatureVerifier}
+};
+
+use wb_reputation_system::{
+    utility::parse_messages,
+    data_types::{
+        event_protocol_messages::event_protocol_messages::Message,
+        tsg_data_types::message::MessageAndPubkey
+    }
+};
+
+use iota_streams::{
+    app::transport::tangle::client::Client,
+    app_channels::api::tangle::{
+        Address, Bytes, Subscriber, Identifier,
+        PublicKey as ChannelPublicKey
+    },
+    core::{println, Result},
+    app::message::HasLink
+};
+use identity::did::MethodData;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use core::str::FromStr;
+use anyhow::anyhow;
+
+/// Where the messages of the interactions are published. The protocol, and so the
+/// messages, are the same whichever is chosen.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum Backend {
+    /// The organizations' Streams channels, through the node at node_url. The messages
+    /// of each interaction are read back from the channel and verified
+    Streams,
+    /// A channel for each organization kept in memory, whose branches are verified like
+    /// those read from Streams. No node is needed
+    InMemory,
+    /// No ledger: the messages of each interaction are handed to the TSGs as they are sent
+    Quick,
+}
+
+/// What became of a packet sent through the fault injector
+#[derive(Clone, Debug)]
+pub enum Transmission {
+    /// The packet is on the channel, with the given id
+    Published(String),
+    /// The packet never reaches the channel
+    Dropped,
+    /// The packet is held back, and reaches the channel at the given time
+    Delayed(u32),
+}
+
+// The ledger the interactions run on. Besides publishing, it keeps the state of the
+// interaction in progress: the message the next Streams packet links to, or the branch
+// being written in memory.
+pub struct Ledger {
+    pub backend: Backend,
+    node_url: String,
+    /// If set, signatures are verified against the registered DID documents
+    resolver: Option<Box<dyn DidResolver>>,
+    /// The time signatures and certificates are made at. If None, the current time
+    clock: Option<u32>,
+    /// Streams: the message the next packet of the interaction links to
+    prev_link: Option<Address>,
+    /// Streams: a reader of each organization's channel, by announcement, which keeps its place in it
+    readers: HashMap<String, ChannelReader>,
+    /// In memory: the branches of each channel by run, as extract_msgs reads them from Streams,
+    /// with the time each message reached the channel
+    channels: HashMap<String, BTreeMap<usize, Vec<(String, String, u32)>>>,
+    /// Quick: the messages of the interaction in progress
+    sent: Vec<MessageAndPubkey>,
+    /// Quick: when each of the messages reached the channel
+    arrivals: Vec<u32>,
+    /// Verifies the messages read back from the channels, remembering the valid signatures
+    verifier: SignatureVerifier,
+}
+
+impl Ledger {
+    pub fn new(backend: Backend, node_url: &str, resolver: Option<Box<dyn DidResolver>>) -> Ledger {
+        return Ledger {
+            backend: backend,
+            node_url: String::from(node_url),
+            resolver: resolver,
+            clock: None,
+            prev_link: None,
+            readers: HashMap::new(),
+            channels: HashMap::new(),
+            sent: Vec::new(),
+            arrivals: Vec::new(),
+            verifier: SignatureVerifier::new()
+        };
+    }
+
+    // Fixes the time signatures are made at, so that a run can be reproduced exactly
+    pub fn with_clock(mut self, time: u32) -> Ledger {
+        self.clock = Some(time);
+        return self;
+    }
+
+    pub fn clock(&self) -> Option<u32> {
+        return self.clock;
+    }
+
+    pub fn resolver(&self) -> Option<&dyn DidResolver> {
+        return self.resolver.as_deref();
+    }
+
+    // The readers of the organizations' channels, with the resolver to verify what they read
+    // against. Those reading the ledger after the runs, like the auditor, reuse the readers
+    // and the branches they have read so far.
+    pub fn readers(&mut self) -> (&mut HashMap<String, ChannelReader>, Option<&dyn DidResolver>) {
+        return (&mut self.readers, self.resolver.as_deref());
+    }
+
+    // The registry the DID documents of the population are published to, if there is one
+    pub fn resolver_mut(&mut self) -> Option<&mut (dyn DidResolver + 'static)> {
+        return self.resolver.as_deref_mut();
+    }
+
+    // The point in time the given number of seconds from now
+    pub fn timeout(&self, duration: u32) -> u32 {
+        return match self.clock {
+            Some(time) => time + duration,
+            None       => generate_sigs::get_timeout(duration)
+        };
+    }
+
+    // The organization opens its channel. On Streams, the reader every organization
+    // lets read its channel is subscribed to it.
+    pub async fn open_channel(&mut self, organization: &mut OrganizationIdentity, index: usize) -> Result<()> {
+        println!("Creating the channel for organization {}:", index);
+        if self.backend != Backend::Streams {
+            let ann_msg = format!("in-memory channel {}", index);
+            self.channels.insert(ann_msg.clone(), BTreeMap::new());
+            organization.ann_msg = Some(ann_msg);
+            return Ok(());
+        }
+
+        let announcement_link = organization.identity.channel_client.send_announce().await?;
+        let ann_link_string = announcement_link.to_string();
+        println!(
+            "-- Announcement Link: {} Tangle Index: {:#}\n",
+            ann_link_string, announcement_link.to_msg_index()
+        );
+
+        /* instantiate the reader subscriber (each sub can only attatch to one channel, but the)
+        name parameter is what determines the keypair values*/
+        let client = Client::new_from_url(&self.node_url);
+        let mut reader_sub = Subscriber::new("reader", client);
+
+        // we add the reader subscriber to the channel
+        reader_sub.receive_announcement(&announcement_link).await?;
+        let subscribe_msg = reader_sub.send_subscribe(&announcement_link).await?;
+        organization.identity.channel_client.receive_subscribe(&subscribe_msg).await?;
+        organization.readers.push(reader_sub.get_public_key().clone());
+
+        // change the organization struct to include their channel announcement
+        organization.ann_msg = Some(ann_link_string);
+        return Ok(());
+    }
+
+    // Attaches the clients of the users about to interact under the organization to its
+    // channel. A Streams subscriber can only be attached to one channel, so a user whose
+    // client is attached to another gets a fresh one from their seed, which subscribes in
+    // open_interaction. The others keep theirs, which only sync what was published since.
+    pub fn attach_clients(&self, users: &mut Vec<UserIdentity>, organization: &OrganizationIdentity) {
+        if self.backend != Backend::Streams {
+            return;
+        }
+        for user in users.iter_mut() {
+            if user.id_info.state.channel == organization.ann_msg {
+                continue;
+            }
+            let client = Client::new_from_url(&self.node_url);
+            user.channel_client = Subscriber::new(&user.id_info.seed.clone().unwrap(), client);
+            user.id_info.state.channel = organization.ann_msg.clone();
+        }
+    }
+
+    // The organization turned the interaction away. A rejected interaction has no keyload,
+    // so on Streams the decision is linked to the announcement.
+    pub async fn reject(
+        &mut self,
+        organization: &mut OrganizationIdentity,
+        decision: &PolicyDecision,
+        run: usize
+    ) -> Result<()> {
+        if self.backend == Backend::Streams {
+            let announcement_link = Address::from_str(organization.ann_msg.as_ref().unwrap())?;
+            publish_decision(organization, &announcement_link, decision, run).await?;
+        }
+        return Ok(());
+    }
+
+    // The participants and witnesses subscribe to the organization's channel, and the
+    // organization sends the keyload of the interaction and publishes its decision on it.
+    // Returns false if a fault gives the interaction up, which the injector records. The
+    // other backends have no node, but its faults are injected all the same, so that the
+    // interactions of every backend fail alike.
+    pub async fn open_interaction(
+        &mut self,
+        organization: &mut OrganizationIdentity,
+        participant_clients: &mut Vec<&mut Subscriber<Client>>,
+        witness_clients: &mut Vec<&mut Subscriber<Client>>,
+        decision: &PolicyDecision,
+        run: usize,
+        faults: &mut FaultInjector
+    ) -> Result<bool> {
+        self.sent = Vec::new();
+        self.arrivals = Vec::new();
+        self.prev_link = None;
+        let streams = self.backend == Backend::Streams;
+
+        // participants process the channel announcement
+        println!("Participants subscribe to channel if not already subscribed:");
+        let announcement_link = match streams {
+            true  => Some(Address::from_str(organization.ann_msg.as_ref().unwrap())?),
+            false => None
+        };
+        let roles = vec![("Participant", &mut *participant_clients), ("Witness", &mut *witness_clients)];
+        for (role, clients) in roles {
+            for i in 0..clients.len() {
+                let subscribe_msg = match &announcement_link {
+                    // the client subscribed when it was attached to the channel
+                    Some(_) if clients[i].is_registered() => None,
+                    Some(link) => {
+                        let ann_address = Address::try_from_bytes(&link.to_bytes())?;
+                        clients[i].receive_announcement(&ann_address).await?;
+                        Some(clients[i].send_subscribe(&ann_address).await?)
+                    },
+                    None => None
+                };
+                if faults.inject_with_retries(Fault::NodeError) {
+                    println!("-- The node failed to process the subscription of {} {}\n", role, i);
+                    faults.abandon();
+                    return Ok(false);
+                }
+                let subscribe_msg = match subscribe_msg {
+                    Some(msg) => msg,
+                    None => continue
+                };
+                let sub_result = organization.identity.channel_client.receive_subscribe(&subscribe_msg).await;
+
+                // either the subscribe works and the program continues, or it doesnt because
+                // the author already has the tn as a subscriber and the program continues
+                match sub_result {
+                    Ok(()) => {println!("-- {} {} is now subscribed", role, i);},
+                    Err(e) => {println!("-- {} {} not subscribed again: {}", role, i, e);},
+                };
+            }
+        }
+        println!("");
+
+        println!("Organization sends keyload message to these clients:");
+        if faults.inject(Fault::KeyloadFailure) {
+            println!("-- Keyload failed\n");
+            faults.abandon();
+            return Ok(false);
+        }
+        let announcement_link = match announcement_link {
+            Some(link) => link,
+            None => return Ok(true)
+        };
+        let (keyload_a_link, _seq_a_link) = match organization.policy.keyload_scope() {
+            KeyloadScope::Everyone => {
+                organization.identity.channel_client.send_keyload_for_everyone(&announcement_link).await?
+            },
+            KeyloadScope::InteractionOnly => {
+                let mut keys: Vec<ChannelPublicKey> = organization.readers.clone();
+                for client in participant_clients.iter().chain(witness_clients.iter()) {
+                    keys.push(client.get_public_key().clone());
+                }
+                let keys: Vec<Identifier> = keys.into_iter().map(|pk| pk.into()).collect();
+                organization.identity.channel_client.send_keyload(&announcement_link, &keys).await?
+            }
+        };
+        println!("-- Keyload sent\n");
+
+        // the decision is published on the interaction's branch
+        publish_decision(organization, &keyload_a_link, decision, run).await?;
+        self.prev_link = Some(keyload_a_link);
+        return Ok(true);
+    }
+
+    // Brings the clients up to date with the channel, retrying the calls the node fails.
+    // Returns false if a client could still not be synced after MAX_NODE_RETRIES retries.
+    // Only Streams clients have anything to sync.
+    pub async fn sync(
+        &mut self,
+        participant_clients: &mut Vec<&mut Subscriber<Client>>,
+        witness_clients: &mut Vec<&mut Subscriber<Client>>,
+        faults: &mut FaultInjector
+    ) -> Result<bool> {
+        for sub in participant_clients.iter_mut().chain(witness_clients.iter_mut()) {
+            if faults.inject_with_retries(Fault::NodeError) {
+                return Ok(false);
+            }
+            if self.backend == Backend::Streams {
+                sub.sync_state().await?;
+            }
+        }
+        return Ok(true);
+    }
+
+    // Publishes the InteractionMsg at the given time, and its initiator resends it while it is
+    // dropped. Returns false if it never reaches the channel, giving the interaction up.
+    pub async fn publish_interaction_msg(
+        &mut self,
+        sender: &mut Subscriber<Client>,
+        sender_did: &str,
+        msg: &Message,
+        run: usize,
+        organization: &OrganizationIdentity,
+        faults: &mut FaultInjector,
+        sent_at: u32
+    ) -> Result<bool> {
+        if faults.inject_with_retries(Fault::DroppedPacket) {
+            faults.abandon();
+            return Ok(false);
+        }
+        let id = self.send(sender, sender_did, msg, run, organization, sent_at).await?;
+        println!("-- InteractionMessage sent. ID: {}\n", id);
+        return Ok(true);
+    }
+
+    // Publishes the packet sent at the given time on the interaction's branch, unless the
+    // faults drop or delay it. A delayed packet is left to the sender to send once it arrives.
+    pub async fn publish(
+        &mut self,
+        sender: &mut Subscriber<Client>,
+        sender_did: &str,
+        msg: &Message,
+        run: usize,
+        organization: &OrganizationIdentity,
+        faults: &mut FaultInjector,
+        sent_at: u32
+    ) -> Result<Transmission> {
+        if faults.inject(Fault::DroppedPacket) {
+            return Ok(Transmission::Dropped);
+        }
+        if faults.inject(Fault::DelayedPacket) {
+            return Ok(Transmission::Delayed(sent_at.saturating_add(faults.delay())));
+        }
+        let id = self.send(sender, sender_did, msg, run, organization, sent_at).await?;
+        return Ok(Transmission::Published(id));
+    }
+
+    // Publishes the packet whatever the faults, e.g. one that was delayed, stamped with the
+    // time it reaches the channel. Returns its id.
+    pub async fn send(
+        &mut self,
+        sender: &mut Subscriber<Client>,
+        sender_did: &str,
+        msg: &Message,
+        run: usize,
+        organization: &OrganizationIdentity,
+        arrival: u32
+    ) -> Result<String> {
+        let payload = serde_json::to_string(msg)?;
+        match self.backend {
+            Backend::Streams => {
+                let (msg_link, _) = sender.send_signed_packet(
+                    self.prev_link.as_ref().unwrap(),
+                    &Bytes(workaround_channel_bug(run, stamp_arrival(arrival, payload)).as_bytes().to_vec()),
+                    &Bytes::default(),
+                ).await?;
+                let id = format!("{}, tangle index: {:#}", msg_link, msg_link.to_msg_index());
+                self.prev_link = Some(msg_link);
+                return Ok(id);
+            },
+            Backend::InMemory => {
+                // like extract_msgs, an InteractionMsg starts the branch of the run
+                let channel = self.channels.entry(organization.ann_msg.clone().unwrap()).or_default();
+                if let Message::InteractionMsg { .. } = msg {
+                    channel.insert(run, Vec::new());
+                }
+                let branch = channel.entry(run).or_default();
+                branch.push((payload, channel_pk_as_multibase(sender), arrival));
+                return Ok(format!("{} on branch {}", branch.len() - 1, run));
+            },
+            Backend::Quick => {
+                self.sent.push(MessageAndPubkey {
+                    message: msg.clone(),
+                    sender_did: String::from(sender_did)
+                });
+                self.arrivals.push(arrival);
+                return Ok(format!("{}", self.sent.len() - 1));
+            }
+        }
+    }
+
+    // The messages of the interaction as the users reading the ledger get them, and when
+    // each reached the channel: read back from the channel and verified, except without a ledger
+    pub async fn interaction_msgs(
+        &mut self,
+        organization: &OrganizationIdentity,
+        run: usize
+    ) -> Result<(Vec<MessageAndPubkey>, Vec<u32>)> {
+        let ann_msg = organization.ann_msg.as_ref().unwrap();
+        let application = String::from("ExchangeApplication");
+        let (branch, arrivals): (Vec<(String, String)>, Vec<u32>) = match self.backend {
+            Backend::Quick => return Ok((
+                std::mem::replace(&mut self.sent, Vec::new()),
+                std::mem::replace(&mut self.arrivals, Vec::new())
+            )),
+            // only the messages published since the last interaction are fetched, and only
+            // those of this run's branch are read
+            Backend::Streams => {
+                let reader = ChannelReader::open(&self.node_url, ann_msg, &mut self.readers).await?;
+                reader.fetch().await?;
+                let which = verify_interaction::WhichBranch::OneBranch(run);
+                (
+                    reader.branches(&which).into_iter().flatten().collect(),
+                    reader.arrivals(&which).into_iter().flatten().collect()
+                )
+            },
+            Backend::InMemory => self.channels
+                .get(ann_msg)
+                .and_then(|channel| channel.get(&run))
+                .map(|branch| branch
+                    .iter()
+                    .map(|(payload, pk, arrival)| ((payload.clone(), pk.clone()), *arrival))
+                    .unzip()
+                )
+                .unwrap_or_default()
+        };
+
+        // a branch which fails verification is an error for the caller to handle, as the
+        // reader is kept between runs
+        if !verify_interaction::verify_branch(&branch, &arrivals, application, true, self.resolver.as_deref(), &mut self.verifier)? {
+            return Err(anyhow!("One of the messages of run {} could not be verified", run));
+        }
+        return Ok((parse_messages::parse_messages(&branch)?, arrivals));
+    }
+}
+
+// The organization publishes its policy decision on the channel, so that anyone
+// reading it can see why an interaction was admitted or not
+pub async fn publish_decision(
+    organization_id: &mut OrganizationIdentity,
+    link_to: &Address,
+    decision: &PolicyDecision,
+    run: usize
+) -> Result<()> {
+    let decision_msg = PolicyMessage::PolicyDecision(decision.clone());
+    let mut decision_str = serde_json::to_string(&decision_msg)?;
+    decision_str = workaround_channel_bug(run, decision_str);
+
+    let (msg_link, _) = organization_id.identity.channel_client.send_signed_packet(
+        link_to,
+        &Bytes(decision_str.as_bytes().to_vec()),
+        &Bytes::default(),
+    ).await?;
+    println!("-- Organization published decision: ID: {}, tangle index: {:#}\n", msg_link, msg_link.to_msg_index());
+    return Ok(());
+}
+
+// Prefixes the packet with the time it reaches the channel, relative to the contract. It stands
+// in for the time the node receives it, which the simulated delays cannot change.
+pub fn stamp_arrival(arrival: u32, tx: String) -> String {
+    return format!("@{}{}", arrival, tx);
+}
+
+pub fn workaround_channel_bug(run: usize, tx: String) -> String {
+    let mut st = format!("{}", run);
+    st.push_str(&tx);
+    return st;
+}
+
+pub fn channel_pk_as_multibase(client: &Subscriber<Client>) -> String {
+    if let MethodData::PublicKeyMultibase(mbpub) = MethodData::new_multibase(client.get_public_key()) {
+        return mbpub;
+    }
+    panic!("Could not encode public key as multibase")
+}
@@ -2,4 +2,17 @@ pub mod interaction;
 pub mod generate_sigs;
 pub mod generate_contract;
 pub mod user_and_organization;
-pub mod quick_interaction;
\ No newline at end of file
+pub mod organization_policy;
+pub mod organization_reputation;
+pub mod reputation_model;
+pub mod global_trust;
+pub mod recommendation;
+pub mod visibility;
+pub mod witness_selection;
+pub mod economy;
+pub mod scheduler;
+pub mod actor_interaction;
+pub mod faults;
+pub mod witness_abuse;
+pub mod ledger;
+pub mod user_arena;
@@ -0,0 +1,229 @@
+use crate::witness_rep::implementation::{
+    user_and_organization::IdInfo,
+    reputation_model::{check_participant, check_avg_participants, reputation_scores}
+};
+
+use wb_reputation_system::data_types::{
+    event_protocol_messages::signatures::organization_cert::OrganizationCertificate,
+    identity::identity::Identity
+};
+
+use iota_streams::{
+    app::transport::tangle::client::Client,
+    app_channels::api::tangle::Author
+};
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub type OrganizationId = Identity<Author<Client>, IdInfo>;
+
+/// Who the organization sends the keyload for an interaction to
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum KeyloadScope {
+    /// Every subscriber of the channel, including those of past interactions
+    Everyone,
+    /// Only the participants and witnesses of the interaction (and the channel readers)
+    InteractionOnly
+}
+
+/// How an organization decides whether to admit an interaction
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum AdmissionRule {
+    /// Admits every interaction
+    Open,
+    /// The average reputation of everyone involved must meet the organization's threshold
+    Average,
+    /// Every user involved must meet the organization's threshold
+    Minimum,
+    /// Participants and witnesses must meet separate thresholds
+    PerRole { participant_threshold: f32, witness_threshold: f32 }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PolicyConfig {
+    pub admission: AdmissionRule,
+    pub keyload: KeyloadScope,
+    /// Members involved in an interaction with a reputation below this are flagged
+    pub review_threshold: f32
+}
+
+/// The users involved in an interaction, as presented to the organization
+pub struct InteractionRequest {
+    pub participants: Vec<OrganizationCertificate>,
+    pub witnesses: Vec<OrganizationCertificate>
+}
+
+/// The outcome of applying a policy, which the organization publishes on its channel
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PolicyDecision {
+    pub run: usize,
+    pub policy: String,
+    pub admitted: bool,
+    pub reason: String,
+    /// Members of the organization flagged for review
+    pub flagged: Vec<String>
+}
+
+/// Wraps the decision so it is serialized with a tag, like the event protocol messages
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum PolicyMessage {
+    PolicyDecision(PolicyDecision)
+}
+
+pub trait OrganizationPolicy {
+    fn name(&self) -> String;
+
+    /// Returns whether the interaction is admitted, and why
+    fn admit(&self, org: &OrganizationId, request: &InteractionRequest) -> (bool, String);
+
+    fn keyload_scope(&self) -> KeyloadScope;
+
+    fn review_threshold(&self) -> f32;
+
+    /// Flags the organization's own members in the interaction whose reputation, in
+    /// the eyes of the organization, is below the review threshold
+    fn flag_for_review(&self, org: &OrganizationId, request: &InteractionRequest) -> Vec<String> {
+        let scores = reputation_scores(org);
+        return request.participants
+            .iter()
+            .chain(request.witnesses.iter())
+            .filter(|cert| cert.org_pubkey == org.id_info.org_cert.org_pubkey)
+            .map(|cert| cert.client_pubkey.clone())
+            .filter(|pk| get_score(&scores, pk, org.user_default_reputation) < self.review_threshold())
+            .collect();
+    }
+
+    fn decide(&self, org: &OrganizationId, request: &InteractionRequest, run: usize) -> PolicyDecision {
+        let (admitted, reason) = self.admit(org, request);
+        return PolicyDecision {
+            run: run,
+            policy: self.name(),
+            admitted: admitted,
+            reason: reason,
+            flagged: self.flag_for_review(org, request)
+        };
+    }
+}
+
+pub struct OpenPolicy {
+    pub keyload: KeyloadScope,
+    pub review_threshold: f32
+}
+
+impl OrganizationPolicy for OpenPolicy {
+    fn name(&self) -> String { String::from("Open") }
+    fn keyload_scope(&self) -> KeyloadScope { self.keyload.clone() }
+    fn review_threshold(&self) -> f32 { self.review_threshold }
+
+    fn admit(&self, _org: &OrganizationId, _request: &InteractionRequest) -> (bool, String) {
+        return (true, String::from("All interactions are admitted"));
+    }
+}
+
+/// The behaviour organizations had before policies were introduced
+pub struct AveragePolicy {
+    pub keyload: KeyloadScope,
+    pub review_threshold: f32
+}
+
+impl OrganizationPolicy for AveragePolicy {
+    fn name(&self) -> String { String::from("Average") }
+    fn keyload_scope(&self) -> KeyloadScope { self.keyload.clone() }
+    fn review_threshold(&self) -> f32 { self.review_threshold }
+
+    fn admit(&self, org: &OrganizationId, request: &InteractionRequest) -> (bool, String) {
+        let pks: Vec<String> = get_all_pubkeys(request);
+        if check_avg_participants(org, &pks) {
+            return (true, String::from("Average reputation meets the threshold"));
+        }
+        return (false, String::from("Average reputation is below the threshold"));
+    }
+}
+
+pub struct MinimumPolicy {
+    pub keyload: KeyloadScope,
+    pub review_threshold: f32
+}
+
+impl OrganizationPolicy for MinimumPolicy {
+    fn name(&self) -> String { String::from("Minimum") }
+    fn keyload_scope(&self) -> KeyloadScope { self.keyload.clone() }
+    fn review_threshold(&self) -> f32 { self.review_threshold }
+
+    fn admit(&self, org: &OrganizationId, request: &InteractionRequest) -> (bool, String) {
+        for pk in get_all_pubkeys(request).iter() {
+            if !check_participant(org, pk) {
+                return (false, format!("{} is below the threshold", pk));
+            }
+        }
+        return (true, String::from("Every user meets the threshold"));
+    }
+}
+
+pub struct PerRolePolicy {
+    pub keyload: KeyloadScope,
+    pub review_threshold: f32,
+    pub participant_threshold: f32,
+    pub witness_threshold: f32
+}
+
+impl OrganizationPolicy for PerRolePolicy {
+    fn name(&self) -> String { String::from("PerRole") }
+    fn keyload_scope(&self) -> KeyloadScope { self.keyload.clone() }
+    fn review_threshold(&self) -> f32 { self.review_threshold }
+
+    fn admit(&self, org: &OrganizationId, request: &InteractionRequest) -> (bool, String) {
+        let scores = reputation_scores(org);
+        let default = org.user_default_reputation;
+        for cert in request.participants.iter() {
+            if get_score(&scores, &cert.client_pubkey, default) < self.participant_threshold {
+                return (false, format!("Participant {} is below the threshold", cert.client_pubkey));
+            }
+        }
+        for cert in request.witnesses.iter() {
+            if get_score(&scores, &cert.client_pubkey, default) < self.witness_threshold {
+                return (false, format!("Witness {} is below the threshold", cert.client_pubkey));
+            }
+        }
+        return (true, String::from("Every user meets the threshold of their role"));
+    }
+}
+
+pub fn new_policy(config: &PolicyConfig) -> Box<dyn OrganizationPolicy> {
+    let keyload = config.keyload.clone();
+    let review_threshold = config.review_threshold;
+    return match config.admission {
+        AdmissionRule::Open     => Box::new(OpenPolicy { keyload, review_threshold }),
+        AdmissionRule::Average  => Box::new(AveragePolicy { keyload, review_threshold }),
+        AdmissionRule::Minimum  => Box::new(MinimumPolicy { keyload, review_threshold }),
+        AdmissionRule::PerRole { participant_threshold, witness_threshold } => Box::new(PerRolePolicy {
+            keyload,
+            review_threshold,
+            participant_threshold,
+            witness_threshold
+        }),
+    };
+}
+
+/// The policy organizations use when none is configured
+pub fn default_policy_config() -> PolicyConfig {
+    return PolicyConfig {
+        admission: AdmissionRule::Average,
+        keyload: KeyloadScope::Everyone,
+        review_threshold: 0.0
+    };
+}
+
+pub fn get_all_pubkeys(request: &InteractionRequest) -> Vec<String> {
+    return request.participants
+        .iter()
+        .chain(request.witnesses.iter())
+        .map(|cert| cert.client_pubkey.clone())
+        .collect();
+}
+
+// Users the organization has no reputation for yet are given its default reputation
+pub fn get_score(scores: &HashMap<String, f32>, pk: &str, default: f32) -> f32 {
+    return scores.get(pk).cloned().unwrap_or(default);
+}
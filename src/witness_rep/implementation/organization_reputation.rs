@@ -0,0 +1,138 @@
+use crate::witness_rep::implementation::{
+    user_and_organization::{UserIdentity, IdInfo},
+    reputation_model::{Role, check_participant_as, reputation_scores}
+};
+
+use wb_reputation_system::data_types::{
+    event_protocol_messages::event_protocol_messages::Message,
+    tsg_data_types::message::MessageAndPubkey
+};
+
+use std::collections::HashMap;
+
+/// A user's view of an organization: the average reputation of the organization's
+/// members the user has a reputation for, and how many such members there are
+pub type OrganizationReputation = (f32, usize);
+
+// Every InteractionMsg carries the organization certificate of each participant and
+// witness, so processing an interaction reveals which organization certified who
+pub fn get_memberships(msgs: &Vec<MessageAndPubkey>) -> Vec<(String, String)> {
+    let mut memberships: Vec<(String, String)> = Vec::new();
+    for msg in msgs.iter() {
+        if let Message::InteractionMsg {
+            contract: _, witnesses: _, witness_sigs, interaction_sigs
+        } = &msg.message {
+            for sig in witness_sigs.0.iter() {
+                memberships.push((sig.org_cert.client_pubkey.clone(), sig.org_cert.org_pubkey.clone()));
+            }
+            for sig in interaction_sigs.0.iter() {
+                memberships.push((sig.org_cert.client_pubkey.clone(), sig.org_cert.org_pubkey.clone()));
+            }
+        }
+    }
+    return memberships;
+}
+
+pub fn learn_memberships(id_info: &mut IdInfo, msgs: &Vec<MessageAndPubkey>) {
+    for (member, org) in get_memberships(msgs) {
+        id_info.state.org_memberships.insert(member, org);
+    }
+}
+
+// Derives a reputation for each organization from the reputations of its members
+pub fn organization_reputations(
+    scores: &HashMap<String, f32>,
+    memberships: &HashMap<String, String>
+) -> HashMap<String, OrganizationReputation> {
+    let mut totals: HashMap<String, (f32, usize)> = HashMap::new();
+    for (member, org) in memberships.iter() {
+        if let Some(score) = scores.get(member) {
+            let entry = totals.entry(org.clone()).or_insert((0.0, 0));
+            entry.0 += score;
+            entry.1 += 1;
+        }
+    }
+    return totals
+        .into_iter()
+        .map(|(org, (total, count))| (org, (total / count as f32, count)))
+        .collect();
+}
+
+pub fn get_organization_reputations(user: &UserIdentity) -> HashMap<String, OrganizationReputation> {
+    return organization_reputations(&reputation_scores(user), &user.id_info.state.org_memberships);
+}
+
+// Checks the candidate's own reputation, in the role they are considered for, and, if the
+// user discounts organizations, the reputation of the organization which certified the
+// candidate. Organizations the user knows no members of are given the benefit of the doubt.
+pub fn check_user(user: &UserIdentity, candidate: &UserIdentity, role: Role) -> bool {
+    let candidate_pk = &candidate.id_info.org_cert.client_pubkey;
+    if !check_participant_as(user, candidate_pk, role) {
+        return false;
+    }
+    return check_organization(user, candidate);
+}
+
+// Checks the reputation of the organization which certified the candidate, if the user
+// discounts organizations
+pub fn check_organization(user: &UserIdentity, candidate: &UserIdentity) -> bool {
+    return match user.id_info.state.organization_threshold {
+        None => true,
+        Some(threshold) => {
+            let candidate_org = &candidate.id_info.org_cert.org_pubkey;
+            match get_organization_reputations(user).get(candidate_org) {
+                Some((reputation, _)) => *reputation >= threshold,
+                None => true
+            }
+        }
+    };
+}
+
+// The average reputation of each organization, as held by the users who are not its
+// members. Written once per run, it shows how quickly the others discount an organization.
+pub fn organization_reputations_string(
+    run: usize,
+    users: &Vec<UserIdentity>,
+    org_pubkeys: &Vec<String>
+) -> String {
+    let user_views: Vec<(String, HashMap<String, OrganizationReputation>)> = users
+        .iter()
+        .map(|u| (u.id_info.org_cert.org_pubkey.clone(), get_organization_reputations(u)))
+        .collect();
+
+    let mut output = format!("Run {}\n", run);
+    for org_pk in org_pubkeys.iter() {
+        let outside_views: Vec<f32> = user_views
+            .iter()
+            .filter(|(own_org, _)| own_org != org_pk)
+            .filter_map(|(_, views)| views.get(org_pk).map(|(reputation, _)| *reputation))
+            .collect();
+        if outside_views.len() > 0 {
+            let avg = outside_views.iter().sum::<f32>() / outside_views.len() as f32;
+            output.push_str(&format!("{}: {}\n", org_pk, avg));
+        }
+    }
+    output.push_str("\n");
+    return output;
+}
+
+#[test]
+pub fn test_organization_reputations() {
+    let scores: HashMap<String, f32> = vec![
+        (String::from("a"), 1.0),
+        (String::from("b"), 0.5),
+        (String::from("c"), 0.0),
+    ].into_iter().collect();
+    let memberships: HashMap<String, String> = vec![
+        (String::from("a"), String::from("org0")),
+        (String::from("b"), String::from("org0")),
+        (String::from("c"), String::from("org1")),
+        (String::from("d"), String::from("org1")),
+    ].into_iter().collect();
+
+    let org_reps = organization_reputations(&scores, &memberships);
+    assert_eq!(org_reps["org0"], (0.75, 2));
+
+    // members without a reputation are not counted
+    assert_eq!(org_reps["org1"], (0.0, 1));
+}
@@ -0,0 +1,248 @@
+use crate::witness_rep::{
+    implementation::{
+        user_and_organization::UserIdentity,
+        generate_sigs::get_multibase,
+        organization_reputation::{check_user, check_organization},
+        reputation_model::{Role, reputation_scores}
+    },
+    utility::verify_interaction::get_signer_key
+};
+
+use iota_streams::core::Result;
+use identity::crypto::{KeyPair, Ed25519, Sign, Verify};
+
+use serde::{Deserialize, Serialize};
+use rand::Rng;
+use std::collections::HashMap;
+
+/// Before accepting a counterparty, a user asks nearby users what they think of it
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RecommendationConfig {
+    /// How many nearby users are asked at most
+    pub recommenders: usize,
+    /// The weight, in [0,1], of the recommendations against the user's own reputation
+    /// for the counterparty
+    pub weight: f32,
+    /// Indices of the users who recommend the opposite of what they think
+    pub liars: Vec<usize>,
+}
+
+/// A user's part in the recommendation protocol, and what it cost them
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Recommender {
+    pub recommenders: usize,
+    pub weight: f32,
+    pub lies: bool,
+    /// Requests sent plus opinions received
+    pub messages: usize,
+    /// The counterparty decisions made, and how many of them were right, i.e. accepted
+    /// a counterparty exactly when its reliability was above the user's threshold
+    pub decisions: usize,
+    pub correct: usize,
+}
+
+impl Recommender {
+    pub fn new(config: &RecommendationConfig, user: usize) -> Recommender {
+        return Recommender {
+            recommenders: config.recommenders,
+            weight: config.weight,
+            lies: config.liars.contains(&user),
+            messages: 0,
+            decisions: 0,
+            correct: 0
+        };
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OpinionPreSig {
+    pub asker: String,
+    pub subject: String,
+    pub reputation: f32,
+}
+
+/// The recommender's reputation for the subject, signed with the recommender's DID key.
+/// The asker is included so an opinion cannot be replayed to someone else.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Opinion {
+    pub asker: String,
+    pub subject: String,
+    pub reputation: f32,
+    pub recommender: String,
+    pub signer_did_pubkey: String,
+    pub signature: Vec<u8>,
+}
+
+// Answers a request for an opinion on the subject. Users without a reputation for
+// the subject have nothing to say.
+pub fn give_opinion(recommender: &UserIdentity, asker: &str, subject: &str) -> Result<Option<Opinion>> {
+    let reputation = match reputation_scores(recommender).get(subject) {
+        Some(r) => *r,
+        None    => return Ok(None)
+    };
+    let lies = recommender.id_info.state.recommender.as_ref().map_or(false, |r| r.lies);
+    let reputation = if lies { 1.0 - reputation } else { reputation };
+
+    let did_keypair = KeyPair::try_from_ed25519_bytes(&recommender.id_info.did_key)?;
+    let pre_sig = OpinionPreSig {
+        asker: String::from(asker),
+        subject: String::from(subject),
+        reputation: reputation
+    };
+    let pre_sig_bytes = serde_json::to_string(&pre_sig)?;
+    let signature: [u8; 64] = Ed25519::sign(&String::into_bytes(pre_sig_bytes), did_keypair.private())?;
+
+    return Ok(Some(Opinion {
+        asker: pre_sig.asker,
+        subject: pre_sig.subject,
+        reputation: reputation,
+        recommender: recommender.id_info.org_cert.client_pubkey.clone(),
+        signer_did_pubkey: get_multibase(&did_keypair),
+        signature: signature.to_vec()
+    }));
+}
+
+// Checks the opinion was signed by its recommender, for this asker and subject
+pub fn verify_opinion(opinion: &Opinion, asker: &str, subject: &str) -> Result<bool> {
+    if opinion.asker != asker || opinion.subject != subject {
+        return Ok(false);
+    }
+    let pre_sig = serde_json::to_string(&OpinionPreSig {
+        asker: opinion.asker.clone(),
+        subject: opinion.subject.clone(),
+        reputation: opinion.reputation
+    })?;
+    let decoded_pubkey = match get_signer_key(opinion.signer_did_pubkey.clone(), None)? {
+        Some(key)   => key,
+        None        => return Ok(false)
+    };
+    return Ok(Ed25519::verify(pre_sig.as_bytes(), &opinion.signature, &decoded_pubkey).is_ok());
+}
+
+// The opinions averaged, each weighted by the asker's reputation for its recommender.
+// None if there are no opinions or the asker trusts none of the recommenders.
+pub fn recommended_reputation(
+    trust: &HashMap<String, f32>,
+    default_reputation: f32,
+    opinions: &Vec<Opinion>
+) -> Option<f32> {
+    let mut total: f32 = 0.0;
+    let mut total_weight: f32 = 0.0;
+    for opinion in opinions.iter() {
+        let weight = trust.get(&opinion.recommender).cloned().unwrap_or(default_reputation);
+        total += weight * opinion.reputation;
+        total_weight += weight;
+    }
+    if total_weight > 0.0 {
+        return Some(total / total_weight);
+    }
+    return None;
+}
+
+// Whether the asker accepts the candidate, users[candidate], as a counterparty. Users
+// who take part in the protocol ask some of the other nearby users for their opinion and
+// fold it into their own reputation for the candidate; the others use check_user.
+pub fn check_counterparty(
+    asker: &mut UserIdentity,
+    candidate: usize,
+    users: &Vec<UserIdentity>,
+    average_proximity: f32,
+    rand_gen: &mut impl Rng
+) -> Result<bool> {
+    let config = match asker.id_info.state.recommender.clone() {
+        None    => return Ok(check_user(asker, &users[candidate], Role::Participant)),
+        Some(c) => c
+    };
+    let asker_pk = asker.id_info.org_cert.client_pubkey.clone();
+    let candidate_pk = users[candidate].id_info.org_cert.client_pubkey.clone();
+
+    let mut requests: usize = 0;
+    let mut messages: usize = 0;
+    let mut opinions: Vec<Opinion> = Vec::new();
+    for (j, user) in users.iter().enumerate() {
+        if requests >= config.recommenders {
+            break;
+        }
+        if j == candidate || average_proximity <= rand_gen.gen() {
+            continue;
+        }
+        requests += 1;
+        messages += 1;
+        if let Some(opinion) = give_opinion(user, &asker_pk, &candidate_pk)? {
+            messages += 1;
+            if verify_opinion(&opinion, &asker_pk, &candidate_pk)? {
+                opinions.push(opinion);
+            }
+        }
+    }
+
+    let trust = reputation_scores(asker);
+    let accepted = match recommended_reputation(&trust, asker.user_default_reputation, &opinions) {
+        None              => check_user(asker, &users[candidate], Role::Participant),
+        Some(recommended) => {
+            let own = trust.get(&candidate_pk).cloned().unwrap_or(asker.user_default_reputation);
+            let combined = (1.0 - config.weight) * own + config.weight * recommended;
+            combined >= asker.user_reputation_threshold && check_organization(asker, &users[candidate])
+        }
+    };
+
+    // only the simulation knows whether the decision was right
+    let deserved = users[candidate].id_info.reliability
+        .map_or(true, |r| r >= asker.user_reputation_threshold);
+    if let Some(recommender) = asker.id_info.state.recommender.as_mut() {
+        recommender.messages += messages;
+        recommender.decisions += 1;
+        if accepted == deserved {
+            recommender.correct += 1;
+        }
+    }
+    return Ok(accepted);
+}
+
+// The cost and accuracy of the counterparty decisions, summed over the users
+pub fn recommendation_summary_string(users: &Vec<UserIdentity>) -> String {
+    let (mut messages, mut decisions, mut correct) = (0, 0, 0);
+    for user in users.iter() {
+        if let Some(r) = &user.id_info.state.recommender {
+            messages += r.messages;
+            decisions += r.decisions;
+            correct += r.correct;
+        }
+    }
+    return format!("messages: {}\ndecisions: {}\ncorrect: {}\n", messages, decisions, correct);
+}
+
+#[test]
+pub fn test_opinions() {
+    let did_keypair = KeyPair::new_ed25519().unwrap();
+    let pre_sig = OpinionPreSig {
+        asker: String::from("asker"),
+        subject: String::from("subject"),
+        reputation: 0.8
+    };
+    let signature = Ed25519::sign(
+        serde_json::to_string(&pre_sig).unwrap().as_bytes(),
+        did_keypair.private()
+    ).unwrap();
+    let mut opinion = Opinion {
+        asker: pre_sig.asker,
+        subject: pre_sig.subject,
+        reputation: pre_sig.reputation,
+        recommender: String::from("r0"),
+        signer_did_pubkey: get_multibase(&did_keypair),
+        signature: signature.to_vec()
+    };
+    assert!(verify_opinion(&opinion, "asker", "subject").unwrap());
+    assert!(!verify_opinion(&opinion, "someone else", "subject").unwrap());
+    opinion.reputation = 0.2;
+    assert!(!verify_opinion(&opinion, "asker", "subject").unwrap());
+
+    // a trusted recommender outweighs a distrusted liar
+    let mut liar = opinion.clone();
+    liar.recommender = String::from("r1");
+    opinion.reputation = 0.9;
+    let trust: HashMap<String, f32> = vec![(String::from("r0"), 0.9), (String::from("r1"), 0.1)].into_iter().collect();
+    let recommended = recommended_reputation(&trust, 0.5, &vec![opinion, liar]).unwrap();
+    assert!((recommended - 0.83).abs() < 0.001);
+    assert_eq!(recommended_reputation(&trust, 0.5, &Vec::new()), None);
+}
@@ -0,0 +1,663 @@
+use crate::witness_rep::implementation::{
+    user_and_organization::{UserIdentity, IdInfo, parse_reputation_scores},
+    user_arena::{UserId, Interner}
+};
+
+use wb_reputation_system::data_types::identity::identity::Identity;
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+// The standard deviation of the uniform distribution, Beta(1,1)
+const UNIFORM_STD: f32 = 0.288675;
+
+/// How a user aggregates the verdicts of the TSG into reputations
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum ReputationModelConfig {
+    /// The model of the upstream Identity (the reputation map of wb_reputation_system)
+    Average,
+    /// Verdicts are evidence for a Beta distribution. The prior has the user's default
+    /// reputation as its expected value, and is worth prior_strength verdicts.
+    Beta { prior_strength: f32, min_confidence: f32 },
+    /// The average of the verdicts, where old verdicts are forgotten
+    Decayed { decay: Decay }
+}
+
+/// How a decayed model forgets old verdicts
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum Decay {
+    /// Each verdict about a user moves their reputation towards it by weight, in (0,1]
+    Exponential { weight: f32 },
+    /// Only the verdicts of the latest updates count. Every interaction processed gives
+    /// two updates, one for the transacting nodes and one for the witnesses.
+    SlidingWindow { updates: usize },
+    /// Only the latest verdicts about each user count
+    CappedMemory { verdicts: usize }
+}
+
+/// Everything a reputation model holds, by public key, so that it can be saved in a
+/// checkpoint and restored (see restore_model)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum ModelState {
+    Average { verdicts: HashMap<String, (f32, usize)> },
+    Beta {
+        prior_strength: f32,
+        min_confidence: f32,
+        default_reputation: f32,
+        evidence: HashMap<String, (f32, f32)>
+    },
+    Decayed {
+        decay: Decay,
+        clock: usize,
+        weighted: HashMap<String, (f32, usize)>,
+        history: HashMap<String, VecDeque<(usize, f32)>>
+    }
+}
+
+pub trait ReputationModel {
+    fn config(&self) -> ReputationModelConfig;
+
+    fn save(&self) -> ModelState;
+
+    fn update(&mut self, verdicts: &Vec<(String, f32)>);
+
+    /// The reputation of the user, or None if there is no evidence about them
+    fn expected(&self, pk: &str) -> Option<f32>;
+
+    /// How certain the model is of the user's reputation, in [0,1]
+    fn confidence(&self, pk: &str) -> f32;
+
+    fn scores(&self) -> HashMap<String, f32>;
+
+    fn check_participant(&self, pk: &str, threshold: f32, default: f32) -> bool {
+        return self.expected(pk).unwrap_or(default) >= threshold;
+    }
+}
+
+/// The role a user is judged in
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Role {
+    Participant,
+    Witness
+}
+
+/// Separate reputations for users as trading partners and as witnesses, kept
+/// alongside the user's combined reputations
+pub struct RoleReputations {
+    pub participant: Box<dyn ReputationModel>,
+    pub witness: Box<dyn ReputationModel>
+}
+
+impl RoleReputations {
+    pub fn get(&self, role: Role) -> &Box<dyn ReputationModel> {
+        return match role {
+            Role::Participant => &self.participant,
+            Role::Witness     => &self.witness
+        };
+    }
+}
+
+/// The mean of the verdicts, like the upstream model, for when reputations
+/// have to be kept apart from the Identity's own map
+pub struct AverageModel {
+    // the sum and number of the verdicts about each user
+    verdicts: HashMap<UserId, (f32, usize)>,
+    ids: Interner
+}
+
+impl AverageModel {
+    pub fn new(ids: &Interner) -> AverageModel {
+        return AverageModel { verdicts: HashMap::new(), ids: ids.clone() };
+    }
+}
+
+impl ReputationModel for AverageModel {
+    fn config(&self) -> ReputationModelConfig {
+        return ReputationModelConfig::Average;
+    }
+
+    fn save(&self) -> ModelState {
+        return ModelState::Average { verdicts: by_pubkey(&self.ids, &self.verdicts) };
+    }
+
+    fn update(&mut self, verdicts: &Vec<(String, f32)>) {
+        for (pk, verdict) in verdicts.iter() {
+            let entry = self.verdicts.entry(self.ids.intern(pk)).or_insert((0.0, 0));
+            entry.0 += verdict;
+            entry.1 += 1;
+        }
+    }
+
+    fn expected(&self, pk: &str) -> Option<f32> {
+        return self.ids.lookup(pk)
+            .and_then(|id| self.verdicts.get(&id))
+            .map(|(sum, count)| sum / *count as f32);
+    }
+
+    // the upstream model has no notion of confidence
+    fn confidence(&self, _pk: &str) -> f32 {
+        return 0.0;
+    }
+
+    fn scores(&self) -> HashMap<String, f32> {
+        return self.verdicts
+            .iter()
+            .map(|(id, (sum, count))| (self.ids.pubkey(*id), sum / *count as f32))
+            .collect();
+    }
+}
+
+pub struct BetaModel {
+    pub prior_strength: f32,
+    pub min_confidence: f32,
+    pub default_reputation: f32,
+    // the positive and negative evidence for each user
+    evidence: HashMap<UserId, (f32, f32)>,
+    ids: Interner
+}
+
+impl BetaModel {
+    pub fn new(prior_strength: f32, min_confidence: f32, default_reputation: f32, ids: &Interner) -> BetaModel {
+        return BetaModel {
+            prior_strength,
+            min_confidence,
+            default_reputation,
+            evidence: HashMap::new(),
+            ids: ids.clone()
+        };
+    }
+
+    // the parameters of the posterior distribution
+    fn alpha_beta(&self, pk: &str) -> (f32, f32) {
+        let (pos, neg) = self.evidence(pk).unwrap_or((0.0, 0.0));
+        let alpha = self.default_reputation * self.prior_strength + pos;
+        let beta = (1.0 - self.default_reputation) * self.prior_strength + neg;
+        return (alpha, beta);
+    }
+
+    pub fn evidence(&self, pk: &str) -> Option<(f32, f32)> {
+        return self.ids.lookup(pk).and_then(|id| self.evidence.get(&id).cloned());
+    }
+}
+
+impl ReputationModel for BetaModel {
+    fn config(&self) -> ReputationModelConfig {
+        return ReputationModelConfig::Beta {
+            prior_strength: self.prior_strength,
+            min_confidence: self.min_confidence
+        };
+    }
+
+    fn save(&self) -> ModelState {
+        return ModelState::Beta {
+            prior_strength: self.prior_strength,
+            min_confidence: self.min_confidence,
+            default_reputation: self.default_reputation,
+            evidence: by_pubkey(&self.ids, &self.evidence)
+        };
+    }
+
+    // a verdict v counts as v positive and 1-v negative evidence
+    fn update(&mut self, verdicts: &Vec<(String, f32)>) {
+        for (pk, verdict) in verdicts.iter() {
+            let entry = self.evidence.entry(self.ids.intern(pk)).or_insert((0.0, 0.0));
+            entry.0 += verdict;
+            entry.1 += 1.0 - verdict;
+        }
+    }
+
+    fn expected(&self, pk: &str) -> Option<f32> {
+        if self.evidence(pk).is_none() {
+            return None;
+        }
+        let (alpha, beta) = self.alpha_beta(pk);
+        return Some(alpha / (alpha + beta));
+    }
+
+    // one minus the standard deviation of the posterior, relative to that of the
+    // uniform distribution. More evidence gives a narrower posterior.
+    fn confidence(&self, pk: &str) -> f32 {
+        let (alpha, beta) = self.alpha_beta(pk);
+        let total = alpha + beta;
+        if total <= 0.0 {
+            return 0.0;
+        }
+        let variance = (alpha * beta) / (total * total * (total + 1.0));
+        return (1.0 - variance.sqrt() / UNIFORM_STD).max(0.0).min(1.0);
+    }
+
+    fn scores(&self) -> HashMap<String, f32> {
+        return self.evidence
+            .keys()
+            .map(|id| self.ids.pubkey(*id))
+            .filter_map(|pk| self.expected(&pk).map(|score| (pk, score)))
+            .collect();
+    }
+
+    // Users there is no evidence about are judged on the prior alone, like the default
+    // reputation of the upstream model, otherwise no interaction could ever take place
+    fn check_participant(&self, pk: &str, threshold: f32, default: f32) -> bool {
+        match self.expected(pk) {
+            None        => return default >= threshold,
+            Some(score) => return score >= threshold && self.confidence(pk) >= self.min_confidence
+        }
+    }
+}
+
+pub struct DecayedModel {
+    pub decay: Decay,
+    // the number of updates so far
+    clock: usize,
+    // the exponentially weighted reputation and the number of verdicts seen, for each user
+    weighted: HashMap<UserId, (f32, usize)>,
+    // the verdicts still remembered about each user, with the update they arrived in
+    history: HashMap<UserId, VecDeque<(usize, f32)>>,
+    ids: Interner
+}
+
+impl DecayedModel {
+    pub fn new(decay: Decay, ids: &Interner) -> DecayedModel {
+        return DecayedModel {
+            decay,
+            clock: 0,
+            weighted: HashMap::new(),
+            history: HashMap::new(),
+            ids: ids.clone()
+        };
+    }
+
+    fn remembered(&self, pk: &str) -> usize {
+        return self.ids.lookup(pk)
+            .and_then(|id| self.history.get(&id))
+            .map_or(0, |verdicts| verdicts.len());
+    }
+}
+
+impl ReputationModel for DecayedModel {
+    fn config(&self) -> ReputationModelConfig {
+        return ReputationModelConfig::Decayed { decay: self.decay.clone() };
+    }
+
+    fn save(&self) -> ModelState {
+        return ModelState::Decayed {
+            decay: self.decay.clone(),
+            clock: self.clock,
+            weighted: by_pubkey(&self.ids, &self.weighted),
+            history: by_pubkey(&self.ids, &self.history)
+        };
+    }
+
+    fn update(&mut self, verdicts: &Vec<(String, f32)>) {
+        self.clock += 1;
+        for (pk, verdict) in verdicts.iter() {
+            match self.decay {
+                Decay::Exponential { weight } => {
+                    // the first verdict about a user is taken as is
+                    let entry = self.weighted.entry(self.ids.intern(pk)).or_insert((*verdict, 0));
+                    entry.0 = (1.0 - weight) * entry.0 + weight * verdict;
+                    entry.1 += 1;
+                },
+                Decay::SlidingWindow { .. } | Decay::CappedMemory { .. } => {
+                    let remembered = self.history.entry(self.ids.intern(pk)).or_insert(VecDeque::new());
+                    remembered.push_back((self.clock, *verdict));
+                    if let Decay::CappedMemory { verdicts: cap } = self.decay {
+                        while remembered.len() > cap {
+                            remembered.pop_front();
+                        }
+                    }
+                }
+            }
+        }
+
+        // verdicts which slid out of the window are forgotten, and users with
+        // nothing left remembered become unknown again
+        if let Decay::SlidingWindow { updates } = self.decay {
+            let clock = self.clock;
+            for remembered in self.history.values_mut() {
+                while remembered.front().map_or(false, |(time, _)| clock - time >= updates) {
+                    remembered.pop_front();
+                }
+            }
+            self.history.retain(|_, remembered| remembered.len() > 0);
+        }
+    }
+
+    fn expected(&self, pk: &str) -> Option<f32> {
+        let id = self.ids.lookup(pk)?;
+        return match self.decay {
+            Decay::Exponential { .. } => self.weighted.get(&id).map(|(reputation, _)| *reputation),
+            _ => self.history
+                .get(&id)
+                .filter(|remembered| remembered.len() > 0)
+                .map(|remembered| {
+                    remembered.iter().map(|(_, verdict)| verdict).sum::<f32>() / remembered.len() as f32
+                })
+        };
+    }
+
+    // how much of the model's memory of the user is filled with verdicts
+    fn confidence(&self, pk: &str) -> f32 {
+        return match self.decay {
+            Decay::Exponential { weight } => {
+                let seen = self.ids.lookup(pk)
+                    .and_then(|id| self.weighted.get(&id))
+                    .map_or(0, |(_, seen)| *seen);
+                1.0 - (1.0 - weight).powi(seen as i32)
+            },
+            Decay::CappedMemory { verdicts } => self.remembered(pk) as f32 / verdicts.max(1) as f32,
+            Decay::SlidingWindow { .. } => {
+                let remembered = self.remembered(pk) as f32;
+                remembered / (remembered + 1.0)
+            }
+        };
+    }
+
+    fn scores(&self) -> HashMap<String, f32> {
+        let ids: Vec<&UserId> = match self.decay {
+            Decay::Exponential { .. } => self.weighted.keys().collect(),
+            _ => self.history.keys().collect()
+        };
+        return ids
+            .into_iter()
+            .map(|id| self.ids.pubkey(*id))
+            .filter_map(|pk| self.expected(&pk).map(|score| (pk, score)))
+            .collect();
+    }
+}
+
+/// Returns None for the upstream model, whose state is the Identity's own reputation map.
+/// The models of a simulation share its interner (see user_arena.rs).
+pub fn new_model(config: &ReputationModelConfig, default_reputation: f32, ids: &Interner) -> Option<Box<dyn ReputationModel>> {
+    return match config {
+        ReputationModelConfig::Average => None,
+        ReputationModelConfig::Beta { prior_strength, min_confidence } => Some(Box::new(
+            BetaModel::new(*prior_strength, *min_confidence, default_reputation, ids)
+        )),
+        ReputationModelConfig::Decayed { decay } => Some(Box::new(DecayedModel::new(decay.clone(), ids))),
+    };
+}
+
+// Like new_model, but the upstream model, whose state cannot be saved, is replaced by
+// its equivalent AverageModel
+pub fn new_saveable_model(config: &ReputationModelConfig, default_reputation: f32, ids: &Interner) -> Box<dyn ReputationModel> {
+    return new_model(config, default_reputation, ids).unwrap_or(Box::new(AverageModel::new(ids)));
+}
+
+pub fn new_role_reputations(config: &ReputationModelConfig, default_reputation: f32, ids: &Interner) -> RoleReputations {
+    return RoleReputations {
+        participant: new_saveable_model(config, default_reputation, ids),
+        witness: new_saveable_model(config, default_reputation, ids)
+    };
+}
+
+// The model a saved state was taken from, as it was then, keeping its users by the given interner
+pub fn restore_model(state: &ModelState, ids: &Interner) -> Box<dyn ReputationModel> {
+    return match state {
+        ModelState::Average { verdicts } => Box::new(AverageModel { verdicts: by_id(ids, verdicts), ids: ids.clone() }),
+        ModelState::Beta { prior_strength, min_confidence, default_reputation, evidence } => Box::new(BetaModel {
+            prior_strength: *prior_strength,
+            min_confidence: *min_confidence,
+            default_reputation: *default_reputation,
+            evidence: by_id(ids, evidence),
+            ids: ids.clone()
+        }),
+        ModelState::Decayed { decay, clock, weighted, history } => Box::new(DecayedModel {
+            decay: decay.clone(),
+            clock: *clock,
+            weighted: by_id(ids, weighted),
+            history: by_id(ids, history),
+            ids: ids.clone()
+        })
+    };
+}
+
+fn by_pubkey<V: Clone>(ids: &Interner, map: &HashMap<UserId, V>) -> HashMap<String, V> {
+    return map.iter().map(|(id, value)| (ids.pubkey(*id), value.clone())).collect();
+}
+
+fn by_id<V: Clone>(ids: &Interner, map: &HashMap<String, V>) -> HashMap<UserId, V> {
+    return map.iter().map(|(pk, value)| (ids.intern(pk), value.clone())).collect();
+}
+
+pub fn get_model_config(id_info: &IdInfo) -> ReputationModelConfig {
+    return match &id_info.state.reputation_model {
+        Some(model) => model.config(),
+        None        => ReputationModelConfig::Average
+    };
+}
+
+//--------------------------------------------------------------
+// The following dispatch to the user's model, or to the upstream
+// Identity if the user has none. They should be used instead of
+// the Identity's own reputation methods.
+//--------------------------------------------------------------
+
+pub fn update_reputation<C>(id: &mut Identity<C, IdInfo>, verdicts: Vec<(String, f32)>) {
+    match id.id_info.state.reputation_model.as_mut() {
+        Some(model) => model.update(&verdicts),
+        None        => id.update_reputation(verdicts)
+    }
+}
+
+// Updates the combined reputations with the verdicts of both roles, and the role
+// reputations, if the user keeps them, with the verdicts of each role
+pub fn update_reputations<C>(id: &mut Identity<C, IdInfo>, tn_verdicts: Vec<(String, f32)>, wn_verdicts: Vec<(String, f32)>) {
+    if let Some(roles) = id.id_info.state.role_reputations.as_mut() {
+        roles.participant.update(&tn_verdicts);
+        roles.witness.update(&wn_verdicts);
+    }
+    update_reputation(id, tn_verdicts);
+    update_reputation(id, wn_verdicts);
+}
+
+// Checks the user's reputation in the given role, or their combined reputation
+// if the user does not keep role reputations
+pub fn check_participant_as<C>(id: &Identity<C, IdInfo>, pk: &str, role: Role) -> bool {
+    return match &id.id_info.state.role_reputations {
+        Some(roles) => roles.get(role).check_participant(pk, id.user_reputation_threshold, id.user_default_reputation),
+        None        => check_participant(id, pk)
+    };
+}
+
+pub fn check_participant<C>(id: &Identity<C, IdInfo>, pk: &str) -> bool {
+    // users who base their checks on the global reputation fall back to
+    // their own model for users the global trust knows nothing about
+    if let Some(view) = id.id_info.state.global_trust.as_ref().filter(|view| view.use_for_checks) {
+        if let Some(reputation) = view.reputations.get(pk) {
+            return *reputation >= id.user_reputation_threshold;
+        }
+    }
+    match &id.id_info.state.reputation_model {
+        Some(model) => model.check_participant(pk, id.user_reputation_threshold, id.user_default_reputation),
+        None        => id.check_participant(&String::from(pk))
+    }
+}
+
+pub fn check_avg_participants<C>(id: &Identity<C, IdInfo>, pks: &Vec<String>) -> bool {
+    match &id.id_info.state.reputation_model {
+        Some(model) => {
+            if pks.len() == 0 {
+                return true;
+            }
+            let total: f32 = pks
+                .iter()
+                .map(|pk| model.expected(pk).unwrap_or(id.user_default_reputation))
+                .sum();
+            return total / pks.len() as f32 >= id.user_reputation_threshold;
+        },
+        None => id.check_avg_participants(pks)
+    }
+}
+
+pub fn reputation_scores<C>(id: &Identity<C, IdInfo>) -> HashMap<String, f32> {
+    match &id.id_info.state.reputation_model {
+        Some(model) => model.scores(),
+        None        => parse_reputation_scores(&id.get_reputation_scores_string())
+    }
+}
+
+/// In the format of the upstream get_reputation_scores_string
+pub fn reputation_scores_string<C>(id: &Identity<C, IdInfo>) -> String {
+    match &id.id_info.state.reputation_model {
+        Some(model) => {
+            let mut scores: Vec<(String, f32)> = model.scores().into_iter().collect();
+            scores.sort_by(|a, b| a.0.cmp(&b.0));
+            return scores
+                .iter()
+                .map(|(pk, score)| format!("{}: {}\n", pk, score))
+                .collect();
+        },
+        None => id.get_reputation_scores_string()
+    }
+}
+
+// The confidence the user has in each of the reputations they hold. The
+// upstream model has no notion of confidence, so nothing is returned for it.
+pub fn reputation_confidences<C>(id: &Identity<C, IdInfo>) -> HashMap<String, f32> {
+    match &id.id_info.state.reputation_model {
+        Some(model) => model.scores().keys().map(|pk| (pk.clone(), model.confidence(pk))).collect(),
+        None        => HashMap::new()
+    }
+}
+
+// The reputation maps of one role, in the format of reputation_maps.txt
+pub fn role_reputations_string(users: &Vec<UserIdentity>, role: Role) -> String {
+    let mut output: String = String::new();
+    for user in users.iter() {
+        let mut scores: Vec<(String, f32)> = user.id_info.state.role_reputations
+            .as_ref()
+            .map(|roles| roles.get(role).scores().into_iter().collect())
+            .unwrap_or(Vec::new());
+        scores.sort_by(|a, b| a.0.cmp(&b.0));
+        output.push_str(&format!("{}\n", user.id_info.org_cert.client_pubkey));
+        for (pk, score) in scores.iter() {
+            output.push_str(&format!("{}: {}\n", pk, score));
+        }
+        output.push_str("\n\n");
+    }
+    return output;
+}
+
+// Written next to the reputation maps, in the same format
+pub fn reputation_confidences_string(users: &Vec<UserIdentity>) -> String {
+    let mut output: String = String::new();
+    for user in users.iter() {
+        let mut confidences: Vec<(String, f32)> = reputation_confidences(user).into_iter().collect();
+        confidences.sort_by(|a, b| a.0.cmp(&b.0));
+        output.push_str(&format!("{}\n", user.id_info.org_cert.client_pubkey));
+        for (pk, confidence) in confidences.iter() {
+            output.push_str(&format!("{}: {}\n", pk, confidence));
+        }
+        output.push_str("\n\n");
+    }
+    return output;
+}
+
+// The average reputation each of the given users has with the other users. Written once
+// per run, it shows how quickly the others notice a change in a user's behaviour.
+pub fn user_reputations_string(run: usize, users: &Vec<UserIdentity>, pks: &Vec<String>) -> String {
+    let views: Vec<(String, HashMap<String, f32>)> = users
+        .iter()
+        .map(|u| (u.id_info.org_cert.client_pubkey.clone(), reputation_scores(u)))
+        .collect();
+
+    let mut output = format!("Run {}\n", run);
+    for pk in pks.iter() {
+        let others: Vec<f32> = views
+            .iter()
+            .filter(|(holder, _)| holder != pk)
+            .filter_map(|(_, scores)| scores.get(pk).cloned())
+            .collect();
+        if others.len() > 0 {
+            let avg = others.iter().sum::<f32>() / others.len() as f32;
+            output.push_str(&format!("{}: {}\n", pk, avg));
+        }
+    }
+    output.push_str("\n");
+    return output;
+}
+
+#[test]
+pub fn test_beta_model() {
+    let mut model = BetaModel::new(2.0, 0.5, 0.5, &Interner::new());
+    assert_eq!(model.expected("a"), None);
+    assert!(model.check_participant("a", 0.4, 0.5));
+
+    model.update(&vec![(String::from("a"), 1.0)]);
+    assert!((model.expected("a").unwrap() - 2.0 / 3.0).abs() < 0.0001);
+
+    // a single verdict is not enough evidence to be confident
+    assert!(!model.check_participant("a", 0.4, 0.5));
+
+    let verdicts: Vec<(String, f32)> = (0..20).map(|_| (String::from("a"), 1.0)).collect();
+    model.update(&verdicts);
+    assert!(model.confidence("a") > 0.5);
+    assert!(model.check_participant("a", 0.4, 0.5));
+}
+
+#[test]
+pub fn test_decayed_models() {
+    let honest: Vec<(String, f32)> = vec![(String::from("a"), 1.0)];
+    let dishonest: Vec<(String, f32)> = vec![(String::from("a"), 0.0)];
+
+    let ids = Interner::new();
+    let mut exponential = DecayedModel::new(Decay::Exponential { weight: 0.5 }, &ids);
+    let mut window = DecayedModel::new(Decay::SlidingWindow { updates: 4 }, &ids);
+    let mut capped = DecayedModel::new(Decay::CappedMemory { verdicts: 2 }, &ids);
+    for model in vec![&mut exponential, &mut window, &mut capped] {
+        for _ in 0..10 {
+            model.update(&honest);
+        }
+        assert_eq!(model.expected("a"), Some(1.0));
+        model.update(&dishonest);
+        model.update(&dishonest);
+    }
+    assert_eq!(exponential.expected("a"), Some(0.25));
+    assert_eq!(window.expected("a"), Some(0.5));
+    assert_eq!(capped.expected("a"), Some(0.0));
+
+    // once every verdict has slid out of the window, the user is unknown again
+    for _ in 0..4 {
+        window.update(&Vec::new());
+    }
+    assert_eq!(window.expected("a"), None);
+}
+
+#[test]
+pub fn test_role_reputations() {
+    let mut roles = new_role_reputations(&ReputationModelConfig::Average, 0.5, &Interner::new());
+
+    // an honest trading partner who lies as a witness
+    roles.participant.update(&vec![(String::from("a"), 1.0), (String::from("a"), 1.0)]);
+    roles.witness.update(&vec![(String::from("a"), 0.0)]);
+    assert_eq!(roles.get(Role::Participant).expected("a"), Some(1.0));
+    assert_eq!(roles.get(Role::Witness).expected("a"), Some(0.0));
+    assert!(roles.get(Role::Participant).check_participant("a", 0.5, 0.5));
+    assert!(!roles.get(Role::Witness).check_participant("a", 0.5, 0.5));
+}
+
+#[test]
+pub fn test_restore_model() {
+    let verdicts: Vec<(String, f32)> = vec![(String::from("a"), 1.0), (String::from("b"), 0.0)];
+    let configs = vec![
+        ReputationModelConfig::Average,
+        ReputationModelConfig::Beta { prior_strength: 2.0, min_confidence: 0.5 },
+        ReputationModelConfig::Decayed { decay: Decay::SlidingWindow { updates: 3 } }
+    ];
+    for config in configs.iter() {
+        let mut model = new_saveable_model(config, 0.5, &Interner::new());
+        model.update(&verdicts);
+        model.update(&vec![(String::from("a"), 0.5)]);
+
+        // the restored model carries on exactly as the saved one, decay clock included,
+        // even with its users interned by another interner
+        let mut restored = restore_model(&model.save(), &Interner::new());
+        assert_eq!(&restored.config(), config);
+        for _ in 0..3 {
+            model.update(&vec![(String::from("b"), 1.0)]);
+            restored.update(&vec![(String::from("b"), 1.0)]);
+            assert_eq!(restored.scores(), model.scores());
+            assert_eq!(restored.confidence("a"), model.confidence("a"));
+        }
+    }
+}
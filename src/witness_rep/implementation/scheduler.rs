@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+use rand::Rng;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// Simulated time, in ticks
+pub type SimTime = u64;
+
+/// The timing of the interactions of the event simulation, in ticks
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SchedulerConfig {
+    /// The average time between the starts of two interactions, which arrive at random
+    pub mean_interarrival: SimTime,
+    /// How long an interaction lasts, from its start until the compensation is sent
+    pub duration: SimTime,
+    /// The longest a witness statement takes to arrive after the interaction starts.
+    /// Statements arriving after the interaction has ended are left out of it.
+    pub max_statement_delay: SimTime,
+    /// The longest the messages of an ended interaction take to reach each observer
+    pub max_propagation_delay: SimTime,
+}
+
+/// What happens in the event simulation
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    /// The participants of the next interaction look for each other and for witnesses
+    Start,
+    /// The statement of the witness, by position, reaches the interaction
+    Statement { interaction: usize, witness: usize },
+    /// The interaction is over, and its participants and witnesses are free again
+    End { interaction: usize },
+    /// The messages of the ended interaction reach the user with the pubkey, who processes them
+    Process { interaction: usize, observer: String },
+}
+
+// An event waiting in the queue. Events are ordered by time, and events due at the same
+// time in the order they were scheduled.
+struct Scheduled<E> {
+    time: SimTime,
+    seq: u64,
+    event: E,
+}
+
+impl<E> PartialEq for Scheduled<E> {
+    fn eq(&self, other: &Self) -> bool {
+        return self.time == other.time && self.seq == other.seq;
+    }
+}
+
+impl<E> Eq for Scheduled<E> {}
+
+impl<E> PartialOrd for Scheduled<E> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        return Some(self.cmp(other));
+    }
+}
+
+impl<E> Ord for Scheduled<E> {
+    // reversed, so the max-heap pops the earliest event first
+    fn cmp(&self, other: &Self) -> Ordering {
+        return (other.time, other.seq).cmp(&(self.time, self.seq));
+    }
+}
+
+/// A queue of events in simulated time
+pub struct Scheduler<E> {
+    queue: BinaryHeap<Scheduled<E>>,
+    now: SimTime,
+    next_seq: u64,
+}
+
+impl<E> Scheduler<E> {
+    pub fn new() -> Scheduler<E> {
+        return Scheduler {
+            queue: BinaryHeap::new(),
+            now: 0,
+            next_seq: 0
+        };
+    }
+
+    // The time of the event last taken from the queue
+    pub fn now(&self) -> SimTime {
+        return self.now;
+    }
+
+    pub fn pending(&self) -> usize {
+        return self.queue.len();
+    }
+
+    // Schedules the event to happen the delay after the current time
+    pub fn schedule(&mut self, delay: SimTime, event: E) {
+        let scheduled = Scheduled {
+            time: self.now + delay,
+            seq: self.next_seq,
+            event: event
+        };
+        self.next_seq += 1;
+        self.queue.push(scheduled);
+    }
+
+    // Takes the earliest event from the queue, advancing the time to it
+    pub fn next(&mut self) -> Option<(SimTime, E)> {
+        let scheduled = self.queue.pop()?;
+        self.now = scheduled.time;
+        return Some((scheduled.time, scheduled.event));
+    }
+}
+
+// The time until the next interaction starts, drawn from an exponential distribution
+pub fn interarrival(config: &SchedulerConfig, rand_gen: &mut impl Rng) -> SimTime {
+    let uniform: f64 = rand_gen.gen();
+    return (-(1.0 - uniform).ln() * config.mean_interarrival as f64).round() as SimTime;
+}
+
+// A delay of at most max, drawn uniformly
+pub fn delay(max: SimTime, rand_gen: &mut impl Rng) -> SimTime {
+    return rand_gen.gen_range(0, max + 1);
+}
+
+#[test]
+pub fn test_scheduler() {
+    let mut scheduler: Scheduler<Event> = Scheduler::new();
+    scheduler.schedule(10, Event::End { interaction: 0 });
+    scheduler.schedule(5, Event::Statement { interaction: 0, witness: 1 });
+    scheduler.schedule(5, Event::Statement { interaction: 0, witness: 0 });
+    scheduler.schedule(0, Event::Start);
+
+    assert_eq!(scheduler.next(), Some((0, Event::Start)));
+
+    // events scheduled later happen relative to the new time
+    scheduler.schedule(1, Event::Start);
+    assert_eq!(scheduler.next(), Some((1, Event::Start)));
+    assert_eq!(scheduler.next(), Some((5, Event::Statement { interaction: 0, witness: 1 })));
+    assert_eq!(scheduler.next(), Some((5, Event::Statement { interaction: 0, witness: 0 })));
+    assert_eq!(scheduler.now(), 5);
+    assert_eq!(scheduler.next(), Some((10, Event::End { interaction: 0 })));
+    assert_eq!(scheduler.next(), None);
+    assert_eq!(scheduler.pending(), 0);
+}
@@ -1,5 +1,15 @@
 use crate::witness_rep::{
     iota_did::create_and_upload_did::Key,
+    implementation::{
+        organization_policy::{OrganizationPolicy, PolicyDecision},
+        reputation_model::{ReputationModel, RoleReputations},
+        global_trust::GlobalView,
+        recommendation::Recommender,
+        economy::Account,
+        witness_abuse::WitnessAbuse
+    },
+    trust_score_generators::user_tsg::TsgConfig,
+    utility::score_lines::get_line_info
 };
 
 use wb_reputation_system::{
@@ -13,14 +23,20 @@ use wb_reputation_system::{
 
 use iota_streams::{
     app::transport::tangle::client::Client,
-    app_channels::api::tangle::{Subscriber, Author}
+    app_channels::api::tangle::{Subscriber, Author, PublicKey}
 };
 
+use std::collections::HashMap;
+
 
 pub type UserIdentity = Identity<Subscriber<Client>, IdInfo>;
 pub struct OrganizationIdentity{
     pub identity:  Identity<Author<Client>, IdInfo>,
     pub ann_msg: Option<String>,
+    pub policy: Box<dyn OrganizationPolicy>,
+    // the subscribers which read the channel and so must always get the keyload
+    pub readers: Vec<PublicKey>,
+    pub decisions: Vec<PolicyDecision>,
 }
 
 // This is all of the external information about a participant, including their
@@ -29,7 +45,37 @@ pub struct IdInfo {
     pub seed: Option<String>,
     pub did_key: Key,
     pub reliability: Option<f32>,
-    pub org_cert: OrganizationCertificate
+    pub org_cert: OrganizationCertificate,
+    // what the simulation keeps track of for the user, besides their identity
+    pub state: UserState
+}
+
+// The state the simulation keeps for a user. Each part is optional or has a
+// default, so users can be created with only the parts a simulation uses.
+#[derive(Default)]
+pub struct UserState {
+    // which organization certified each user, as learned from processed interactions
+    pub org_memberships: HashMap<String, String>,
+    // users from organizations with a lower reputation than this are not accepted
+    pub organization_threshold: Option<f32>,
+    // how verdicts are aggregated into reputations. If None, the Identity's own
+    // reputation map is used (see reputation_model.rs)
+    pub reputation_model: Option<Box<dyn ReputationModel>>,
+    // the user's view of the global reputations, if they are computed (see global_trust.rs)
+    pub global_trust: Option<GlobalView>,
+    // how the user judges the interactions they process
+    pub tsg: TsgConfig,
+    // separate reputations for each role, if the simulation keeps them
+    pub role_reputations: Option<RoleReputations>,
+    // the user's part in the recommendation protocol, if it is simulated
+    pub recommender: Option<Recommender>,
+    // the user's balance, if the simulation keeps accounts
+    pub account: Option<Account>,
+    // how the user gets around the rules on statements when they witness, if they do
+    pub witness_abuse: Option<WitnessAbuse>,
+    // the announcement of the channel the user's client is attached to, if it is
+    // attached to one (see Ledger::attach_clients)
+    pub channel: Option<String>
 }
 
 
@@ -41,6 +87,14 @@ pub fn get_public_keys(participants: &Vec<OrganizationCertificate>) -> Vec<Strin
         .collect();
 }
 
+// Parses the output of get_reputation_scores_string back into a map
+pub fn parse_reputation_scores(scores: &str) -> HashMap<String, f32> {
+    return scores
+        .split('\n')
+        .filter_map(|line| get_line_info(line))
+        .collect();
+}
+
 /// BEWARE, this function assumes that an organization uses its own OrgCert
 pub fn get_index_org_with_pubkey(organizations: &Vec<OrganizationIdentity>, pk: &str) -> usize {
     let orgs: Vec<usize> = organizations
@@ -0,0 +1,173 @@
+use crate::witness_rep::implementation::reputation_model::Role;
+
+use rand::Rng;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// A user's public key, interned, so that reputations can be kept by a small copyable
+/// key instead of the multibase string. Ids are only meaningful to the interner which
+/// handed them out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct UserId(pub u32);
+
+/// Hands out the UserIds of a population, in the order the keys are first seen. Every
+/// reputation model of a simulation is given a clone of the same interner, which shares
+/// its ids, so the ids are dropped along with the population.
+#[derive(Clone, Default)]
+pub struct Interner {
+    ids: Arc<RwLock<InternedKeys>>
+}
+
+#[derive(Default)]
+struct InternedKeys {
+    ids: HashMap<String, UserId>,
+    pubkeys: Vec<String>
+}
+
+impl Interner {
+    pub fn new() -> Interner {
+        return Interner::default();
+    }
+
+    // The id of the public key, handing out a new one if the key has not been seen before
+    pub fn intern(&self, pk: &str) -> UserId {
+        if let Some(id) = self.lookup(pk) {
+            return id;
+        }
+        let mut keys = self.ids.write().unwrap();
+        if let Some(id) = keys.ids.get(pk) {
+            return *id;
+        }
+        let id = UserId(keys.pubkeys.len() as u32);
+        keys.ids.insert(String::from(pk), id);
+        keys.pubkeys.push(String::from(pk));
+        return id;
+    }
+
+    // The id of the public key, or None if it was never interned, in which case
+    // no model sharing the interner can hold a reputation for it
+    pub fn lookup(&self, pk: &str) -> Option<UserId> {
+        return self.ids.read().unwrap().ids.get(pk).cloned();
+    }
+
+    pub fn pubkey(&self, id: UserId) -> String {
+        return self.ids
+            .read()
+            .unwrap()
+            .pubkeys
+            .get(id.0 as usize)
+            .cloned()
+            .expect("The user id was not handed out by the interner");
+    }
+}
+
+/// The users taken out of the population for an interaction. The population is an arena:
+/// every user keeps their index in it for the whole simulation, except while they are
+/// taken out. A user is taken out in constant time by moving the last user into their
+/// place, and everyone is put back in the reverse order, which undoes the moves.
+pub struct Checkout {
+    /// The index each user was taken from, their role, and their place among the
+    /// participants or the witnesses, in the order they were taken
+    taken: Vec<(usize, Role, usize)>
+}
+
+impl Checkout {
+    pub fn new() -> Checkout {
+        return Checkout { taken: Vec::new() };
+    }
+
+    // Takes the user at the index out of the population, to be the participant or witness
+    // at the given place. The indices of the users after it are those of the shortened population.
+    pub fn take<T>(&mut self, users: &mut Vec<T>, index: usize, role: Role, place: usize) -> T {
+        self.taken.push((index, role, place));
+        return users.swap_remove(index);
+    }
+
+    // Puts the participants and witnesses back where they were taken from
+    pub fn put_back<T>(self, users: &mut Vec<T>, participants: Vec<T>, witnesses: Vec<T>) {
+        let mut participants: Vec<Option<T>> = participants.into_iter().map(Some).collect();
+        let mut witnesses: Vec<Option<T>> = witnesses.into_iter().map(Some).collect();
+        for (index, role, place) in self.taken.into_iter().rev() {
+            let user = match role {
+                Role::Participant => participants[place].take(),
+                Role::Witness     => witnesses[place].take()
+            };
+            users.push(user.expect("A user was put back twice"));
+            let last = users.len() - 1;
+            users.swap(index, last);
+        }
+    }
+}
+
+/// The indices of a population in a random order, drawn one at a time, so that looking
+/// for a few users among many does not cost a pass over all of them. Only the positions
+/// drawn from are remembered.
+pub struct RandomOrder {
+    len: usize,
+    drawn: usize,
+    swapped: HashMap<usize, usize>
+}
+
+impl RandomOrder {
+    pub fn new(len: usize) -> RandomOrder {
+        return RandomOrder { len, drawn: 0, swapped: HashMap::new() };
+    }
+
+    // The next index, or None once every index has been drawn
+    pub fn next(&mut self, rand_gen: &mut impl Rng) -> Option<usize> {
+        if self.drawn >= self.len {
+            return None;
+        }
+        let j = rand_gen.gen_range(self.drawn, self.len);
+        let at_j = self.swapped.get(&j).cloned().unwrap_or(j);
+        let at_drawn = self.swapped.get(&self.drawn).cloned().unwrap_or(self.drawn);
+        self.swapped.insert(j, at_drawn);
+        self.swapped.remove(&self.drawn);
+        self.drawn += 1;
+        return Some(at_j);
+    }
+}
+
+#[test]
+pub fn test_checkout() {
+    let mut users: Vec<usize> = (0..6).collect();
+    let mut checkout = Checkout::new();
+    let initiator = checkout.take(&mut users, 1, Role::Participant, 0);
+    let counterparty = checkout.take(&mut users, 4, Role::Participant, 1);
+    let second = checkout.take(&mut users, 2, Role::Witness, 1);
+    let first = checkout.take(&mut users, 0, Role::Witness, 0);
+    assert_eq!((initiator, counterparty, first, second), (1, 4, 0, 2));
+    assert_eq!(users.len(), 2);
+
+    checkout.put_back(&mut users, vec![initiator, counterparty], vec![first, second]);
+    assert_eq!(users, (0..6).collect::<Vec<usize>>());
+}
+
+#[test]
+pub fn test_random_order() {
+    use rand::{SeedableRng, rngs::StdRng};
+    let mut rand_gen = StdRng::seed_from_u64(7);
+    let mut order = RandomOrder::new(50);
+    let mut drawn: Vec<usize> = Vec::new();
+    while let Some(i) = order.next(&mut rand_gen) {
+        drawn.push(i);
+    }
+    drawn.sort();
+    assert_eq!(drawn, (0..50).collect::<Vec<usize>>());
+}
+
+#[test]
+pub fn test_intern() {
+    let ids = Interner::new();
+    let a = ids.intern("a");
+    let b = ids.intern("b");
+    assert_ne!(a, b);
+    assert_eq!(ids.intern("a"), a);
+    assert_eq!(ids.lookup("b"), Some(b));
+    assert_eq!(ids.lookup("c"), None);
+    assert_eq!(ids.pubkey(a), "a");
+
+    // a clone shares the ids, but another interner hands out its own
+    assert_eq!(ids.clone().lookup("a"), Some(a));
+    assert_eq!(Interner::new().lookup("a"), None);
+}
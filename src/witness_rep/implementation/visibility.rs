@@ -0,0 +1,189 @@
+use crate::witness_rep::implementation::{
+    user_and_organization::{UserIdentity, OrganizationIdentity},
+    organization_policy::KeyloadScope
+};
+
+use serde::{Deserialize, Serialize};
+use rand::Rng;
+use std::collections::{HashMap, HashSet, BTreeMap};
+
+/// Who, besides the participants and witnesses, learns about an interaction. Only
+/// users who got the keyload of the interaction can read it, whatever the model.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum VisibilityConfig {
+    /// Nobody else
+    InteractionOnly,
+    /// The members of the organization hosting the interaction
+    HostingOrganization,
+    /// Each other user with the given probability, in [0,1], of being in range
+    Proximity { proximity: f32 },
+    /// The users within the given number of hops of the participants and witnesses,
+    /// where users are linked once they have taken part in an interaction together
+    SocialDistance { hops: usize },
+}
+
+/// What the simulation remembers to decide who learns about each interaction
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Visibility {
+    /// The users subscribed to each organization's channel, by organization pubkey.
+    /// Users subscribe the first time they take part in one of its interactions.
+    pub subscribers: HashMap<String, HashSet<String>>,
+    /// Who has taken part in an interaction with whom
+    pub contacts: HashMap<String, HashSet<String>>,
+    /// The users and the organization who learned about the interaction of each run
+    pub observers: BTreeMap<usize, HashSet<String>>,
+}
+
+impl Visibility {
+    // Records the interaction of the run, taken part in by the involved users under the
+    // host organization, and decides who learns about it
+    pub fn record_interaction<'a>(
+        &mut self,
+        config: &VisibilityConfig,
+        run: usize,
+        involved: &Vec<String>,
+        host_org: &str,
+        keyload: &KeyloadScope,
+        users: impl Iterator<Item = &'a UserIdentity>,
+        rand_gen: &mut impl Rng
+    ) -> HashSet<String> {
+        let subscribers = self.subscribers.entry(String::from(host_org)).or_insert(HashSet::new());
+        subscribers.extend(involved.iter().cloned());
+
+        // only the users who got the keyload can decrypt the interaction
+        let recipients: HashSet<String> = match keyload {
+            KeyloadScope::Everyone        => subscribers.clone(),
+            KeyloadScope::InteractionOnly => involved.iter().cloned().collect()
+        };
+
+        let mut candidates: HashSet<String> = involved.iter().cloned().collect();
+        match config {
+            VisibilityConfig::InteractionOnly => {},
+            VisibilityConfig::HostingOrganization => {
+                candidates.extend(users
+                    .filter(|u| u.id_info.org_cert.org_pubkey == host_org)
+                    .map(|u| u.id_info.org_cert.client_pubkey.clone())
+                );
+            },
+            VisibilityConfig::Proximity { proximity } => {
+                for user in users {
+                    if *proximity > rand_gen.gen() {
+                        candidates.insert(user.id_info.org_cert.client_pubkey.clone());
+                    }
+                }
+            },
+            VisibilityConfig::SocialDistance { hops } => {
+                candidates = within_hops(&self.contacts, &candidates, *hops);
+            }
+        }
+
+        for user in involved.iter() {
+            let contacts = self.contacts.entry(user.clone()).or_insert(HashSet::new());
+            contacts.extend(involved.iter().filter(|other| *other != user).cloned());
+        }
+
+        let mut observers: HashSet<String> = candidates.intersection(&recipients).cloned().collect();
+        observers.insert(String::from(host_org));
+        self.observers.insert(run, observers.clone());
+        return observers;
+    }
+}
+
+// The users reachable from the start in at most the given number of hops
+pub fn within_hops(
+    contacts: &HashMap<String, HashSet<String>>,
+    start: &HashSet<String>,
+    hops: usize
+) -> HashSet<String> {
+    let mut reached: HashSet<String> = start.clone();
+    let mut frontier: HashSet<String> = start.clone();
+    for _ in 0..hops {
+        let next: HashSet<String> = frontier
+            .iter()
+            .filter_map(|user| contacts.get(user))
+            .flatten()
+            .filter(|user| !reached.contains(*user))
+            .cloned()
+            .collect();
+        reached.extend(next.iter().cloned());
+        frontier = next;
+    }
+    return reached;
+}
+
+// Whether the user or organization with the pubkey learned about the interaction.
+// Interactions with no recorded observers are seen by everyone.
+pub fn observes(observers: &Option<HashSet<String>>, pubkey: &str) -> bool {
+    return match observers {
+        None            => true,
+        Some(observers) => observers.contains(pubkey)
+    };
+}
+
+/// Where each participant and organization is in the simulation, by pubkey, so that an
+/// interaction only costs as much as the number of users who learned about it rather
+/// than the size of the population. The participants and organizations keep their
+/// places for the whole simulation, so the index is built once.
+pub struct ObserverIndex {
+    participants: HashMap<String, usize>,
+    organizations: HashMap<String, usize>,
+}
+
+impl ObserverIndex {
+    pub fn new(participants: &Vec<UserIdentity>, organizations: &Vec<OrganizationIdentity>) -> ObserverIndex {
+        return ObserverIndex {
+            participants: participants
+                .iter()
+                .enumerate()
+                .map(|(i, part)| (part.id_info.org_cert.client_pubkey.clone(), i))
+                .collect(),
+            organizations: organizations
+                .iter()
+                .enumerate()
+                .map(|(i, org)| (org.identity.id_info.org_cert.org_pubkey.clone(), i))
+                .collect()
+        };
+    }
+
+    // The places of the participants and of the organizations who learned about the
+    // interaction, in order. Interactions with no recorded observers are seen by everyone.
+    pub fn observers(&self, observers: &Option<HashSet<String>>) -> (Vec<usize>, Vec<usize>) {
+        return match observers {
+            None => (
+                (0..self.participants.len()).collect(),
+                (0..self.organizations.len()).collect()
+            ),
+            Some(observers) => {
+                let mut participants: Vec<usize> = observers.iter().filter_map(|pk| self.participants.get(pk)).cloned().collect();
+                let mut organizations: Vec<usize> = observers.iter().filter_map(|pk| self.organizations.get(pk)).cloned().collect();
+                participants.sort();
+                organizations.sort();
+                (participants, organizations)
+            }
+        };
+    }
+}
+
+// How many users learned about each interaction, on average
+pub fn visibility_summary_string(visibility: &Visibility) -> String {
+    let interactions = visibility.observers.len();
+    let observers: usize = visibility.observers.values().map(|o| o.len()).sum();
+    let average = if interactions > 0 { observers as f32 / interactions as f32 } else { 0.0 };
+    return format!("interactions: {}\nobservers: {}\n", interactions, average);
+}
+
+#[test]
+pub fn test_within_hops() {
+    let contacts: HashMap<String, HashSet<String>> = vec![
+        (String::from("a"), vec![String::from("b")].into_iter().collect()),
+        (String::from("b"), vec![String::from("a"), String::from("c")].into_iter().collect()),
+        (String::from("c"), vec![String::from("b"), String::from("d")].into_iter().collect()),
+    ].into_iter().collect();
+    let start: HashSet<String> = vec![String::from("a")].into_iter().collect();
+
+    assert_eq!(within_hops(&contacts, &start, 0), start);
+    assert_eq!(within_hops(&contacts, &start, 2).len(), 3);
+    assert!(within_hops(&contacts, &start, 3).contains("d"));
+    assert!(observes(&None, "a"));
+    assert!(!observes(&Some(start), "b"));
+}
@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+
+/// Ways a witness can try to get around the rules on witness statements
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum WitnessAbuse {
+    /// Never makes a statement, so that there is none to judge them by
+    Withhold,
+    /// Waits until the participants have compensated, and then states what
+    /// most of the other witnesses stated, so as to always agree with the vote
+    CopyLate,
+    /// States their outcome and then its opposite, hoping the one that suits
+    /// the judgement is counted
+    Equivocate,
+    /// Repeats their statement, hoping to be counted twice
+    Repeat,
+}
+
+/// A user who abuses the rules whenever they witness an interaction
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AbusiveWitness {
+    pub user: usize,
+    pub abuse: WitnessAbuse,
+}
+
+// The outcomes the witness states before the participants compensate, and those stated after,
+// given the outcome they would otherwise state and the outcomes the other witnesses state
+pub fn witness_statements(
+    abuse: &Option<WitnessAbuse>,
+    outcome: &Vec<bool>,
+    others: &Vec<Vec<bool>>
+) -> (Vec<Vec<bool>>, Vec<Vec<bool>>) {
+    return match abuse {
+        None                            => (vec![outcome.clone()], Vec::new()),
+        Some(WitnessAbuse::Withhold)    => (Vec::new(), Vec::new()),
+        Some(WitnessAbuse::CopyLate)    => (Vec::new(), vec![majority(outcome, others)]),
+        Some(WitnessAbuse::Equivocate)  => (vec![outcome.clone(), outcome.iter().map(|o| !o).collect()], Vec::new()),
+        Some(WitnessAbuse::Repeat)      => (vec![outcome.clone(), outcome.clone()], Vec::new()),
+    };
+}
+
+// What most of the others stated about each participant. Without any others to copy,
+// the witness falls back on their own outcome.
+pub fn majority(outcome: &Vec<bool>, others: &Vec<Vec<bool>>) -> Vec<bool> {
+    if others.len() == 0 {
+        return outcome.clone();
+    }
+    return (0..outcome.len())
+        .map(|j| {
+            let honest = others.iter().filter(|other| other.get(j) == Some(&true)).count();
+            honest * 2 >= others.len()
+        })
+        .collect();
+}
+
+#[test]
+pub fn test_witness_statements() {
+    let outcome = vec![true, false];
+    let others = vec![vec![false, false], vec![false, true], vec![false, true]];
+
+    assert_eq!(witness_statements(&None, &outcome, &others), (vec![outcome.clone()], Vec::new()));
+    assert_eq!(witness_statements(&Some(WitnessAbuse::Withhold), &outcome, &others), (Vec::new(), Vec::new()));
+    assert_eq!(
+        witness_statements(&Some(WitnessAbuse::CopyLate), &outcome, &others),
+        (Vec::new(), vec![vec![false, true]])
+    );
+    assert_eq!(
+        witness_statements(&Some(WitnessAbuse::Equivocate), &outcome, &others).0,
+        vec![vec![true, false], vec![false, true]]
+    );
+    assert_eq!(majority(&outcome, &Vec::new()), outcome);
+}
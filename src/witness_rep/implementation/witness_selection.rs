@@ -0,0 +1,249 @@
+use crate::witness_rep::implementation::{
+    user_and_organization::UserIdentity,
+    reputation_model::reputation_scores
+};
+
+use serde::{Deserialize, Serialize};
+use rand::{RngCore, seq::SliceRandom};
+use std::cmp::Ordering;
+
+/// How the participants choose their witnesses among the users all of them accept
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum WitnessSelectionConfig {
+    /// Every eligible user, as many as the witness ceiling allows
+    AllEligible,
+    /// The eligible users the participants hold in the highest regard
+    TopReputation,
+    /// A random choice of the eligible users
+    Random,
+    /// At least one witness from each participant's organization, the rest by reputation
+    OrganizationDiverse,
+    /// Witnesses charge base_fee plus reputation_premium times their reputation, and the
+    /// participants choose the best reputation for the price without exceeding the budget
+    CostAware { base_fee: f32, reputation_premium: f32, budget: f32 },
+}
+
+/// An eligible witness, as seen by the participants
+#[derive(Clone, Debug)]
+pub struct Candidate {
+    /// The index of the user among the users who are not participants
+    pub index: usize,
+    pub org_pubkey: String,
+    /// The average reputation the participants hold for the user
+    pub reputation: f32,
+}
+
+pub struct SelectionRequest {
+    pub participant_orgs: Vec<String>,
+    pub witness_floor: usize,
+    pub witness_ceiling: Option<usize>,
+}
+
+impl SelectionRequest {
+    // How many witnesses a strategy aims for
+    pub fn target(&self) -> usize {
+        return self.witness_ceiling.unwrap_or(self.witness_floor);
+    }
+}
+
+pub trait WitnessSelection {
+    fn name(&self) -> String;
+
+    /// Returns the indices of the chosen witnesses, or None if the candidates do not
+    /// allow an acceptable choice, in which case the participants look again
+    fn select(
+        &self,
+        candidates: &Vec<Candidate>,
+        request: &SelectionRequest,
+        rand_gen: &mut dyn RngCore
+    ) -> Option<Vec<usize>>;
+}
+
+pub struct AllEligibleSelection;
+pub struct TopReputationSelection;
+pub struct RandomSelection;
+pub struct OrganizationDiverseSelection;
+pub struct CostAwareSelection {
+    pub base_fee: f32,
+    pub reputation_premium: f32,
+    pub budget: f32
+}
+
+impl WitnessSelection for AllEligibleSelection {
+    fn name(&self) -> String { String::from("AllEligible") }
+
+    fn select(&self, candidates: &Vec<Candidate>, request: &SelectionRequest, _rand_gen: &mut dyn RngCore) -> Option<Vec<usize>> {
+        let ceiling = request.witness_ceiling.unwrap_or(candidates.len());
+        let chosen: Vec<usize> = candidates.iter().take(ceiling).map(|c| c.index).collect();
+        return enough(chosen, request);
+    }
+}
+
+impl WitnessSelection for TopReputationSelection {
+    fn name(&self) -> String { String::from("TopReputation") }
+
+    fn select(&self, candidates: &Vec<Candidate>, request: &SelectionRequest, _rand_gen: &mut dyn RngCore) -> Option<Vec<usize>> {
+        let chosen: Vec<usize> = by_reputation(candidates)
+            .iter()
+            .take(request.target())
+            .map(|c| c.index)
+            .collect();
+        return enough(chosen, request);
+    }
+}
+
+impl WitnessSelection for RandomSelection {
+    fn name(&self) -> String { String::from("Random") }
+
+    fn select(&self, candidates: &Vec<Candidate>, request: &SelectionRequest, rand_gen: &mut dyn RngCore) -> Option<Vec<usize>> {
+        let chosen: Vec<usize> = candidates
+            .choose_multiple(rand_gen, request.target())
+            .map(|c| c.index)
+            .collect();
+        return enough(chosen, request);
+    }
+}
+
+impl WitnessSelection for OrganizationDiverseSelection {
+    fn name(&self) -> String { String::from("OrganizationDiverse") }
+
+    fn select(&self, candidates: &Vec<Candidate>, request: &SelectionRequest, _rand_gen: &mut dyn RngCore) -> Option<Vec<usize>> {
+        let ranked = by_reputation(candidates);
+
+        // the best regarded witness of each participant's organization
+        let mut chosen: Vec<usize> = Vec::new();
+        for org in request.participant_orgs.iter() {
+            let best = ranked.iter().find(|c| &c.org_pubkey == org)?;
+            if !chosen.contains(&best.index) {
+                chosen.push(best.index);
+            }
+        }
+        if chosen.len() > request.witness_ceiling.unwrap_or(chosen.len()) {
+            return None;
+        }
+
+        for candidate in ranked.iter() {
+            if chosen.len() >= request.target() {
+                break;
+            }
+            if !chosen.contains(&candidate.index) {
+                chosen.push(candidate.index);
+            }
+        }
+        return enough(chosen, request);
+    }
+}
+
+impl CostAwareSelection {
+    pub fn fee(&self, candidate: &Candidate) -> f32 {
+        return self.base_fee + self.reputation_premium * candidate.reputation;
+    }
+}
+
+impl WitnessSelection for CostAwareSelection {
+    fn name(&self) -> String { String::from("CostAware") }
+
+    fn select(&self, candidates: &Vec<Candidate>, request: &SelectionRequest, _rand_gen: &mut dyn RngCore) -> Option<Vec<usize>> {
+        // the most reputation for the money first
+        let mut ranked: Vec<&Candidate> = candidates.iter().collect();
+        ranked.sort_by(|a, b| {
+            let value_a = a.reputation / self.fee(a).max(f32::EPSILON);
+            let value_b = b.reputation / self.fee(b).max(f32::EPSILON);
+            value_b.partial_cmp(&value_a).unwrap_or(Ordering::Equal)
+        });
+
+        let ceiling = request.witness_ceiling.unwrap_or(candidates.len());
+        let mut spent: f32 = 0.0;
+        let mut chosen: Vec<usize> = Vec::new();
+        for candidate in ranked {
+            if chosen.len() >= ceiling {
+                break;
+            }
+            let fee = self.fee(candidate);
+            if spent + fee <= self.budget {
+                spent += fee;
+                chosen.push(candidate.index);
+            }
+        }
+        return enough(chosen, request);
+    }
+}
+
+pub fn new_witness_selection(config: &Option<WitnessSelectionConfig>) -> Box<dyn WitnessSelection> {
+    return match config {
+        None | Some(WitnessSelectionConfig::AllEligible) => Box::new(AllEligibleSelection),
+        Some(WitnessSelectionConfig::TopReputation)       => Box::new(TopReputationSelection),
+        Some(WitnessSelectionConfig::Random)              => Box::new(RandomSelection),
+        Some(WitnessSelectionConfig::OrganizationDiverse) => Box::new(OrganizationDiverseSelection),
+        Some(WitnessSelectionConfig::CostAware { base_fee, reputation_premium, budget }) => Box::new(
+            CostAwareSelection {
+                base_fee: *base_fee,
+                reputation_premium: *reputation_premium,
+                budget: *budget
+            }
+        ),
+    };
+}
+
+// The eligible users, at the given indices of users, with the average reputation the
+// participants hold for each of them
+pub fn witness_candidates(
+    participants: &Vec<UserIdentity>,
+    users: &Vec<UserIdentity>,
+    eligible: &Vec<usize>
+) -> Vec<Candidate> {
+    let scores: Vec<_> = participants.iter().map(|p| (reputation_scores(p), p.user_default_reputation)).collect();
+    return eligible
+        .iter()
+        .map(|i| {
+            let pk = &users[*i].id_info.org_cert.client_pubkey;
+            let total: f32 = scores
+                .iter()
+                .map(|(s, default)| s.get(pk).cloned().unwrap_or(*default))
+                .sum();
+            Candidate {
+                index: *i,
+                org_pubkey: users[*i].id_info.org_cert.org_pubkey.clone(),
+                reputation: total / scores.len().max(1) as f32
+            }
+        })
+        .collect();
+}
+
+fn by_reputation(candidates: &Vec<Candidate>) -> Vec<&Candidate> {
+    let mut ranked: Vec<&Candidate> = candidates.iter().collect();
+    ranked.sort_by(|a, b| b.reputation.partial_cmp(&a.reputation).unwrap_or(Ordering::Equal));
+    return ranked;
+}
+
+fn enough(chosen: Vec<usize>, request: &SelectionRequest) -> Option<Vec<usize>> {
+    if chosen.len() < request.witness_floor {
+        return None;
+    }
+    return Some(chosen);
+}
+
+#[test]
+pub fn test_witness_selection() {
+    let candidates: Vec<Candidate> = vec![(0, "org0", 0.9), (1, "org0", 0.8), (2, "org1", 0.3), (3, "org1", 0.6)]
+        .into_iter()
+        .map(|(index, org, reputation)| Candidate { index, org_pubkey: String::from(org), reputation })
+        .collect();
+    let request = SelectionRequest {
+        participant_orgs: vec![String::from("org0"), String::from("org1")],
+        witness_floor: 2,
+        witness_ceiling: Some(2)
+    };
+    let mut rand_gen = rand::thread_rng();
+
+    assert_eq!(AllEligibleSelection.select(&candidates, &request, &mut rand_gen), Some(vec![0, 1]));
+    assert_eq!(TopReputationSelection.select(&candidates, &request, &mut rand_gen), Some(vec![0, 1]));
+    assert_eq!(OrganizationDiverseSelection.select(&candidates, &request, &mut rand_gen), Some(vec![0, 3]));
+    assert_eq!(RandomSelection.select(&candidates, &request, &mut rand_gen).unwrap().len(), 2);
+
+    // the second best value witness does not fit the budget after the best
+    let cost_aware = CostAwareSelection { base_fee: 1.0, reputation_premium: 1.0, budget: 3.6 };
+    assert_eq!(cost_aware.select(&candidates, &request, &mut rand_gen), Some(vec![0, 3]));
+    let too_poor = CostAwareSelection { base_fee: 1.0, reputation_premium: 1.0, budget: 1.5 };
+    assert_eq!(too_poor.select(&candidates, &request, &mut rand_gen), None);
+}
@@ -12,14 +12,41 @@ use crypto::signatures::ed25519;
 
 
 pub type Key = [u8; 32];
-pub enum RunMode {Testing, Deploying}
+
+/// How DIDs are created:
+///  - Testing: only the keypair is generated, there is no DID document
+///  - Deploying: the DID document is published to the Tangle, through the private node
+///  - Local: the DID document is created and signed, but publishing it is left to a
+///    local registry (see did_registry.rs), so no node is needed
+#[derive(Clone, Debug)]
+pub enum RunMode {Testing, Deploying, Local}
+
+// the private tangle the documents are published to when deploying
+const PRIVATE_NODE_URL: &str = "http://127.0.0.1:14265";
+const NETWORK_NAME: &str = "dev";
 
 // returns a tuple of the Account and the Stronghold file name
 // in a practical setting, we would return the url and need to fetch it from the tangle
 pub async fn create_n_dids(n: usize, run_mode: RunMode) -> Result<Vec<(Option<IotaDocument>,(KeyPair,(Key,Key)),Option<Receipt>)>> {
     let mut did_array = Vec::new();
     for _ in 0..n {
-        let did_info = create_and_upload_did(&run_mode).await?;
+        // Generate a new Ed25519 public/private key pair.
+        let (keypair, pub_and_sec) = gen_iota_keypair();
+        let did_info = create_and_upload_did(&run_mode, keypair, pub_and_sec).await?;
+        did_array.push(did_info);
+    }
+    return Ok(did_array);
+}
+
+// the same as create_n_dids, but for keys which were generated previously (e.g. loaded
+// from a keystore), so that a population of users can be reused across simulations
+pub async fn recreate_dids(secrets: &Vec<Key>, run_mode: RunMode) -> Result<Vec<(Option<IotaDocument>,(KeyPair,(Key,Key)),Option<Receipt>)>> {
+    let mut did_array = Vec::new();
+    for sec in secrets.iter() {
+        let keypair = KeyPair::try_from_ed25519_bytes(sec)?;
+        let mut pubk: Key = [0; 32];
+        pubk.copy_from_slice(keypair.public().as_ref());
+        let did_info = create_and_upload_did(&run_mode, keypair, (pubk, *sec)).await?;
         did_array.push(did_info);
     }
     return Ok(did_array);
@@ -27,35 +54,35 @@ pub async fn create_n_dids(n: usize, run_mode: RunMode) -> Result<Vec<(Option<Io
 
 // uploads the did for this user and returns the Account object
 async fn create_and_upload_did(
-    run_mode: &RunMode
+    run_mode: &RunMode,
+    keypair: KeyPair,
+    pub_and_sec: (Key,Key)
 ) -> Result<(Option<IotaDocument>,(KeyPair,(Key,Key)),Option<Receipt>)> {
-    let network_name = "dev";
-    let network = Network::try_from_name(network_name)?;
-
-    // hardcoded as this fn will only ever be used on the private tangle
-    //let explorer = ExplorerUrl::parse("http://127.0.0.1:8082")?;
-    let private_node_url = "http://127.0.0.1:14265";
-    let encoding = DIDMessageEncoding::JsonBrotli;
-    let client_builder = ClientBuilder::new()
-        .network(network.clone())
-        .encoding(encoding)
-        .primary_node(private_node_url, None, None)?;
-    
-    let client = Client::from_builder(client_builder).await?;
-    let client_map = ClientMap::from_client(client);
-
-    // Generate a new Ed25519 public/private key pair.
-    let (keypair, pub_and_sec) = gen_iota_keypair();
+    if let RunMode::Testing = run_mode {
+        return Ok((None, (keypair, pub_and_sec), None));
+    }
 
-    if let RunMode::Deploying = run_mode {
-        // Create a DID Document (an identity) from the generated key pair.
-        let mut document: IotaDocument = IotaDocument::new(&keypair)?;
+    // Create a DID Document (an identity) from the generated key pair.
+    let mut document: IotaDocument = IotaDocument::new(&keypair)?;
 
-        // Sign the DID Document with the default signing method.
-        document.sign_self(keypair.private(), &document.default_signing_method()?.id())?;
+    // Sign the DID Document with the default signing method.
+    document.sign_self(keypair.private(), &document.default_signing_method()?.id())?;
 
+    if let RunMode::Deploying = run_mode {
         println!("DID Document JSON > {:#}", document);
 
+        // hardcoded as this fn will only ever be used on the private tangle
+        //let explorer = ExplorerUrl::parse("http://127.0.0.1:8082")?;
+        let network = Network::try_from_name(NETWORK_NAME)?;
+        let encoding = DIDMessageEncoding::JsonBrotli;
+        let client_builder = ClientBuilder::new()
+            .network(network.clone())
+            .encoding(encoding)
+            .primary_node(PRIVATE_NODE_URL, None, None)?;
+        
+        let client = Client::from_builder(client_builder).await?;
+        let client_map = ClientMap::from_client(client);
+
         // Publish the DID Document to the Tangle.
         let receipt: Receipt = client_map.publish_document(&document).await?;
 
@@ -72,7 +99,7 @@ async fn create_and_upload_did(
         return Ok((Some(document), (keypair, pub_and_sec), Some(receipt)));
     }
 
-    return Ok((None, (keypair, pub_and_sec), None));
+    return Ok((Some(document), (keypair, pub_and_sec), None));
 }
 
 // returns a keypair and the associated private key
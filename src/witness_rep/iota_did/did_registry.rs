@@ -0,0 +1,179 @@
+use crate::witness_rep::iota_did::create_and_upload_did::Key;
+
+use wb_reputation_system::data_types::event_protocol_messages::signatures::organization_cert::OrganizationCertificate;
+
+use identity::{
+    iota::{IotaDocument, IotaDID, Receipt},
+    crypto::KeyPair,
+    did::MethodData
+};
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf
+};
+
+/// Where the DID documents are published to when running offline
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum DidRegistryConfig {
+    /// Documents are kept for the duration of the simulation
+    Memory,
+    /// Documents are written, one per file, to the given directory
+    File(String)
+}
+
+/// Publishes DID documents and resolves them by DID. Stands in for the Tangle,
+/// so documents can be published and resolved without a node.
+pub trait DidResolver {
+    fn publish(&mut self, document: &IotaDocument) -> Result<()>;
+
+    fn resolve(&self, did: &str) -> Result<Option<IotaDocument>>;
+}
+
+pub struct MemoryDidRegistry {
+    documents: HashMap<String, IotaDocument>
+}
+
+impl MemoryDidRegistry {
+    pub fn new() -> MemoryDidRegistry {
+        return MemoryDidRegistry {
+            documents: HashMap::new()
+        };
+    }
+}
+
+impl DidResolver for MemoryDidRegistry {
+    fn publish(&mut self, document: &IotaDocument) -> Result<()> {
+        self.documents.insert(document.id().to_string(), document.clone());
+        return Ok(());
+    }
+
+    fn resolve(&self, did: &str) -> Result<Option<IotaDocument>> {
+        return Ok(self.documents.get(did).cloned());
+    }
+}
+
+// The documents are read from the directory once, when the registry is created, and
+// from then on resolved from memory. Published documents are written to the directory
+// as well, so a later registry on the same directory finds them.
+pub struct FileDidRegistry {
+    dir: PathBuf,
+    index: MemoryDidRegistry
+}
+
+impl FileDidRegistry {
+    pub fn new(dir: &str) -> Result<FileDidRegistry> {
+        fs::create_dir_all(dir)?;
+        let mut index = MemoryDidRegistry::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().map_or(false, |ext| ext == "json") {
+                let document: IotaDocument = serde_json::from_str(&fs::read_to_string(path)?)?;
+                index.publish(&document)?;
+            }
+        }
+        return Ok(FileDidRegistry { dir: PathBuf::from(dir), index: index });
+    }
+
+    // DIDs contain colons, which are not allowed in file names on every platform
+    fn document_path(&self, did: &str) -> PathBuf {
+        return self.dir.join(format!("{}.json", did.replace(':', "_")));
+    }
+}
+
+impl DidResolver for FileDidRegistry {
+    fn publish(&mut self, document: &IotaDocument) -> Result<()> {
+        let path = self.document_path(&document.id().to_string());
+        fs::write(path, serde_json::to_string(document)?)?;
+        return self.index.publish(document);
+    }
+
+    fn resolve(&self, did: &str) -> Result<Option<IotaDocument>> {
+        return self.index.resolve(did);
+    }
+}
+
+pub fn new_registry(config: &DidRegistryConfig) -> Result<Box<dyn DidResolver>> {
+    return match config {
+        DidRegistryConfig::Memory       => Ok(Box::new(MemoryDidRegistry::new())),
+        DidRegistryConfig::File(dir)    => Ok(Box::new(FileDidRegistry::new(dir)?)),
+    };
+}
+
+// Publishes the documents created by create_n_dids in RunMode::Local
+pub fn register_documents(
+    registry: &mut dyn DidResolver,
+    did_details: &Vec<(Option<IotaDocument>, (KeyPair, (Key, Key)), Option<Receipt>)>
+) -> Result<()> {
+    for (document, _, _) in did_details.iter() {
+        match document {
+            Some(doc)   => registry.publish(doc)?,
+            None        => return Err(anyhow!("DIDs must be created in RunMode::Local to be registered"))
+        }
+    }
+    return Ok(());
+}
+
+// Returns the key bytes of the signing method of the DID document the signer's organization
+// certificate is bound to, or None if no such document is registered. The certificate holds
+// the DID key it was issued for, from which the DID is derived, so signatures are verified
+// against the certified signer's document rather than whatever key the signer presented.
+pub fn resolve_signer_key(resolver: &dyn DidResolver, org_cert: &OrganizationCertificate) -> Result<Option<Vec<u8>>> {
+    let certified_key = MethodData::PublicKeyMultibase(org_cert.client_pubkey.clone()).try_decode()?;
+    let did = IotaDID::new(&certified_key)?;
+    let document = match resolver.resolve(&did.to_string())? {
+        Some(doc)   => doc,
+        None        => return Ok(None)
+    };
+    return Ok(Some(document.default_signing_method()?.key_data().try_decode()?));
+}
+
+#[cfg(test)]
+fn test_document_and_cert() -> (IotaDocument, KeyPair, OrganizationCertificate) {
+    use crate::witness_rep::{
+        iota_did::create_and_upload_did::gen_iota_keypair,
+        implementation::generate_sigs::get_multibase
+    };
+
+    let (keypair, _) = gen_iota_keypair();
+    let mut document = IotaDocument::new(&keypair).unwrap();
+    document.sign_self(keypair.private(), &document.default_signing_method().unwrap().id()).unwrap();
+    let org_cert = OrganizationCertificate {
+        client_pubkey: get_multibase(&keypair),
+        timeout: 0,
+        org_pubkey: String::new(),
+        signature: Vec::new()
+    };
+    return (document, keypair, org_cert);
+}
+
+#[test]
+pub fn test_memory_registry() {
+    let mut registry = MemoryDidRegistry::new();
+    let (document, keypair, org_cert) = test_document_and_cert();
+    registry.publish(&document).unwrap();
+
+    assert!(registry.resolve(&document.id().to_string()).unwrap().is_some());
+    assert_eq!(resolve_signer_key(&registry, &org_cert).unwrap(), Some(keypair.public().as_ref().to_vec()));
+
+    // certificates for DIDs which were never registered do not resolve
+    let (_, _, unregistered) = test_document_and_cert();
+    assert_eq!(resolve_signer_key(&registry, &unregistered).unwrap(), None);
+}
+
+#[test]
+pub fn test_file_registry() {
+    let dir = std::env::temp_dir().join("test_file_did_registry");
+    let _ = fs::remove_dir_all(&dir);
+    let dir = dir.to_str().unwrap();
+
+    let (document, keypair, org_cert) = test_document_and_cert();
+    FileDidRegistry::new(dir).unwrap().publish(&document).unwrap();
+
+    // a registry created later on the same directory indexes the published documents
+    let registry = FileDidRegistry::new(dir).unwrap();
+    assert_eq!(resolve_signer_key(&registry, &org_cert).unwrap(), Some(keypair.public().as_ref().to_vec()));
+}
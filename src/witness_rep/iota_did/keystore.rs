@@ -0,0 +1,174 @@
+use crate::witness_rep::{
+    iota_did::create_and_upload_did::Key,
+    implementation::user_and_organization::{UserIdentity, OrganizationIdentity}
+};
+
+use wb_reputation_system::data_types::event_protocol_messages::signatures::organization_cert::OrganizationCertificate;
+
+use crypto::{
+    ciphers::{chacha::XChaCha20Poly1305, traits::Aead},
+    keys::pbkdf::PBKDF2_HMAC_SHA256,
+    utils::rand
+};
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+const SALT_LENGTH: usize = 16;
+const PBKDF2_ITERATIONS: usize = 100_000;
+
+/// Where the population is stored. The passphrase is never written out with the
+/// rest of the simulation parameters.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct KeystoreConfig {
+    pub path: String,
+    #[serde(skip_serializing, default)]
+    pub passphrase: String,
+}
+
+/// A user's identity, without any simulation-specific state (reliability,
+/// thresholds, reputation map), so it can be reused across simulations
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StoredUser {
+    pub seed: String,
+    pub did_key: Key,
+    pub organization: usize,
+    pub org_cert: OrganizationCertificate,
+}
+
+/// A channel opened by an organization, which can be reopened from its seed
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StoredChannel {
+    pub author_seed: String,
+    pub ann_msg: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StoredOrganization {
+    pub organization: usize,
+    pub did_key: Key,
+    pub org_cert: OrganizationCertificate,
+    /// Every channel the organization has opened, oldest first
+    pub channels: Vec<StoredChannel>,
+}
+
+/// The identities of a population of users and their organizations
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Population {
+    pub users: Vec<StoredUser>,
+    pub organizations: Vec<StoredOrganization>,
+}
+
+/// What is written to disk: the population (or a checkpoint, see checkpoint.rs), encrypted
+/// with a key derived from a passphrase
+#[derive(Serialize, Deserialize)]
+pub struct EncryptedKeystore {
+    salt: Vec<u8>,
+    nonce: Vec<u8>,
+    tag: Vec<u8>,
+    ciphertext: Vec<u8>,
+}
+
+impl Population {
+    pub fn new() -> Population {
+        return Population {
+            users: Vec::new(),
+            organizations: Vec::new()
+        };
+    }
+
+    pub fn organization(&self, organization: usize) -> Option<&StoredOrganization> {
+        return self.organizations.iter().find(|o| o.organization == organization);
+    }
+
+    pub fn organization_mut(&mut self, organization: usize) -> Option<&mut StoredOrganization> {
+        return self.organizations.iter_mut().find(|o| o.organization == organization);
+    }
+}
+
+pub fn store_user(user: &UserIdentity, organization: usize) -> StoredUser {
+    return StoredUser {
+        seed: user.id_info.seed.clone().expect("Users must have a seed to be stored"),
+        did_key: user.id_info.did_key,
+        organization: organization,
+        org_cert: user.id_info.org_cert.clone()
+    };
+}
+
+pub fn store_organization(org: &OrganizationIdentity, organization: usize) -> StoredOrganization {
+    let mut stored = StoredOrganization {
+        organization: organization,
+        did_key: org.identity.id_info.did_key,
+        org_cert: org.identity.id_info.org_cert.clone(),
+        channels: Vec::new()
+    };
+    add_channel(&mut stored, org);
+    return stored;
+}
+
+// Records the organization's current channel, if it has opened one
+pub fn add_channel(stored: &mut StoredOrganization, org: &OrganizationIdentity) {
+    if let (Some(seed), Some(ann_msg)) = (&org.identity.id_info.seed, &org.ann_msg) {
+        stored.channels.push(StoredChannel {
+            author_seed: seed.clone(),
+            ann_msg: ann_msg.clone()
+        });
+    }
+}
+
+pub fn save_population(path: &str, passphrase: &str, population: &Population) -> Result<()> {
+    let plaintext = serde_json::to_vec(population)?;
+    let keystore = encrypt(passphrase, &plaintext)?;
+    fs::write(path, serde_json::to_string(&keystore)?)?;
+    return Ok(());
+}
+
+pub fn load_population(path: &str, passphrase: &str) -> Result<Option<Population>> {
+    if !Path::new(path).exists() {
+        return Ok(None);
+    }
+    let keystore: EncryptedKeystore = serde_json::from_str(&fs::read_to_string(path)?)?;
+    let plaintext = decrypt(passphrase, &keystore)?;
+    return Ok(Some(serde_json::from_slice(&plaintext)?));
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Vec<u8>> {
+    let mut key = vec![0; XChaCha20Poly1305::KEY_LENGTH];
+    PBKDF2_HMAC_SHA256(passphrase.as_bytes(), salt, PBKDF2_ITERATIONS, &mut key)
+        .map_err(|e| anyhow!("Could not derive the keystore key: {:?}", e))?;
+    return Ok(key);
+}
+
+pub fn encrypt(passphrase: &str, plaintext: &[u8]) -> Result<EncryptedKeystore> {
+    let mut salt = vec![0; SALT_LENGTH];
+    let mut nonce = vec![0; XChaCha20Poly1305::NONCE_LENGTH];
+    rand::fill(&mut salt).map_err(|e| anyhow!("{:?}", e))?;
+    rand::fill(&mut nonce).map_err(|e| anyhow!("{:?}", e))?;
+    let key = derive_key(passphrase, &salt)?;
+
+    let mut ciphertext = vec![0; plaintext.len()];
+    let mut tag = vec![0; XChaCha20Poly1305::TAG_LENGTH];
+    XChaCha20Poly1305::try_encrypt(&key, &nonce, &salt, plaintext, &mut ciphertext, &mut tag)
+        .map_err(|e| anyhow!("Could not encrypt the keystore: {:?}", e))?;
+
+    return Ok(EncryptedKeystore { salt, nonce, tag, ciphertext });
+}
+
+// Fails if the passphrase is wrong or the keystore has been tampered with
+pub fn decrypt(passphrase: &str, keystore: &EncryptedKeystore) -> Result<Vec<u8>> {
+    let key = derive_key(passphrase, &keystore.salt)?;
+    let mut plaintext = vec![0; keystore.ciphertext.len()];
+    XChaCha20Poly1305::try_decrypt(
+        &key, &keystore.nonce, &keystore.salt, &mut plaintext, &keystore.ciphertext, &keystore.tag
+    ).map_err(|_| anyhow!("Could not decrypt the keystore, the passphrase may be wrong"))?;
+    return Ok(plaintext);
+}
+
+#[test]
+pub fn test_keystore_round_trip() {
+    let plaintext = b"did keys and seeds";
+    let keystore = encrypt("correct horse", plaintext).unwrap();
+    assert_eq!(decrypt("correct horse", &keystore).unwrap(), plaintext.to_vec());
+    assert!(decrypt("wrong horse", &keystore).is_err());
+}
@@ -1,3 +1,5 @@
 // Decentralised IDentity (DID)
 
-pub mod create_and_upload_did;
\ No newline at end of file
+pub mod create_and_upload_did;
+pub mod did_registry;
+pub mod keystore;
\ No newline at end of file
@@ -2,4 +2,8 @@ pub mod iota_did;
 pub mod utility;
 pub mod implementation;
 pub mod simulation;
-pub mod quick_simulation;
\ No newline at end of file
+pub mod quick_simulation;
+pub mod trust_score_generators;
+pub mod checkpoint;
+pub mod event_simulation;
+pub mod engine;
\ No newline at end of file
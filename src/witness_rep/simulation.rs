@@ -1,51 +1,59 @@
 use crate::witness_rep::{
-    iota_did::create_and_upload_did::{create_n_dids, Key, RunMode},
-    implementation::{generate_contract, generate_sigs},
-    implementation::{
-        interaction::{interaction, LazyMethod},
-        user_and_organization::{
-            UserIdentity, OrganizationIdentity,
-            IdInfo, get_index_org_with_pubkey}
+    iota_did::{
+        did_registry::DidRegistryConfig,
+        keystore::{
+            KeystoreConfig, Population,
+            store_user, store_organization, add_channel
+        }
     },
-    utility::{verify_interaction, read_msgs, extract_msgs},
-};
-
-use wb_reputation_system::{
-    trust_score_generators::exchange_application_tsg::{
-        trivial_tsg::TsgOrganization,
+    implementation::{
+        user_and_organization::{UserIdentity, OrganizationIdentity},
+        organization_policy::PolicyConfig,
+        organization_reputation::{check_user, learn_memberships},
+        reputation_model::{
+            ReputationModelConfig, ReputationModel, RoleReputations, Role,
+            new_model, new_saveable_model, new_role_reputations, update_reputations
+        },
+        global_trust::GlobalTrustConfig,
+        recommendation::{RecommendationConfig, check_counterparty},
+        visibility::{VisibilityConfig, ObserverIndex},
+        witness_selection::{
+            WitnessSelectionConfig, WitnessSelection, SelectionRequest, witness_candidates
+        },
+        economy::{EconomyConfig, deny},
+        scheduler::SchedulerConfig,
+        faults::FaultConfig,
+        ledger::Backend,
+        witness_abuse::{AbusiveWitness, WitnessAbuse},
+        user_arena::{Checkout, RandomOrder, Interner}
     },
-    utility::parse_messages,
-    data_types::identity::identity::{
-        Identity ,ReputationMap
+    engine::{self, EngineOptions},
+    trust_score_generators::{
+        cross_org_tsg::OrgTrustMatrix,
+        select_tsg::{TsgBatch, run_user_tsg_batched},
+        user_tsg::TsgConfig
     }
 };
 
-use iota_streams::{
-    app::transport::tangle::client::Client,
-    app_channels::api::tangle::{
-        Author, ChannelType, Subscriber
-    },
-    app_channels::Tangle,
-    core::Result
-};
-use identity::{
-    crypto::{KeyPair, KeyType},
-};
+use wb_reputation_system::data_types::tsg_data_types::message::MessageAndPubkey;
+
+use iota_streams::core::Result;
+use anyhow::anyhow;
 
 use rand::Rng;
 use std::collections::BTreeSet;
 use std::collections::HashSet;
 use std::collections::HashMap;
-use std::convert::TryInto;
-use std::iter::FromIterator;
 use std::fs;
-use chrono::prelude::{Utc, DateTime};
+use std::sync::Arc;
 use serde::{Deserialize, Serialize};
 
 pub const ALPH9: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ9";
 pub const DEFAULT_DURATION: u32 = 60*60*24*365; // 1 year
 
-#[derive(Serialize, Clone, Debug)]
+// Every field but the population and its thresholds is optional, so configs can set
+// the ones they use and take the rest from Default (..Default::default())
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct SimulationConfig {
     pub node_url: String,
     pub num_users: usize,
@@ -58,12 +66,55 @@ pub struct SimulationConfig {
     pub user_organizations: Vec<usize>,
     pub organization_reputation_threshold: Vec<f32>,
     pub organization_default_reputation: Vec<f32>,
+    pub organization_trust: Option<Vec<Vec<f32>>>,
+    pub organization_policy: Option<Vec<PolicyConfig>>,
+    pub user_organization_threshold: Option<Vec<f32>>,
+    pub did_registry: Option<DidRegistryConfig>,
+    pub keystore: Option<KeystoreConfig>,
+    pub checkpoint_interval: Option<usize>,
+    pub reputation_model: Option<ReputationModelConfig>,
+    pub global_trust: Option<GlobalTrustConfig>,
+    pub user_reputation_model: Option<Vec<ReputationModelConfig>>,
+    pub reliability_changes: Option<Vec<ReliabilityChange>>,
+    pub user_tsg: Option<Vec<TsgConfig>>,
+    #[serde(default)]
+    pub role_reputation: bool,
+    pub recommendation: Option<RecommendationConfig>,
+    pub visibility: Option<VisibilityConfig>,
+    pub witness_selection: Option<WitnessSelectionConfig>,
+    pub witness_ceiling: Option<usize>,
+    pub max_tries: Option<usize>,
+    pub economy: Option<EconomyConfig>,
+    pub scheduler: Option<SchedulerConfig>,
+    #[serde(default)]
+    pub actors: bool,
+    pub faults: Option<FaultConfig>,
+    pub witness_abuse: Option<Vec<AbusiveWitness>>,
+}
+
+/// How many times participants look for a counterparty, and then for witnesses,
+/// before the run is abandoned, if the simulation parameters do not say
+pub const DEFAULT_MAX_TRIES: usize = 100;
+
+/// With a witness ceiling, the participants stop looking for witnesses once they have
+/// found this many times the ceiling, and choose among those
+pub const WITNESS_POOL_FACTOR: usize = 4;
+
+/// From the start of the run, the user behaves with the new reliability
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ReliabilityChange {
+    pub run: usize,
+    pub user: usize,
+    pub reliability: f32,
 }
 
 // For now this simulation is capturing the abstract scenario where the initiating participant wishes 
 // to informally buy something from somebody nearby. However, not all people around them are particpants
 // of the system he uses. Therefore, the average_proximity paramater is included. This  represents the
 // chance a participant being in range of some other participant.
+//
+// The simulation runs on the engine (engine.rs), with the interactions published on the organizations'
+// Streams channels. The quick simulation runs on the same engine without a ledger.
 // 
 // Params:
 //      - average_proximity: [0,1], 1 meaning all participants are in range
@@ -71,388 +122,347 @@ pub struct SimulationConfig {
 //      - runs: the number of iterations of the simulations
 //      - reliability: an array assigning a reliability score to participants at the respective indices
 //      - organizations: an array assigning a organization to participants at the respective indices
+//      - organization_(reputation_threshold/default_reputation): indexed by organization
+//      - organization_trust: optional matrix where [i][j] is the weight users of organization i give 
+//        to witnesses from organization j. If None, witnesses from other organizations are given
+//        the default reputation of the user
+//      - organization_policy: optional policy for each organization, indexed by organization. If None,
+//        organizations admit interactions whose average reputation meets their threshold
+//      - user_organization_threshold: optional minimum reputation, derived from its members, an organization
+//        must have for a user to accept its members as counterparties or witnesses
+//      - did_registry: optional local registry the DID documents of users and organizations are
+//        published to. If set, signatures are verified against the registered documents. If None,
+//        no documents are created and the keys presented by signers are trusted. Without a ledger
+//        (Backend::Quick), signatures are not verified, so it is ignored
+//      - keystore: optional encrypted file holding the identities of the users and organizations. If it
+//        exists, the population it holds takes part in the simulation, otherwise a new population is
+//        created and stored there. Either way, the channels the organizations open are recorded in it.
+//        Only used on Streams, the channels of the other backends not outliving the simulation
+//      - checkpoint_interval: optional number of runs after which the state of the simulation is saved
+//        to its run directory, so it can be continued with checkpoint::resume. The checkpoint holds the
+//        secret keys of the population, so it is encrypted with the passphrase of the keystore, which
+//        is then required. Only used on Streams
+//      - reputation_model: how users and organizations aggregate verdicts into reputations. If None,
+//        the model of the upstream Identity (ReputationModelConfig::Average) is used
+//      - global_trust: optional EigenTrust-style combination of all of the users' reputation maps, recomputed
+//        every interval runs. Users may base their checks on it instead of their own map
+//      - user_reputation_model: optional reputation model for each user, overriding reputation_model
+//      - reliability_changes: optional changes to the reliability of users during the simulation. The average
+//        reputation of the changed users is then written after every run, to reliability_changes.txt
+//      - user_tsg: optional TSG for each user. If None, every user runs the TSG of their organization
+//      - role_reputation: whether users keep separate reputations for others as participants and as witnesses,
+//        and choose counterparties and witnesses by them. They are written to participant_reputations.txt
+//        and witness_reputations.txt
+//      - recommendation: optional protocol in which users ask nearby users for signed opinions of a potential
+//        counterparty before accepting it. The messages it cost and how often the decisions were right are
+//        written to recommendations.txt
+//      - visibility: optional model of who learns about each interaction, limited to the users who got its
+//        keyload. If None, every user and organization processes every interaction. The average number
+//        of observers is written to visibility.txt
+//      - witness_selection: optional strategy by which the participants choose their witnesses among the
+//        users all of them accept. If None, every such user witnesses (WitnessSelectionConfig::AllEligible)
+//      - witness_ceiling: optional maximum number of witnesses in a interaction. The participants then only
+//        look for WITNESS_POOL_FACTOR times as many eligible users to choose among, instead of all of them
+//      - max_tries: optional number of attempts at finding a counterparty, and then enough witnesses,
+//        before the run is abandoned. Defaults to DEFAULT_MAX_TRIES
+//      - economy: optional value of the contracts and fees of the witnesses. If set, every user keeps a
+//        balance, written to utilities.txt with the value of the interactions they were denied, and the
+//        average balance of the users of each reliability is written to strategy_utilities.txt
+//      - scheduler: the timing of the interactions, which overlap in simulated time. Required by the event
+//        simulation (event_simulation.rs) and ignored by the others
+//      - actors: whether the participants, witnesses and organization of each interaction run as tasks
+//        exchanging requests (actor_interaction.rs). Only possible without a ledger (Backend::Quick),
+//        so the simulation fails with an error if it is set for another backend
+//      - faults: optional probabilities of dropped and delayed packets, offline witnesses, failed keyloads,
+//        crashed participants and node errors. Interactions are only given up before their InteractionMsg
+//        is on the channel; after that, faults cost them statements and compensations. The number of each
+//        fault and of the interactions given up are written to faults.txt
+//      - witness_abuse: optional users who, whenever they witness, get around the rules on witness statements
+//        (statement_rules.rs) by withholding, repeating, contradicting or copying statements
 pub async fn simulation(
     sc: SimulationConfig
 ) -> Result<String> {
+    let options = EngineOptions::new(Backend::Streams, "Emmulation run", true);
+    let (folder_name, _) = engine::run(&sc, &options).await?;
+    return Ok(folder_name);
+}
 
-    if sc.reliability.len() != sc.num_users {
-        panic!("Number of elements in 'reliability' parameter must equal the num_users!");
-    } else if sc.reliability.len() != sc.num_users {
-        panic!("Number of elements in 'organizations' parameter must equal the num_users!");
-    }
-
-    let mut rand_gen = rand::thread_rng();
-
-    let time: DateTime<Utc> = Utc::now();
-    let folder_name = format!("./runs/Emmulation run {:?}", time);
-    println!("{}", folder_name);
-    fs::create_dir(&folder_name)?;
-
-    
-    let file_name = format!("{}/sim_parameters.txt", &folder_name);
-    let output = serde_json::to_string(&sc)?;
-    fs::write(file_name, output).expect("Unable to write file");
-
-
-    //--------------------------------------------------------------
-    //--------------------------------------------------------------
-    //  CREATE ORGANIZATIONS WHICH ACT AS AN OVERLAY FOR PARTICIPANTS
-    //--------------------------------------------------------------
+// All participants and organizations who learned about each of the interactions run
+// their TSG on it, in order, and include the verdicts in their reputation maps. arrivals
+// holds when the messages of each interaction reached the channel, first_hand what the
+// participants of each interaction know first hand, and observers who learned about it,
+// if the simulation recorded them. Only those observers are visited, found through the
+// index. The TSG only runs once for all of the users it gives the same verdicts (see
+// select_tsg::TsgBatch).
+pub fn process_interactions(
+    participants: &mut Vec<UserIdentity>,
+    organizations: &mut Vec<OrganizationIdentity>,
+    index: &ObserverIndex,
+    interactions: &Vec<Vec<MessageAndPubkey>>,
+    arrivals: &Vec<Vec<u32>>,
+    first_hand: &Vec<HashMap<String, bool>>,
+    observers: &Vec<Option<HashSet<String>>>,
+    org_trust: &Option<OrgTrustMatrix>
+) {
+    let unknown: HashMap<String, bool> = HashMap::new();
+    for (k, parsed_msgs) in interactions.iter().enumerate() {
+        let knowledge = first_hand.get(k).unwrap_or(&unknown);
+        let (part_observers, org_observers) = index.observers(observers.get(k).unwrap_or(&None));
+        let mut batch = TsgBatch::new(Arc::new(parsed_msgs.clone()), arrivals.get(k).cloned().unwrap_or_default());
+        for i in part_observers {
+            let part = &mut participants[i];
+            let (tn_verdicts, wn_verdicts) = run_user_tsg_batched(part, &mut batch, org_trust, knowledge);
+            update_reputations(part, tn_verdicts, wn_verdicts);
+            learn_memberships(&mut part.id_info, parsed_msgs);
+        }
     
-    let client = Client::new_from_url(&sc.node_url);
-
-    // we find the set of organizations
-    let orgs_set: HashSet<&usize> = HashSet::from_iter(sc.user_organizations.iter());
-    let orgs: Vec<&usize> = orgs_set.into_iter().collect();
-
-    // in their simplest form, an organization can be represented by
-    // a keypair, so we assign one to each organization
-    let org_did_details = create_n_dids(orgs.len(), RunMode::Testing).await?;
-
-    // we create a mapping of organization index to public key and 
-    // create an OrganizationIdentity object for each organization
-    let mut org_kp_map: HashMap<usize, KeyPair> = HashMap::new();
-    let organizations: &mut Vec<OrganizationIdentity> = &mut Vec::new();
-    let mut i = 0;
-    for (_, (kp, (_,sec)), _) in org_did_details {
-        org_kp_map.insert(orgs[i].clone(), kp);
-        i += 1;
-
-        let seed: &str = &(0..81)
-        .map(|_| {
-            ALPH9
-                .chars()
-                .nth(rand_gen.gen_range(0, 27))
-                .unwrap()
-        })
-        .collect::<String>();
-
-        let on: Author<Tangle> = Author::new(seed, ChannelType::MultiBranch, client.clone());
-        let repeat_kp = KeyPair::try_from_ed25519_bytes(&sec)?;
-        let pubkey =  generate_sigs::get_multibase(&repeat_kp);
-        let reputation_map: ReputationMap = HashMap::new();
-
-        let org_id: Identity<Author<Client>, IdInfo> = Identity{
-            channel_client: on,
-            id_info: IdInfo {
-                seed: None,
-                did_key: sec,
-                reliability: None,
-                org_cert: generate_sigs::generate_org_cert(pubkey, &repeat_kp, DEFAULT_DURATION)?
-            },
-            reputation_map: reputation_map,
-            user_reputation_threshold: sc.organization_reputation_threshold[i],
-            user_default_reputation: sc.organization_default_reputation[i]
-        };
-
-        let org_id_with_announcement = OrganizationIdentity{
-            identity: org_id,
-            ann_msg: None
-        };
-        organizations.push(org_id_with_announcement);
+        // do the same for organizations
+        for i in org_observers {
+            let org = &mut organizations[i];
+            let (tn_verdicts, wn_verdicts) = run_user_tsg_batched(&org.identity, &mut batch, org_trust, &unknown);
+            update_reputations(&mut org.identity, tn_verdicts, wn_verdicts);
+        }
     }
+}
 
-
-    //--------------------------------------------------------------
-    // CREATE PARTICIPANTS FOR SIMULATION
-    // (MORE DETAILS IN ALL_IN_ONE_TRANSACTION.RS)
-    //--------------------------------------------------------------
-
-    // create Decentalised Ids (for now, none needed for the organization)
-    let did_details = create_n_dids(sc.num_users, RunMode::Testing).await?;
-    
-    let part_did_secret : Vec<Key> = did_details
-                                            .iter()
-                                            .map(|(_, (_,(_, privkey)), _)| *privkey)
-                                            .collect();
-    
-    let part_did_kps : Vec<&KeyPair> = did_details
-                                            .iter()
-                                            .map(|(_, (kp,_), _)| kp)
-                                            .collect();
-
-    // create channel subscriber instances
+// Writes each organization's policy decisions, so the effect of their strictness can be compared
+pub fn policy_decisions_string(organizations: &Vec<OrganizationIdentity>) -> String {
     let mut output: String = String::new();
-    let mut participants: &mut Vec<UserIdentity> = &mut Vec::new();
-    for i in 0..sc.num_users{
-        let name = format!("Participant {}", i);
-        let tn = Subscriber::new(&name, client.clone());
-        let org_kp = &org_kp_map[&sc.user_organizations[i]];
-        let part_did_pk = generate_sigs::get_multibase(&part_did_kps[i]);
-        let reputation_map: ReputationMap = HashMap::new();
-        let id = UserIdentity {
-            channel_client: tn,
-            id_info: IdInfo {
-                seed: Some(name),
-                did_key: part_did_secret[i],
-                reliability: Some(sc.reliability[i]),
-                org_cert: generate_sigs::generate_org_cert(part_did_pk.clone(), org_kp, DEFAULT_DURATION)?
-            },
-            reputation_map: reputation_map,
-            user_reputation_threshold: sc.user_reputation_threshold[i],
-            user_default_reputation: sc.user_default_reputation[i]
-        };
-        participants.push(id);
-
-        let part_entry = format!("{}: {}\n", part_did_pk, sc.reliability[i]);
-        output.push_str(&part_entry);
-    }
-
-    let file_name = format!("{}/start_reliability.txt", &folder_name);
-    fs::write(file_name, output).expect("Unable to write file");
-    
-    //--------------------------------------------------------------
-    // RUN SIMULATION
-    //--------------------------------------------------------------
-
-    // organizations (acting as authors) create the channels
-    for i in 0..organizations.len(){
-        println!("Creating the channel for oranization {}:", i);
-        let announcement_link = organizations[i].identity.channel_client.send_announce().await?;
-        let ann_link_string = announcement_link.to_string();
-        println!(
-            "-- Announcement Link: {} Tangle Index: {:#}\n",
-            ann_link_string, announcement_link.to_msg_index()
+    for org in organizations.iter() {
+        let admitted = org.decisions.iter().filter(|d| d.admitted).count();
+        let info = format!(
+            "{} ({} policy): admitted {} of {}\n",
+            org.identity.id_info.org_cert.client_pubkey, org.policy.name(), admitted, org.decisions.len()
         );
-
-        /* instantiate the reader subscriber (each sub can only attatch to one channel, but the)
-        name parameter is what determines the keypair values*/
-        let mut reader_sub = Subscriber::new("reader", client.clone());
-
-        // we add the reader subscriber to the channel
-        reader_sub.receive_announcement(&announcement_link).await?;
-        let subscribe_msg = reader_sub.send_subscribe(&announcement_link).await?;
-        organizations[i].identity.channel_client.receive_subscribe(&subscribe_msg).await?;
-
-        // change the organization struct to include their channel announcement
-        organizations[i].ann_msg = Some(ann_link_string);
+        output.push_str(&info);
+        for decision in org.decisions.iter() {
+            output.push_str(&format!("-- {:?}\n", decision));
+        }
+        output.push_str("\n");
     }
+    return output;
+}
 
-
-    // generate the lazy methods (currenlty the first half of the runs are 
-    // 'constant true' and the second half are 'random')
-    println!("Generating lazy methods:");
-    let lazy_methods: Vec<LazyMethod> = (0..sc.runs)
-        .map(|_| {
-            match rand_gen.gen_range(0,3) {
-                0 => LazyMethod::Constant(true),
-                1 => LazyMethod::Constant(false),
-                2 => LazyMethod::Random,
-                _ => panic!("Random number generator failure")
-            }
-        }).collect::<Vec<LazyMethod>>()
-        .try_into().expect("wrong size iterator");
-    println!("-- Lazy methods to be used: {:?}\n", lazy_methods);
-
-    for i in 0..sc.runs {
-        println!("\n\n\n---------------------STARTING RUN {}---------------------", i);
-        // run the iteration
-        let ran = simulation_iteration(
-            organizations,
-            participants,
-            sc.average_proximity,
-            sc.witness_floor,
-            lazy_methods[i].clone(),
-            &sc.node_url,
-            &format!("output_{}", i),
-            &mut rand_gen,
-            i,
-            folder_name.clone()
-        ).await?;
-
-        if !ran {
-            println!("FAILED TO RUN")
+// Validates the organization level parameters, which must have an entry for
+// every organization index, and fails on the first which does not
+pub fn check_organization_config(sc: &SimulationConfig) -> Result<()> {
+    let num_orgs = sc.user_organizations.iter().max().map(|m| m + 1).unwrap_or(0);
+    if let Some(matrix) = &sc.organization_trust {
+        if matrix.len() < num_orgs || matrix.iter().any(|row| row.len() < num_orgs) {
+            return Err(anyhow!("The 'organization_trust' matrix must have an entry for every pair of organizations!"));
         }
-
-        participants = reset_clients(participants, client.clone())?;
     }
-
-    // write all of the reputation maps to file, next to their did public key
-    let mut output: String = String::new();
-    for part in participants {
-        let pk = format!("{}\n", part.id_info.org_cert.client_pubkey);
-        let map = format!("{}\n\n", part.get_reputation_scores_string());
-        output.push_str(&pk);
-        output.push_str(&map);
+    if let Some(policies) = &sc.organization_policy {
+        if policies.len() < num_orgs {
+            return Err(anyhow!("The 'organization_policy' parameter must have an entry for every organization!"));
+        }
     }
-    let file_name = format!("{}/reputation_maps.txt", &folder_name);
-    fs::write(file_name, output).expect("Unable to write file");
-    
-    return Ok(folder_name);
+    if let Some(thresholds) = &sc.user_organization_threshold {
+        if thresholds.len() != sc.num_users {
+            return Err(anyhow!("The 'user_organization_threshold' parameter must have an entry for every user!"));
+        }
+    }
+    if let Some(tsgs) = &sc.user_tsg {
+        if tsgs.len() != sc.num_users {
+            return Err(anyhow!("The 'user_tsg' parameter must have an entry for every user!"));
+        }
+    }
+    if let Some(models) = &sc.user_reputation_model {
+        if models.len() != sc.num_users {
+            return Err(anyhow!("The 'user_reputation_model' parameter must have an entry for every user!"));
+        }
+    }
+    if sc.reliability_changes.iter().flatten().any(|change| change.user >= sc.num_users) {
+        return Err(anyhow!("The 'reliability_changes' parameter refers to a user who does not exist!"));
+    }
+    if let Some(recommendation) = &sc.recommendation {
+        if recommendation.liars.iter().any(|liar| *liar >= sc.num_users) {
+            return Err(anyhow!("The 'recommendation' parameter refers to a liar who does not exist!"));
+        }
+        if recommendation.weight < 0.0 || recommendation.weight > 1.0 {
+            return Err(anyhow!("The 'recommendation' weight must be in [0,1]!"));
+        }
+    }
+    if sc.witness_ceiling.map_or(false, |ceiling| ceiling < sc.witness_floor) {
+        return Err(anyhow!("The 'witness_ceiling' parameter must be at least the 'witness_floor'!"));
+    }
+    if let Some(faults) = &sc.faults {
+        let probabilities = vec![
+            faults.drop_packet, faults.delay_packet, faults.witness_offline,
+            faults.keyload_failure, faults.participant_crash, faults.node_error
+        ];
+        if probabilities.iter().any(|p| *p < 0.0 || *p > 1.0) {
+            return Err(anyhow!("The 'faults' probabilities must be in [0,1]!"));
+        }
+    }
+    if sc.witness_abuse.as_ref().map_or(false, |abusers| abusers.iter().any(|a| a.user >= sc.num_users)) {
+        return Err(anyhow!("The 'witness_abuse' parameter refers to a user who does not exist!"));
+    }
+    if let Some(VisibilityConfig::Proximity { proximity }) = &sc.visibility {
+        if *proximity < 0.0 || *proximity > 1.0 {
+            return Err(anyhow!("The 'visibility' proximity must be in [0,1]!"));
+        }
+    }
+    return Ok(());
 }
 
+pub fn user_witness_abuse(sc: &SimulationConfig, user: usize) -> Option<WitnessAbuse> {
+    return sc.witness_abuse
+        .as_ref()
+        .and_then(|abusers| abusers.iter().find(|a| a.user == user))
+        .map(|a| a.abuse.clone());
+}
 
-// Runs a single iteration of a simualtion
-pub async fn simulation_iteration(
-    organizations: &mut Vec<OrganizationIdentity>,
-    mut participants: &mut Vec<UserIdentity>,
-    average_proximity: f32,
-    witness_floor: usize,
-    lazy_method: LazyMethod,
-    node_url: &str,
-    output_name: &str,
-    rand_gen: &mut rand::prelude::ThreadRng,
-    run: usize,
-    folder_name: String
-) -> Result<bool> {
-
-    //--------------------------------------------------------------
-    // GENERATE GROUPS OF TRANSACATING NODES AND WITNESSES
-    //--------------------------------------------------------------
-
-    let gen_op = generate_participants_and_witnesses(
-        &mut participants,
-        average_proximity,
-        witness_floor,
-        rand_gen,
-        100,
-        true
-    )?;
-
-    let (mut participant_clients, mut witness_clients) = match gen_op{
-        None => {
-            return Ok(false);
-        },
-        Some(x) => x
+pub fn user_tsg_config(sc: &SimulationConfig, user: usize) -> TsgConfig {
+    return match &sc.user_tsg {
+        Some(tsgs) => tsgs[user].clone(),
+        None       => TsgConfig::Organization
     };
+}
 
-    //--------------------------------------------------------------
-    // GET THE ORGANIZATION OF THE INITIATING TRANSACTING NODE [0]
-    //--------------------------------------------------------------
-
-    // get orgs' pubkey and find the org with that pubkey
-    let init_tn_org_pk = &participant_clients[0].id_info.org_cert.org_pubkey;
-    let org_index = get_index_org_with_pubkey(&organizations, init_tn_org_pk);
-    println!("\nRun under organization {}\n", organizations[org_index].identity.id_info.org_cert.client_pubkey);
-
-
-    //--------------------------------------------------------------
-    // GENERATE CONTRACT
-    //--------------------------------------------------------------
-
-    println!("Generating contract:");
-    let contract = generate_contract::generate_exchange_contract(
-        &mut participant_clients,
-        organizations[org_index].ann_msg.clone().unwrap()
-    )?;
-    println!("-- Contract generated\n");
-
-    //--------------------------------------------------------------
-    // PERFORM THE INTERACTION WITH CONTRACT
-    //--------------------------------------------------------------
-
-    let interaction_result = interaction(
-        contract,
-        &mut participant_clients,
-        &mut witness_clients,
-        &mut organizations[org_index],
-        lazy_method,
-        run,
-    ).await?;
-    
-    let (mut tn_honesty, mut wn_honesty) = (Vec::new(), Vec::new());
-    match interaction_result{
-        Some((tn_h, wn_h)) => {
-            tn_honesty = tn_h;
-            wn_honesty = wn_h;
-        },
-        None => {
-            println!(
-                "The average reputation of the participants does not satisfy the organizations threshold"
-            );
-            return Ok(false);
-        }
+// What the participants of an interaction know first hand: whether each of them behaved honestly
+pub fn first_hand_knowledge(participant_clients: &Vec<UserIdentity>, tn_honesty: &Vec<bool>) -> HashMap<String, bool> {
+    return participant_clients
+        .iter()
+        .map(|part| part.id_info.org_cert.client_pubkey.clone())
+        .zip(tn_honesty.iter().cloned())
+        .collect();
+}
+
+pub fn user_role_reputations(sc: &SimulationConfig, user: usize, ids: &Interner) -> Option<RoleReputations> {
+    return match sc.role_reputation {
+        true  => Some(new_role_reputations(&user_model_config(sc, user), sc.user_default_reputation[user], ids)),
+        false => None
     };
+}
 
-    // put the particpants back into the original array
-    participants.append(&mut witness_clients);
-    participants.append(&mut participant_clients);
-
-    //--------------------------------------------------------------
-    // VERIFY THE INTERACTION AND SAVE THE OUTPUT TO FILE
-    //--------------------------------------------------------------
-
-    // verify the interaction
-    let ann_msg = &organizations[org_index].ann_msg.as_ref().unwrap();
-    let branches = verify_interaction::WhichBranch::LastBranch;
-    let channel_msgs = read_msgs::read_msgs(node_url, ann_msg).await?;
-    let application = String::from("ExchangeApplication");
-    let (verified, msgs, pks) = verify_interaction::verify_interaction(
-        channel_msgs, branches, application
-    ).await?;
-
-    if !verified {
-        panic!("One of the messages could not be verified");
-    }
+// The reputation model of an identity. A simulation which is checkpointed keeps every
+// reputation in a model whose state can be saved (see checkpoint.rs). The models of the
+// simulation share the interner of its population.
+pub fn identity_model(
+    sc: &SimulationConfig,
+    config: &ReputationModelConfig,
+    default_reputation: f32,
+    ids: &Interner
+) -> Option<Box<dyn ReputationModel>> {
+    return match sc.checkpoint_interval {
+        Some(_) => Some(new_saveable_model(config, default_reputation, ids)),
+        None    => new_model(config, default_reputation, ids)
+    };
+}
 
-    // for each message
-    let mut output: String = String::new();
-    let info = format!("TN honesty {:?}\nWN honesty: {:?}\n\n", tn_honesty, wn_honesty);
-    output.push_str(&info);
-    for i in 0..msgs.len() {
-        // print the message and then the id_info of the sender
-        let msg = format!("Message {:?}\n", msgs[i]);
-        let pk = format!("Channel pubkey: {:?}\n\n", pks[i]);
-        output.push_str(&msg);
-        output.push_str(&pk);
-    }
-    let file_name = format!("{}/{}", &folder_name, &output_name);
-    fs::write(file_name, output).expect("Unable to write file");
-
-    //--------------------------------------------------------------
-    // ALL PARTICIPANTS NOW UPDATE THEIR reputation SCORES BY
-    // PROCESSING THE LATEST INTERACTION
-    //--------------------------------------------------------------
-
-    // participants update their reputation scores of each other
-    let channel_msgs = read_msgs::read_msgs(node_url, ann_msg).await?;
-    let branch_msgs = extract_msgs::extract_msg(channel_msgs, verify_interaction::WhichBranch::LastBranch);
-    //println!("HHHHEERREEE: {:?}", branch_msgs);
-    let parsed_msgs = parse_messages::parse_messages(&branch_msgs)?;
-    for part in participants.into_iter() {
-
-        let tsg_org = TsgOrganization {
-            org_pubkey: part.id_info.org_cert.org_pubkey.clone(),
-            default_reputation: 0.5
-        };
-
-        part.run_tsg_and_include_in_rm(parsed_msgs.clone(), tsg_org);
-    }
+pub fn user_model_config(sc: &SimulationConfig, user: usize) -> ReputationModelConfig {
+    return match &sc.user_reputation_model {
+        Some(models) => models[user].clone(),
+        None         => sc.reputation_model.clone().unwrap_or(ReputationModelConfig::Average)
+    };
+}
 
-    // do the same for organizations
-    for org in organizations.into_iter() {
+// The public keys of the users, in the order of the simulation parameters
+pub fn read_user_pubkeys(folder_name: &str) -> Result<Vec<String>> {
+    let start_rels = fs::read_to_string(format!("{}/start_reliability.txt", folder_name))?;
+    return Ok(start_rels
+        .split('\n')
+        .filter_map(|line| line.split(": ").next().filter(|pk| pk.len() > 0))
+        .map(String::from)
+        .collect());
+}
 
-        let tsg_org = TsgOrganization {
-            org_pubkey: org.identity.id_info.org_cert.org_pubkey.clone(),
-            default_reputation: 0.5
-        };
+pub fn changed_user_pubkeys(changes: &Option<Vec<ReliabilityChange>>, user_pks: &Vec<String>) -> Vec<String> {
+    let users: BTreeSet<usize> = changes
+        .iter()
+        .flatten()
+        .map(|change| change.user)
+        .collect();
+    return users.into_iter().map(|user| user_pks[user].clone()).collect();
+}
 
-        org.identity.run_tsg_and_include_in_rm(parsed_msgs.clone(), tsg_org);
+pub fn apply_reliability_changes(
+    changes: &Option<Vec<ReliabilityChange>>,
+    run: usize,
+    participants: &mut Vec<UserIdentity>,
+    user_pks: &Vec<String>
+) {
+    for change in changes.iter().flatten().filter(|change| change.run == run) {
+        let pk = &user_pks[change.user];
+        if let Some(part) = participants.iter_mut().find(|part| &part.id_info.org_cert.client_pubkey == pk) {
+            println!("User {} now has reliability {}", change.user, change.reliability);
+            part.id_info.reliability = Some(change.reliability);
+        }
+    }
+}
 
-        //println!("tn_verdicts: {:?}", tn_verdicts);
-        //println!("wn_verdicts: {:?}\n", wn_verdicts);
+// A stored population can only be reused by a simulation with the same users and organizations
+pub fn check_population(sc: &SimulationConfig, population: &Population) -> Result<()> {
+    if population.users.len() != sc.num_users {
+        return Err(anyhow!("The keystore holds {} users, but 'num_users' is {}!", population.users.len(), sc.num_users));
+    }
+    for (i, user) in population.users.iter().enumerate() {
+        if user.organization != sc.user_organizations[i] {
+            return Err(anyhow!("User {} belongs to a different organization in the keystore!", i));
+        }
+        if population.organization(user.organization).is_none() {
+            return Err(anyhow!("The keystore holds no identity for organization {}!", user.organization));
+        }
     }
+    return Ok(());
+}
 
-    return Ok(true);
+// Adds the channels the organizations just opened to the population, or stores
+// the population for the first time
+pub fn update_population(
+    population: Option<Population>,
+    participants: &Vec<UserIdentity>,
+    organizations: &Vec<OrganizationIdentity>,
+    orgs: &Vec<&usize>,
+    user_organizations: &Vec<usize>
+) -> Population {
+    match population {
+        Some(mut stored) => {
+            for (i, org) in organizations.iter().enumerate() {
+                if let Some(stored_org) = stored.organization_mut(*orgs[i]) {
+                    add_channel(stored_org, org);
+                }
+            }
+            return stored;
+        },
+        None => {
+            let mut stored = Population::new();
+            for (i, part) in participants.iter().enumerate() {
+                stored.users.push(store_user(part, user_organizations[i]));
+            }
+            for (i, org) in organizations.iter().enumerate() {
+                stored.organizations.push(store_organization(org, *orgs[i]));
+            }
+            return stored;
+        }
+    }
 }
 
-// Generates the participants and the witnesses for the next simulation.
-// Will return None if no witnesses can be found after max_tries
+// Generates the participants and the witnesses for the next simulation, the witnesses
+// being chosen by the selection strategy among the users every participant accepts. They
+// are taken out of the users, and the checkout puts them back in their place afterwards.
+// The participants look at the users in a random order, and stop once they have found
+// WITNESS_POOL_FACTOR times the witness ceiling, if there is one, so that the search does
+// not grow with the number of users. Will return None if no witnesses can be found after max_tries
 pub fn generate_participants_and_witnesses(
     users: &mut Vec<UserIdentity>,
     average_proximity: f32,
     witness_floor: usize,
-    rand_gen: &mut rand::prelude::ThreadRng,
+    witness_ceiling: Option<usize>,
+    selection: &dyn WitnessSelection,
+    rand_gen: &mut impl Rng,
     max_tries: usize,
     print: bool
-) -> Result<Option<(Vec<UserIdentity>,Vec<UserIdentity>)>> {
+) -> Result<Option<(Vec<UserIdentity>, Vec<UserIdentity>, Checkout)>> {
 
     let mut participant_clients: Vec<UserIdentity> = Vec::new();
     let mut witness_clients: Vec<UserIdentity> = Vec::new();
+    let mut checkout = Checkout::new();
 
     // we select the initiating participant randomly
     let random_participant_index = rand_gen.gen_range(0, users.len());
-    participant_clients.push(users.remove(random_participant_index));
+    participant_clients.push(checkout.take(users, random_participant_index, Role::Participant, 0));
     
     // The initiating participant participant searches for another to transact with.
     // Using mod, this section will only finish when one is found, representing the start
@@ -462,110 +472,86 @@ pub fn generate_participants_and_witnesses(
     }
     for i in 0.. {
         if i >= max_tries {
-            users.append(&mut participant_clients);
+            checkout.put_back(users, participant_clients, witness_clients);
             return Ok(None);
         }
 
         let cur_index = i % users.len();
         if average_proximity > rand_gen.gen() {
-            // checking potential counterparty reputation
-            let potential_part_pk = users[cur_index].id_info.org_cert.client_pubkey.clone();
+            // checking potential counterparty reputation (and their organization's)
             if print{
                 println!("-- Checking user {}'s reputation", cur_index);
             }
-            if participant_clients[0].check_participant(&potential_part_pk){
-                participant_clients.push(users.remove(cur_index));
+            if check_counterparty(&mut participant_clients[0], cur_index, users, average_proximity, rand_gen)?{
+                participant_clients.push(checkout.take(users, cur_index, Role::Participant, 1));
                 if print{
                     println!("---- User {} added\n", cur_index);
                 }
                 break;
             }
+            deny(&mut users[cur_index]);
         }
     }
 
-    // The participants now search for witnesses and combine their results.
-    // Each iteration of the upper loop is one of the participants searching for
-    // witnesses. We must work with indexes instead of actual objects to removing potential
-    // witnesses from the list for participants of indices larger than 0
+    // The participants now search for witnesses together. A user is eligible if they are
+    // in range of every participant and every participant accepts them.
     if print{
         println!("Selecting users to be witnesses:");
     }
-    let mut main_set_of_witnesses: BTreeSet<usize> = BTreeSet::new();
+    let pool_size = witness_ceiling.map_or(usize::MAX, |ceiling| ceiling * WITNESS_POOL_FACTOR);
+    let mut chosen_witnesses: BTreeSet<usize> = BTreeSet::new();
     for i in 0.. {
         if i >= max_tries {
-            users.append(&mut participant_clients);
+            checkout.put_back(users, participant_clients, witness_clients);
             return Ok(None);
         }
 
-        let mut tn_witnesses_lists: Vec<Vec<usize>> = Vec::new();
-        for i in 0..participant_clients.len(){
+        let mut main_set_of_witnesses: BTreeSet<usize> = BTreeSet::new();
+        let mut order = RandomOrder::new(users.len());
+        while main_set_of_witnesses.len() < pool_size {
+            let j = match order.next(rand_gen) {
+                Some(j) => j,
+                None    => break
+            };
+            let in_range = participant_clients.iter().all(|_| average_proximity > rand_gen.gen());
             if print{
-                println!("-- Participant {} is finding witnesses:", i);
+                println!("---- Trying user {}. In range={}", j, in_range);
             }
-            let mut tn_witnesses: Vec<usize> = Vec::new();
-
-            for j in 0..users.len(){
-                let rand: f32 = rand_gen.gen();
+            if in_range && participant_clients.iter().all(|part| check_user(part, &users[j], Role::Witness)) {
+                main_set_of_witnesses.insert(j);
                 if print{
-                    println!("---- Trying user {}. Rand={}. Avg proximity={}", j, rand, average_proximity);
-                }
-                if average_proximity > rand {
-                    if print{
-                        println!("---- Checking user {}'s reputation", j);
-                    }
-                    let potential_wn_pk = users[j].id_info.org_cert.client_pubkey.clone();
-                    if participant_clients[i].check_participant(&potential_wn_pk){
-                        tn_witnesses.push(j);
-                        if print{
-                            println!("------ User {} added", j);
-                        }
-                    }
+                    println!("------ User {} added", j);
                 }
             }
-
-            if print{
-                println!("---- Found witnesses at indices: {:?}\n", tn_witnesses);
-            }
-            tn_witnesses_lists.push(tn_witnesses);
-        }
-
-        // The participants combine their witnesses, and check if there are enough.
-        // Using BTreeSet because it is ordered
-        for witness in tn_witnesses_lists[0].clone(){
-            main_set_of_witnesses.insert(witness);
-        }
-
-        for i in 1..tn_witnesses_lists.len() {
-            let mut set_of_witnesses: BTreeSet<usize> = BTreeSet::new();
-            for witness in tn_witnesses_lists[i].clone(){
-                set_of_witnesses.insert(witness);
-            }
-            main_set_of_witnesses = main_set_of_witnesses.intersection(&set_of_witnesses).cloned().collect();
         }
 
         println!("-- Final list of witness indices: {:?}", main_set_of_witnesses);
 
         if main_set_of_witnesses.len() >= witness_floor {
-            break;
+            let eligible: Vec<usize> = main_set_of_witnesses.iter().cloned().collect();
+            let request = SelectionRequest {
+                participant_orgs: participant_clients.iter().map(|p| p.id_info.org_cert.org_pubkey.clone()).collect(),
+                witness_floor: witness_floor,
+                witness_ceiling: witness_ceiling
+            };
+            let candidates = witness_candidates(&participant_clients, users, &eligible);
+            if let Some(chosen) = selection.select(&candidates, &request, rand_gen) {
+                chosen_witnesses = chosen.into_iter().collect();
+                if print{
+                    println!("-- {} selection chose witness indices: {:?}", selection.name(), chosen_witnesses);
+                }
+                break;
+            }
         }
     }
 
-    // convert indices into objects (as it is ordered, we can account for
-    // the changing indices)
-    for (i, witness) in main_set_of_witnesses.iter().enumerate() {
-        witness_clients.push(users.remove(*witness - i))
+    // take the witnesses out from the highest index down, so that the indices of those
+    // still to be taken are not moved, keeping them in the order of their indices
+    let mut taken: Vec<Option<UserIdentity>> = chosen_witnesses.iter().map(|_| None).collect();
+    for (place, witness) in chosen_witnesses.iter().enumerate().rev() {
+        taken[place] = Some(checkout.take(users, *witness, Role::Witness, place));
     }
+    witness_clients.extend(taken.into_iter().flatten());
 
-    return Ok(Some((participant_clients, witness_clients)));
-}
-
-pub fn reset_clients(
-    participants: &mut Vec<UserIdentity>,
-    client: Client
-) -> Result<&mut Vec<UserIdentity>> {
-    for i in 0..participants.len(){
-        let new_client = Subscriber::new(&participants[i].id_info.seed.clone().unwrap(), client.clone());
-        participants[i].channel_client = new_client;
-    }
-    return Ok(participants);
+    return Ok(Some((participant_clients, witness_clients, checkout)));
 }
\ No newline at end of file
@@ -0,0 +1,153 @@
+use crate::witness_rep::trust_score_generators::{
+    user_tsg::weighted_vote,
+    statement_rules::check_interaction
+};
+
+use wb_reputation_system::data_types::{
+    event_protocol_messages::event_protocol_messages::Message,
+    tsg_data_types::message::MessageAndPubkey
+};
+
+use std::collections::HashMap;
+
+/// Verdicts about users, as included in reputation maps
+pub type Verdicts = Vec<(String, f32)>;
+
+/// How much the users of each organization trust the witnesses certified by
+/// every other organization, keyed by the organizations' public keys
+#[derive(Clone, Debug)]
+pub struct OrgTrustMatrix {
+    pub trust: HashMap<String, HashMap<String, f32>>
+}
+
+impl OrgTrustMatrix {
+    /// Converts the matrix from the SimulationConfig, which is indexed by organization
+    /// (matrix[user's org][witness's org]), into one keyed by public key
+    pub fn from_config(
+        matrix: &Vec<Vec<f32>>,
+        org_pubkeys: &HashMap<usize, String>
+    ) -> OrgTrustMatrix {
+        let mut trust: HashMap<String, HashMap<String, f32>> = HashMap::new();
+        for (from, from_pk) in org_pubkeys.iter() {
+            let mut row: HashMap<String, f32> = HashMap::new();
+            for (to, to_pk) in org_pubkeys.iter() {
+                row.insert(to_pk.clone(), matrix[*from][*to]);
+            }
+            trust.insert(from_pk.clone(), row);
+        }
+        return OrgTrustMatrix { trust: trust };
+    }
+
+    /// The trust users of from_org place in witnesses of to_org, or the
+    /// default if the matrix has no entry for them
+    pub fn get_trust(&self, from_org: &str, to_org: &str, default: f32) -> f32 {
+        return self.trust
+            .get(from_org)
+            .and_then(|row| row.get(to_org))
+            .cloned()
+            .unwrap_or(default);
+    }
+}
+
+/// The parts of an interaction's messages a TSG needs
+pub struct InteractionInfo {
+    /// The participants' did pubkeys, in the order witnesses give outcomes for them
+    pub participants: Vec<String>,
+    /// The witnesses' did pubkeys and their organizations' pubkeys
+    pub witnesses: Vec<(String, String)>,
+    /// The outcome each witness stated in time, keyed by did pubkey, by the rules
+    /// of statement_rules.rs
+    pub statements: HashMap<String, Vec<bool>>,
+    /// The witnesses who stated different outcomes
+    pub equivocators: Vec<String>,
+    /// Whether any statement broke the rules, and was left out
+    pub breached: bool
+}
+
+impl InteractionInfo {
+    /// Whether every witness of the interaction made a single statement in time. Witnesses
+    /// who went offline, or whose statements were lost or late, leave it incomplete.
+    pub fn is_complete(&self) -> bool {
+        return !self.breached && self.witnesses.iter().all(|(witness, _)| self.statements.contains_key(witness));
+    }
+}
+
+// Collects the participants and witnesses from the InteractionMsg and the outcomes
+// from the WitnessStatements which follow the rules on statements, by the time each
+// message reached the channel. Returns None if there is no InteractionMsg.
+pub fn get_interaction_info(msgs: &Vec<MessageAndPubkey>, arrivals: &Vec<u32>) -> Option<InteractionInfo> {
+    let mut info: Option<InteractionInfo> = None;
+    for msg in msgs.iter() {
+        match &msg.message {
+            Message::InteractionMsg {
+                contract: _, witnesses: _, witness_sigs, interaction_sigs
+            } => {
+                info = Some(InteractionInfo {
+                    participants: interaction_sigs.0
+                        .iter()
+                        .map(|sig| sig.signer_did_pubkey.clone())
+                        .collect(),
+                    witnesses: witness_sigs.0
+                        .iter()
+                        .map(|sig| (sig.signer_did_pubkey.clone(), sig.org_cert.org_pubkey.clone()))
+                        .collect(),
+                    statements: HashMap::new(),
+                    equivocators: Vec::new(),
+                    breached: false
+                });
+            },
+            _ => {}
+        }
+    }
+
+    let report = check_interaction(msgs, arrivals);
+    return info.map(|mut i| {
+        i.breached = report.breached();
+        i.statements = report.statements;
+        i.equivocators = report.equivocators;
+        i
+    });
+}
+
+// A TSG where the witnesses' statements are weighted by how much the user's organization
+// trusts the organization that certified each witness. A participant is judged honest
+// if the weighted majority says so, and a witness is judged honest if it agreed with
+// the judgement on every participant.
+pub fn tsg_cross_organization(
+    msgs: &Vec<MessageAndPubkey>,
+    arrivals: &Vec<u32>,
+    org_pubkey: &str,
+    default_reputation: f32,
+    org_trust: &OrgTrustMatrix
+) -> (Verdicts, Verdicts) {
+    let info = match get_interaction_info(msgs, arrivals) {
+        None    => return (Vec::new(), Vec::new()),
+        Some(i) => i
+    };
+
+    return weighted_vote(
+        &info,
+        |_, witness_org| org_trust.get_trust(org_pubkey, witness_org, default_reputation),
+        &HashMap::new()
+    );
+}
+
+#[test]
+pub fn test_org_trust_matrix() {
+    let org_pubkeys: HashMap<usize, String> = vec![
+        (0, String::from("org0")),
+        (1, String::from("org1")),
+    ].into_iter().collect();
+    let matrix = vec![
+        vec![1.0, 0.2],
+        vec![0.7, 1.0],
+    ];
+
+    let org_trust = OrgTrustMatrix::from_config(&matrix, &org_pubkeys);
+    assert_eq!(org_trust.get_trust("org0", "org1", 0.5), 0.2);
+    assert_eq!(org_trust.get_trust("org1", "org0", 0.5), 0.7);
+    assert_eq!(org_trust.get_trust("org1", "org1", 0.5), 1.0);
+
+    // unknown organizations fall back on the default
+    assert_eq!(org_trust.get_trust("org0", "org2", 0.5), 0.5);
+}
@@ -0,0 +1,4 @@
+pub mod cross_org_tsg;
+pub mod select_tsg;
+pub mod user_tsg;
+pub mod statement_rules;
\ No newline at end of file
@@ -0,0 +1,91 @@
+use crate::witness_rep::{
+    implementation::{
+        user_and_organization::IdInfo,
+        reputation_model::reputation_scores
+    },
+    trust_score_generators::{
+        cross_org_tsg::{OrgTrustMatrix, Verdicts},
+        user_tsg::{TsgConfig, TsgContext, new_tsg}
+    }
+};
+
+use wb_reputation_system::{
+    data_types::{
+        identity::identity::Identity,
+        tsg_data_types::message::MessageAndPubkey
+    }
+};
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// The messages of an interaction and when they reached the channel, shared by everyone
+/// who processes it, and the verdicts the TSGs gave on them so far, by batch key (see
+/// TrustScoreGenerator::batch_key)
+pub struct TsgBatch {
+    pub msgs: Arc<Vec<MessageAndPubkey>>,
+    pub arrivals: Vec<u32>,
+    verdicts: HashMap<String, (Verdicts, Verdicts)>
+}
+
+impl TsgBatch {
+    pub fn new(msgs: Arc<Vec<MessageAndPubkey>>, arrivals: Vec<u32>) -> TsgBatch {
+        return TsgBatch { msgs, arrivals, verdicts: HashMap::new() };
+    }
+}
+
+// What the user (or organization) brings to their TSG
+fn tsg_context<'a, C>(
+    id: &'a Identity<C, IdInfo>,
+    org_trust: &'a Option<OrgTrustMatrix>,
+    first_hand: &'a HashMap<String, bool>,
+    arrivals: &'a Vec<u32>
+) -> TsgContext<'a> {
+    let reputations = match id.id_info.state.tsg {
+        TsgConfig::ReputationWeighted => Some(reputation_scores(id)),
+        _ => None
+    };
+    return TsgContext {
+        user_pubkey: &id.id_info.org_cert.client_pubkey,
+        org_pubkey: &id.id_info.org_cert.org_pubkey,
+        default_reputation: id.user_default_reputation,
+        reputations: reputations,
+        org_trust: org_trust,
+        first_hand: first_hand,
+        arrivals: arrivals
+    };
+}
+
+// Runs the TSG the user (or organization) has chosen, from their perspective. Without
+// a trust matrix, the organization TSG gives all witnesses from other organizations
+// the same flat default.
+pub fn run_user_tsg<C>(
+    id: &Identity<C, IdInfo>,
+    msgs: &Vec<MessageAndPubkey>,
+    arrivals: &Vec<u32>,
+    org_trust: &Option<OrgTrustMatrix>,
+    first_hand: &HashMap<String, bool>
+) -> (Verdicts, Verdicts) {
+    let ctx = tsg_context(id, org_trust, first_hand, arrivals);
+    return new_tsg(&id.id_info.state.tsg).verdicts(msgs, &ctx);
+}
+
+// Like run_user_tsg, but only runs the TSG if nobody with the same batch key has
+// processed the interaction yet, and otherwise hands out the same verdicts
+pub fn run_user_tsg_batched<C>(
+    id: &Identity<C, IdInfo>,
+    batch: &mut TsgBatch,
+    org_trust: &Option<OrgTrustMatrix>,
+    first_hand: &HashMap<String, bool>
+) -> (Verdicts, Verdicts) {
+    let ctx = tsg_context(id, org_trust, first_hand, &batch.arrivals);
+    let tsg = new_tsg(&id.id_info.state.tsg);
+    let msgs = &batch.msgs;
+    return match tsg.batch_key(&ctx) {
+        None      => tsg.verdicts(msgs, &ctx),
+        Some(key) => batch.verdicts
+            .entry(key)
+            .or_insert_with(|| tsg.verdicts(msgs, &ctx))
+            .clone()
+    };
+}
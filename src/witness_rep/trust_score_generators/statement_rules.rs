@@ -0,0 +1,186 @@
+use wb_reputation_system::data_types::{
+    event_protocol_messages::event_protocol_messages::{Contract, Message, Outcome},
+    tsg_data_types::message::MessageAndPubkey
+};
+
+use std::collections::HashMap;
+
+/// The verdict for a witness who made no statement in time. A witness who withholds
+/// their statement cannot be told apart from one who went offline, so both are judged
+/// as if they had lied, or withholding would be a safe way for a lazy witness to go unjudged.
+pub const ABSENT_WITNESS_VERDICT: f32 = 0.0;
+
+/// The verdict for a witness who stated two different outcomes of the same interaction
+pub const EQUIVOCATION_VERDICT: f32 = 0.0;
+
+/// What the rules on witness statements make of the statements of an interaction.
+/// Senders are identified by whichever key the messages were given with.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StatementReport {
+    /// The outcome each witness stated in time, leaving out the equivocators
+    pub statements: HashMap<String, Vec<bool>>,
+    /// The senders whose first statement came after the deadline
+    pub late: Vec<String>,
+    /// The senders who repeated a statement
+    pub duplicates: Vec<String>,
+    /// The senders who stated different outcomes
+    pub equivocators: Vec<String>,
+}
+
+impl StatementReport {
+    /// Whether any statement broke the rules
+    pub fn breached(&self) -> bool {
+        return self.late.len() > 0 || self.duplicates.len() > 0 || self.equivocators.len() > 0;
+    }
+}
+
+// When the contract was made, which is when the witnesses state the outcome, and when it
+// times out, which is the deadline for their statements. The participants compensate once
+// it has timed out. Contracts of other applications set no deadline.
+pub fn contract_times(contract: &Contract) -> (u32, u32) {
+    return match contract {
+        Contract::ExchangeApplication(exchange) => (exchange.time, exchange.timeout),
+        _ => (0, u32::MAX)
+    };
+}
+
+// Applies the rules on witness statements to the messages of an interaction, in the order
+// they are on the channel, each with the time it reached the channel if that is known:
+//      - a witness makes a single statement before the deadline, the timeout of the contract
+//        in the InteractionMsg. Statements which reached the channel after it are left out,
+//        however they were held up. Those whose arrival is not known are taken to be in time.
+//      - a statement repeating an earlier one of the same witness is left out
+//      - a witness who states different outcomes has equivocated, and all of their
+//        statements are left out
+pub fn check_statements<'a, I>(msgs: I) -> StatementReport
+where
+    I: IntoIterator<Item = (&'a str, &'a Message, Option<u32>)>
+{
+    let mut report = StatementReport::default();
+    let mut first_statements: HashMap<String, Vec<bool>> = HashMap::new();
+    let mut deadline: Option<u32> = None;
+    for (sender, msg, arrival) in msgs {
+        let outcomes = match msg {
+            Message::WitnessStatement { outcome: Outcome::ExchangeApplication(outcomes) } => outcomes,
+            Message::InteractionMsg { contract, .. } => {
+                deadline = deadline.or(Some(contract_times(contract).1));
+                continue;
+            },
+            _ => continue
+        };
+
+        match first_statements.get(sender) {
+            Some(first) if first == outcomes => report.duplicates.push(String::from(sender)),
+            Some(_) => {
+                if !report.equivocators.iter().any(|e| e == sender) {
+                    report.equivocators.push(String::from(sender));
+                }
+            },
+            None => {
+                first_statements.insert(String::from(sender), outcomes.clone());
+                let late = match (arrival, deadline) {
+                    (Some(arrival), Some(deadline)) => arrival > deadline,
+                    _ => false
+                };
+                match late {
+                    true  => report.late.push(String::from(sender)),
+                    false => {
+                        report.statements.insert(String::from(sender), outcomes.clone());
+                    }
+                };
+            }
+        }
+    }
+
+    for equivocator in report.equivocators.iter() {
+        report.statements.remove(equivocator);
+    }
+    return report;
+}
+
+// Applies the rules to the messages of an interaction, given with the time each of them
+// reached the channel. Arrivals may be empty if they are not known.
+pub fn check_interaction(msgs: &Vec<MessageAndPubkey>, arrivals: &Vec<u32>) -> StatementReport {
+    return check_statements(msgs
+        .iter()
+        .enumerate()
+        .map(|(i, msg)| (msg.sender_did.as_str(), &msg.message, arrivals.get(i).cloned()))
+    );
+}
+
+#[cfg(test)]
+fn statement(outcomes: Vec<bool>) -> Message {
+    return Message::WitnessStatement { outcome: Outcome::ExchangeApplication(outcomes) };
+}
+
+#[cfg(test)]
+pub fn test_interaction_msg(time: u32, timeout: u32) -> Message {
+    use wb_reputation_system::data_types::event_protocol_messages::{
+        application_constructs::application_contracts::{
+            exchange_app_contract::ExchangeContract,
+            utility_types::{ParticipantUsers, WitnessUsers}
+        },
+        event_protocol_messages::{ArrayOfWnSignitures, ArrayOfIntSignitures}
+    };
+    let contract = Contract::ExchangeApplication(ExchangeContract {
+        channel_address: String::from("channel"),
+        offer: String::from("p1 allows p2 take their place in the queue"),
+        participants: ParticipantUsers(Vec::new()),
+        compensation: Vec::new(),
+        time: time,
+        location: ((53, 20, 27.036), (6, 15, 2.695)),
+        timeout: timeout
+    });
+    return Message::InteractionMsg {
+        contract: contract,
+        witnesses: WitnessUsers(Vec::new()),
+        witness_sigs: ArrayOfWnSignitures(Vec::new()),
+        interaction_sigs: ArrayOfIntSignitures(Vec::new())
+    };
+}
+
+#[test]
+pub fn test_check_statements() {
+    use wb_reputation_system::data_types::event_protocol_messages::{
+        application_constructs::application_messages::exchange_app_messages::CompensationMsg,
+        event_protocol_messages::ApplicationMsg
+    };
+    let compensation = Message::ApplicationMsg(ApplicationMsg::ExchangeApplication(CompensationMsg {
+        payments: vec![String::from("wn_a: 0.01")]
+    }));
+
+    // the contract is made at 100 and times out at 200
+    let msgs: Vec<(&str, Message, Option<u32>)> = vec![
+        ("p0", test_interaction_msg(100, 200), Some(100)),
+        ("w0", statement(vec![true, true]), Some(100)),
+        ("w1", statement(vec![true, false]), Some(100)),
+        ("w1", statement(vec![true, false]), Some(100)),
+        ("w2", statement(vec![false, false]), Some(100)),
+        ("w2", statement(vec![true, true]), Some(100)),
+        ("p0", compensation, Some(200)),
+        ("w4", statement(vec![true, true]), Some(150)),
+        ("w3", statement(vec![true, true]), Some(201)),
+        ("w0", statement(vec![false, false]), Some(201)),
+    ];
+    let report = check_statements(msgs.iter().map(|(sender, msg, arrival)| (*sender, msg, *arrival)));
+
+    // a repeated statement still counts once, and a late one not at all. A delayed statement
+    // which reached the channel before the timeout is in time, even after a compensation.
+    assert_eq!(report.statements.len(), 2);
+    assert_eq!(report.statements["w1"], vec![true, false]);
+    assert_eq!(report.statements["w4"], vec![true, true]);
+    assert_eq!(report.duplicates, vec![String::from("w1")]);
+    assert_eq!(report.late, vec![String::from("w3")]);
+
+    // contradicting an earlier statement is equivocation, even after the deadline
+    assert_eq!(report.equivocators, vec![String::from("w2"), String::from("w0")]);
+    assert!(report.breached());
+
+    // without the arrivals, no statement can be shown to be late
+    let report = check_statements(msgs.iter().map(|(sender, msg, _)| (*sender, msg, None)));
+    assert_eq!(report.late.len(), 0);
+    assert_eq!(report.statements.len(), 3);
+
+    let clean = vec![("w0", statement(vec![true]))];
+    assert!(!check_statements(clean.iter().map(|(sender, msg)| (*sender, msg, Some(0)))).breached());
+}
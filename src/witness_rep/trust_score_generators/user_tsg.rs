@@ -0,0 +1,347 @@
+use crate::witness_rep::trust_score_generators::{
+    cross_org_tsg::{
+        OrgTrustMatrix, Verdicts, InteractionInfo, get_interaction_info, tsg_cross_organization
+    },
+    statement_rules::{ABSENT_WITNESS_VERDICT, EQUIVOCATION_VERDICT}
+};
+
+use wb_reputation_system::{
+    trust_score_generators::exchange_application_tsg::trivial_tsg::tsg_organization,
+    data_types::tsg_data_types::message::MessageAndPubkey
+};
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The TSG a user judges interactions with
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum TsgConfig {
+    /// The upstream organization TSG, or the cross-organization TSG if the
+    /// simulation has an organization trust matrix
+    Organization,
+    /// Every witness statement counts the same
+    MajorityVote,
+    /// Each witness statement is weighted by the user's reputation for the witness
+    ReputationWeighted,
+    /// A majority vote, except that participants of the interaction judge the
+    /// other participants by what they experienced themselves
+    FirstHandAware,
+    /// Witnesses from the user's own organization are weighted by own_org_weight,
+    /// in [0,1], and the others by 1 - own_org_weight
+    OrganizationWeighted { own_org_weight: f32 },
+}
+
+impl Default for TsgConfig {
+    fn default() -> TsgConfig {
+        return TsgConfig::Organization;
+    }
+}
+
+/// What a user brings to the TSG besides the messages of the interaction
+pub struct TsgContext<'a> {
+    pub user_pubkey: &'a str,
+    pub org_pubkey: &'a str,
+    pub default_reputation: f32,
+    /// The user's reputations, only needed by TsgConfig::ReputationWeighted
+    pub reputations: Option<HashMap<String, f32>>,
+    pub org_trust: &'a Option<OrgTrustMatrix>,
+    /// Whether each participant of the interaction actually behaved honestly. Only
+    /// known to the simulation as it runs, so empty when interactions are replayed.
+    pub first_hand: &'a HashMap<String, bool>,
+    /// When each message of the interaction reached the channel, which decides whether
+    /// the statements were in time. Empty if not known, when no statement counts as late.
+    pub arrivals: &'a Vec<u32>,
+}
+
+pub trait TrustScoreGenerator {
+    fn verdicts(&self, msgs: &Vec<MessageAndPubkey>, ctx: &TsgContext) -> (Verdicts, Verdicts);
+
+    /// What the verdicts depend on besides the messages. Users with the same key get the
+    /// same verdicts on an interaction, so the TSG only has to run once for all of them
+    /// (see select_tsg::TsgBatch). None if the verdicts depend on the user's own reputations.
+    fn batch_key(&self, ctx: &TsgContext) -> Option<String>;
+}
+
+pub struct OrganizationTsg;
+pub struct MajorityVoteTsg;
+pub struct ReputationWeightedTsg;
+pub struct FirstHandAwareTsg;
+pub struct OrganizationWeightedTsg {
+    pub own_org_weight: f32
+}
+
+impl TrustScoreGenerator for OrganizationTsg {
+    fn verdicts(&self, msgs: &Vec<MessageAndPubkey>, ctx: &TsgContext) -> (Verdicts, Verdicts) {
+        return match ctx.org_trust {
+            None => match get_interaction_info(msgs, ctx.arrivals) {
+                None => (Vec::new(), Vec::new()),
+                // the upstream TSG is only given complete interactions. Without some of the
+                // statements, the others are weighted the way it weights them.
+                Some(info) if !info.is_complete() => weighted_vote(
+                    &info,
+                    |_, witness_org| if witness_org == ctx.org_pubkey { 1.0 } else { ctx.default_reputation },
+                    &HashMap::new()
+                ),
+                Some(_) => tsg_organization(
+                    msgs.clone(),
+                    String::from(ctx.org_pubkey),
+                    ctx.default_reputation
+                ).unwrap()
+            },
+            Some(matrix) => tsg_cross_organization(
+                msgs,
+                ctx.arrivals,
+                ctx.org_pubkey,
+                ctx.default_reputation,
+                matrix
+            )
+        };
+    }
+
+    fn batch_key(&self, ctx: &TsgContext) -> Option<String> {
+        return Some(format!("Organization {} {}", ctx.org_pubkey, ctx.default_reputation));
+    }
+}
+
+impl TrustScoreGenerator for MajorityVoteTsg {
+    fn verdicts(&self, msgs: &Vec<MessageAndPubkey>, ctx: &TsgContext) -> (Verdicts, Verdicts) {
+        return match get_interaction_info(msgs, ctx.arrivals) {
+            None       => (Vec::new(), Vec::new()),
+            Some(info) => weighted_vote(&info, |_, _| 1.0, &HashMap::new())
+        };
+    }
+
+    fn batch_key(&self, _ctx: &TsgContext) -> Option<String> {
+        return Some(String::from("MajorityVote"));
+    }
+}
+
+impl TrustScoreGenerator for ReputationWeightedTsg {
+    fn verdicts(&self, msgs: &Vec<MessageAndPubkey>, ctx: &TsgContext) -> (Verdicts, Verdicts) {
+        let empty: HashMap<String, f32> = HashMap::new();
+        let reputations = ctx.reputations.as_ref().unwrap_or(&empty);
+        return match get_interaction_info(msgs, ctx.arrivals) {
+            None       => (Vec::new(), Vec::new()),
+            Some(info) => weighted_vote(
+                &info,
+                |witness, _| reputations.get(witness).cloned().unwrap_or(ctx.default_reputation),
+                &HashMap::new()
+            )
+        };
+    }
+
+    fn batch_key(&self, _ctx: &TsgContext) -> Option<String> {
+        return None;
+    }
+}
+
+impl TrustScoreGenerator for FirstHandAwareTsg {
+    fn verdicts(&self, msgs: &Vec<MessageAndPubkey>, ctx: &TsgContext) -> (Verdicts, Verdicts) {
+        let info = match get_interaction_info(msgs, ctx.arrivals) {
+            None    => return (Vec::new(), Vec::new()),
+            Some(i) => i
+        };
+
+        // only a participant of the interaction experienced it first hand
+        let took_part = info.participants.iter().any(|pk| pk == ctx.user_pubkey);
+        let first_hand: HashMap<String, bool> = match took_part {
+            true  => ctx.first_hand.clone(),
+            false => HashMap::new()
+        };
+        return weighted_vote(&info, |_, _| 1.0, &first_hand);
+    }
+
+    // first hand knowledge is kept for the participants of the interaction, so whoever
+    // has some took part in it, and everyone else gets the majority vote
+    fn batch_key(&self, ctx: &TsgContext) -> Option<String> {
+        return Some(format!("FirstHandAware {}", ctx.first_hand.contains_key(ctx.user_pubkey)));
+    }
+}
+
+impl TrustScoreGenerator for OrganizationWeightedTsg {
+    fn verdicts(&self, msgs: &Vec<MessageAndPubkey>, ctx: &TsgContext) -> (Verdicts, Verdicts) {
+        let own_org_weight = self.own_org_weight;
+        return match get_interaction_info(msgs, ctx.arrivals) {
+            None       => (Vec::new(), Vec::new()),
+            Some(info) => weighted_vote(
+                &info,
+                |_, witness_org| if witness_org == ctx.org_pubkey { own_org_weight } else { 1.0 - own_org_weight },
+                &HashMap::new()
+            )
+        };
+    }
+
+    fn batch_key(&self, ctx: &TsgContext) -> Option<String> {
+        return Some(format!("OrganizationWeighted {} {}", self.own_org_weight, ctx.org_pubkey));
+    }
+}
+
+pub fn new_tsg(config: &TsgConfig) -> Box<dyn TrustScoreGenerator> {
+    return match config {
+        TsgConfig::Organization         => Box::new(OrganizationTsg),
+        TsgConfig::MajorityVote         => Box::new(MajorityVoteTsg),
+        TsgConfig::ReputationWeighted   => Box::new(ReputationWeightedTsg),
+        TsgConfig::FirstHandAware       => Box::new(FirstHandAwareTsg),
+        TsgConfig::OrganizationWeighted { own_org_weight } => Box::new(
+            OrganizationWeightedTsg { own_org_weight: *own_org_weight }
+        ),
+    };
+}
+
+// Whether the TSG gives different verdicts to users who took part in the interaction,
+// in which case the verdicts cannot be recomputed from the ledger alone
+pub fn uses_first_hand(config: &TsgConfig) -> bool {
+    return *config == TsgConfig::FirstHandAware;
+}
+
+// A participant is judged honest if the weighted majority of the witnesses says so,
+// unless the user knows better first hand, and a witness is judged honest if it agreed
+// with the judgement on every participant. Witnesses who made no statement in time, or
+// who equivocated, are penalised (see statement_rules.rs).
+pub fn weighted_vote<F>(
+    info: &InteractionInfo,
+    weight: F,
+    first_hand: &HashMap<String, bool>
+) -> (Verdicts, Verdicts)
+where
+    F: Fn(&str, &str) -> f32
+{
+    // the weighted vote on each participant
+    let mut tn_verdicts: Verdicts = Vec::new();
+    let mut judgements: Vec<Option<bool>> = Vec::new();
+    for (j, participant) in info.participants.iter().enumerate() {
+        let mut honest_weight: f32 = 0.0;
+        let mut total_weight: f32 = 0.0;
+        for (witness, witness_org) in info.witnesses.iter() {
+            if let Some(outcome) = info.statements.get(witness) {
+                if j < outcome.len() {
+                    let w = weight(witness.as_str(), witness_org.as_str());
+                    total_weight += w;
+                    if outcome[j] {
+                        honest_weight += w;
+                    }
+                }
+            }
+        }
+
+        // if no trusted witness made a statement, there is nothing to judge by
+        let judgement = match first_hand.get(participant) {
+            Some(honest) => Some(*honest),
+            None if total_weight > 0.0 => Some(honest_weight / total_weight >= 0.5),
+            None => None
+        };
+        if let Some(honest) = judgement {
+            tn_verdicts.push((participant.clone(), if honest {1.0} else {0.0}));
+        }
+        judgements.push(judgement);
+    }
+
+    // witnesses are judged against the outcome of the vote
+    let mut wn_verdicts: Verdicts = Vec::new();
+    for (witness, _) in info.witnesses.iter() {
+        if info.equivocators.contains(witness) {
+            wn_verdicts.push((witness.clone(), EQUIVOCATION_VERDICT));
+            continue;
+        }
+        match info.statements.get(witness) {
+            Some(outcome) => {
+                let agreed = judgements
+                    .iter()
+                    .zip(outcome.iter())
+                    .all(|(judgement, stated)| match judgement {
+                        Some(honest) => honest == stated,
+                        None         => true
+                    });
+                wn_verdicts.push((witness.clone(), if agreed {1.0} else {0.0}));
+            },
+            None => wn_verdicts.push((witness.clone(), ABSENT_WITNESS_VERDICT))
+        };
+    }
+
+    return (tn_verdicts, wn_verdicts);
+}
+
+#[test]
+pub fn test_weighted_vote() {
+    // two witnesses of org0 say the participant was dishonest, one of org1 says honest
+    let info = InteractionInfo {
+        participants: vec![String::from("p")],
+        witnesses: vec![
+            (String::from("w0"), String::from("org0")),
+            (String::from("w1"), String::from("org0")),
+            (String::from("w2"), String::from("org1")),
+        ],
+        statements: vec![
+            (String::from("w0"), vec![false]),
+            (String::from("w1"), vec![false]),
+            (String::from("w2"), vec![true]),
+        ].into_iter().collect(),
+        equivocators: Vec::new(),
+        breached: false
+    };
+
+    let (tn, wn) = weighted_vote(&info, |_, _| 1.0, &HashMap::new());
+    assert_eq!(tn, vec![(String::from("p"), 0.0)]);
+    assert_eq!(wn.iter().filter(|(_, v)| *v == 1.0).count(), 2);
+
+    // a user of org1 who hardly trusts org0
+    let (tn, _) = weighted_vote(&info, |_, org| if org == "org1" { 0.9 } else { 0.1 }, &HashMap::new());
+    assert_eq!(tn, vec![(String::from("p"), 1.0)]);
+
+    // a counterparty who saw the participant behave honestly catches the lying witnesses
+    let first_hand: HashMap<String, bool> = vec![(String::from("p"), true)].into_iter().collect();
+    let (tn, wn) = weighted_vote(&info, |_, _| 1.0, &first_hand);
+    assert_eq!(tn, vec![(String::from("p"), 1.0)]);
+    assert_eq!(wn, vec![
+        (String::from("w0"), 0.0),
+        (String::from("w1"), 0.0),
+        (String::from("w2"), 1.0),
+    ]);
+}
+
+#[test]
+pub fn test_incomplete_interactions() {
+    // the second witness went offline before making a statement
+    let info = InteractionInfo {
+        participants: vec![String::from("p0"), String::from("p1")],
+        witnesses: vec![
+            (String::from("w0"), String::from("org0")),
+            (String::from("w1"), String::from("org0")),
+        ],
+        statements: vec![
+            (String::from("w0"), vec![true, false]),
+        ].into_iter().collect(),
+        equivocators: Vec::new(),
+        breached: false
+    };
+    assert!(!info.is_complete());
+
+    // the participants are judged by the statements there are, and the absent witness
+    // is penalised
+    let (tn, wn) = weighted_vote(&info, |_, _| 1.0, &HashMap::new());
+    assert_eq!(tn, vec![(String::from("p0"), 1.0), (String::from("p1"), 0.0)]);
+    assert_eq!(wn, vec![(String::from("w0"), 1.0), (String::from("w1"), ABSENT_WITNESS_VERDICT)]);
+
+    // an equivocating witness is penalised, even when one of their statements was right
+    let info = InteractionInfo {
+        equivocators: vec![String::from("w1")],
+        breached: true,
+        ..info
+    };
+    let (_, wn) = weighted_vote(&info, |_, _| 1.0, &HashMap::new());
+    assert_eq!(wn[1], (String::from("w1"), EQUIVOCATION_VERDICT));
+
+    // an interaction whose InteractionMsg never reached the channel gives no verdicts
+    let ctx = TsgContext {
+        user_pubkey: "p0",
+        org_pubkey: "org0",
+        default_reputation: 0.5,
+        reputations: None,
+        org_trust: &None,
+        first_hand: &HashMap::new(),
+        arrivals: &Vec::new()
+    };
+    for config in vec![TsgConfig::Organization, TsgConfig::MajorityVote, TsgConfig::FirstHandAware] {
+        assert_eq!(new_tsg(&config).verdicts(&Vec::new(), &ctx), (Vec::new(), Vec::new()));
+    }
+}
@@ -0,0 +1,353 @@
+use crate::witness_rep::{
+    iota_did::{
+        create_and_upload_did::gen_iota_keypair,
+        did_registry::DidResolver
+    },
+    implementation::{
+        generate_sigs,
+        user_and_organization::{UserIdentity, OrganizationIdentity, IdInfo, UserState},
+        reputation_model::{
+            ReputationModelConfig, new_model, get_model_config,
+            update_reputations, reputation_scores
+        },
+        user_arena::Interner
+    },
+    utility::{
+        read_msgs::ChannelReader,
+        verify_interaction::{self, WhichBranch},
+        batch_verify::SignatureVerifier
+    },
+    trust_score_generators::{
+        cross_org_tsg::OrgTrustMatrix,
+        user_tsg::{TsgConfig, TsgContext, new_tsg, uses_first_hand}
+    }
+};
+
+use wb_reputation_system::{
+    utility::parse_messages,
+    data_types::{
+        event_protocol_messages::signatures::organization_cert::OrganizationCertificate,
+        identity::identity::ReputationMap
+    }
+};
+
+use iota_streams::{
+    app::transport::tangle::client::Client,
+    app_channels::api::tangle::Subscriber,
+    core::Result
+};
+
+use std::collections::{BTreeMap, HashMap};
+
+/// Reputations which differ by less than this are considered equal
+pub const DRIFT_TOLERANCE: f32 = 0.0001;
+
+/// A discrepancy between the reputation map a holder keeps and the one
+/// recomputed from the ledger by the auditor
+#[derive(Clone, Debug)]
+pub enum AuditFinding {
+    /// The holder never included a user the ledger says they should have
+    Missing { holder: String, subject: String, expected: f32 },
+    /// The holder has a user the ledger has no verified interactions for
+    Unexpected { holder: String, subject: String, held: f32 },
+    /// Both have the user, but the reputations differ
+    Drift { holder: String, subject: String, held: f32, expected: f32 },
+}
+
+/// The outcome of replaying the channels from the perspective of one TSG and reputation model
+pub struct ChannelAudit {
+    pub org_pubkey: String,
+    pub default_reputation: f32,
+    pub reputation_model: ReputationModelConfig,
+    pub tsg: TsgConfig,
+    pub interactions: usize,
+    pub unverified_runs: Vec<usize>,
+    pub observer: UserIdentity,
+}
+
+pub struct AuditReport {
+    pub audits: Vec<ChannelAudit>,
+    pub findings: Vec<AuditFinding>,
+    pub holders_checked: usize,
+}
+
+// Reads every interaction on the channels, and when each of their messages reached the
+// channel. Runs are unique across channels, so the branches of every channel are merged
+// into a single ordered history. The readers keep their place in the channels, so only
+// the messages published since they last read are fetched.
+pub async fn read_history(
+    node_url: &str,
+    ann_msgs: &Vec<String>,
+    readers: &mut HashMap<String, ChannelReader>
+) -> Result<(BTreeMap<usize, Vec<(String, String)>>, BTreeMap<usize, Vec<u32>>)> {
+    let mut history: BTreeMap<usize, Vec<(String, String)>> = BTreeMap::new();
+    let mut arrivals: BTreeMap<usize, Vec<u32>> = BTreeMap::new();
+    let every_branch = WhichBranch::FromBranch(0);
+    for ann_msg in ann_msgs.iter() {
+        let reader = ChannelReader::open(node_url, ann_msg, readers).await?;
+        reader.fetch().await?;
+        let runs = reader.runs(&every_branch);
+        history.extend(runs.iter().cloned().zip(reader.branches(&every_branch)));
+        arrivals.extend(runs.iter().cloned().zip(reader.arrivals(&every_branch)));
+    }
+    return Ok((history, arrivals));
+}
+
+// Verifies each interaction of the history and replays the TSG in the order the
+// interactions took place. The result is the reputation map an observer, who uses
+// the TSG of the given organization, default reputation and reputation model, would
+// compute from scratch.
+pub fn audit_history(
+    node_url: &str,
+    history: &BTreeMap<usize, Vec<(String, String)>>,
+    arrivals: &BTreeMap<usize, Vec<u32>>,
+    org_pubkey: &str,
+    default_reputation: f32,
+    reputation_model: &ReputationModelConfig,
+    tsg: &TsgConfig,
+    org_trust: &Option<OrgTrustMatrix>,
+    resolver: Option<&dyn DidResolver>
+) -> Result<ChannelAudit> {
+    let mut observer = new_observer(node_url, org_pubkey, reputation_model, tsg, default_reputation)?;
+    let generator = new_tsg(tsg);
+    let no_first_hand: HashMap<String, bool> = HashMap::new();
+    let no_arrivals: Vec<u32> = Vec::new();
+    let application = String::from("ExchangeApplication");
+    let mut verifier = SignatureVerifier::new();
+    let mut unverified_runs: Vec<usize> = Vec::new();
+    for (run, branch_msgs) in history.iter() {
+        let branch_arrivals = arrivals.get(run).unwrap_or(&no_arrivals);
+        // the signatures of past interactions will have timed out since, but the statements
+        // are still judged by when they reached the channel
+        if !verify_interaction::verify_branch(branch_msgs, branch_arrivals, application.clone(), false, resolver, &mut verifier)? {
+            unverified_runs.push(*run);
+            continue;
+        }
+
+        let parsed_msgs = parse_messages::parse_messages(branch_msgs)?;
+        let reputations = match tsg {
+            TsgConfig::ReputationWeighted => Some(reputation_scores(&observer)),
+            _ => None
+        };
+        let ctx = TsgContext {
+            user_pubkey: &observer.id_info.org_cert.client_pubkey,
+            org_pubkey: org_pubkey,
+            default_reputation: default_reputation,
+            reputations: reputations,
+            org_trust: org_trust,
+            first_hand: &no_first_hand,
+            arrivals: branch_arrivals
+        };
+        let (tn_verdicts, wn_verdicts) = generator.verdicts(&parsed_msgs, &ctx);
+        update_reputations(&mut observer, tn_verdicts, wn_verdicts);
+    }
+
+    return Ok(ChannelAudit {
+        org_pubkey: String::from(org_pubkey),
+        default_reputation: default_reputation,
+        reputation_model: reputation_model.clone(),
+        tsg: tsg.clone(),
+        interactions: history.len(),
+        unverified_runs: unverified_runs,
+        observer: observer
+    });
+}
+
+// Audits the channels of all organizations once per perspective (organization, default
+// reputation, reputation model and TSG) and compares the recomputed reputation maps against those held
+// by the participants and the organizations.
+pub async fn audit_simulation(
+    node_url: &str,
+    participants: &Vec<UserIdentity>,
+    organizations: &Vec<OrganizationIdentity>,
+    org_trust: &Option<OrgTrustMatrix>,
+    readers: &mut HashMap<String, ChannelReader>,
+    resolver: Option<&dyn DidResolver>
+) -> Result<AuditReport> {
+    let ann_msgs: Vec<String> = organizations
+        .iter()
+        .filter_map(|org| org.ann_msg.clone())
+        .collect();
+    let (history, arrivals) = read_history(node_url, &ann_msgs, readers).await?;
+
+    // every holder, with the perspective their reputation map was built from
+    let mut holders: Vec<(String, String, f32, ReputationModelConfig, TsgConfig, HashMap<String, f32>)> = organizations
+        .iter()
+        .map(|org| (
+            org.identity.id_info.org_cert.client_pubkey.clone(),
+            org.identity.id_info.org_cert.org_pubkey.clone(),
+            org.identity.user_default_reputation,
+            get_model_config(&org.identity.id_info),
+            org.identity.id_info.state.tsg.clone(),
+            reputation_scores(&org.identity)
+        ))
+        .collect();
+    for part in participants.iter() {
+        // what a user knew first hand is not on the ledger, so their map cannot be recomputed
+        if uses_first_hand(&part.id_info.state.tsg) {
+            continue;
+        }
+        holders.push((
+            part.id_info.org_cert.client_pubkey.clone(),
+            part.id_info.org_cert.org_pubkey.clone(),
+            part.user_default_reputation,
+            get_model_config(&part.id_info),
+            part.id_info.state.tsg.clone(),
+            reputation_scores(part)
+        ));
+    }
+
+    let mut audits: Vec<ChannelAudit> = Vec::new();
+    let mut findings: Vec<AuditFinding> = Vec::new();
+    for (holder, org_pubkey, default_reputation, model, tsg, held) in holders.iter() {
+        // holders sharing a perspective share the audit
+        let existing = audits
+            .iter()
+            .position(|a| {
+                &a.org_pubkey == org_pubkey
+                    && a.default_reputation == *default_reputation
+                    && &a.reputation_model == model
+                    && &a.tsg == tsg
+            });
+        let audit_index = match existing {
+            Some(index) => index,
+            None => {
+                audits.push(audit_history(
+                    node_url, &history, &arrivals, org_pubkey, *default_reputation, model, tsg, org_trust, resolver
+                )?);
+                audits.len() - 1
+            }
+        };
+
+        let expected = reputation_scores(&audits[audit_index].observer);
+        findings.append(&mut compare_reputations(holder, held, &expected));
+    }
+
+    return Ok(AuditReport {
+        audits: audits,
+        findings: findings,
+        holders_checked: holders.len()
+    });
+}
+
+pub fn compare_reputations(
+    holder: &str,
+    held: &HashMap<String, f32>,
+    expected: &HashMap<String, f32>
+) -> Vec<AuditFinding> {
+    let mut findings: Vec<AuditFinding> = Vec::new();
+    for (subject, exp) in expected.iter() {
+        match held.get(subject) {
+            None => findings.push(AuditFinding::Missing {
+                holder: String::from(holder),
+                subject: subject.clone(),
+                expected: *exp
+            }),
+            Some(h) => {
+                if (h - exp).abs() > DRIFT_TOLERANCE {
+                    findings.push(AuditFinding::Drift {
+                        holder: String::from(holder),
+                        subject: subject.clone(),
+                        held: *h,
+                        expected: *exp
+                    });
+                }
+            }
+        }
+    }
+    for (subject, h) in held.iter() {
+        if !expected.contains_key(subject) {
+            findings.push(AuditFinding::Unexpected {
+                holder: String::from(holder),
+                subject: subject.clone(),
+                held: *h
+            });
+        }
+    }
+    return findings;
+}
+
+pub fn audit_report_string(report: &AuditReport) -> String {
+    let mut output: String = String::new();
+    for audit in report.audits.iter() {
+        let info = format!(
+            "Perspective of organization {} (default reputation {}, {:?} model, {:?} TSG)\n-- Interactions read: {}\n-- Unverified runs: {:?}\n\n",
+            audit.org_pubkey, audit.default_reputation, audit.reputation_model, audit.tsg,
+            audit.interactions, audit.unverified_runs
+        );
+        output.push_str(&info);
+    }
+
+    let summary = format!(
+        "Holders checked: {}\nFindings: {}\n\n",
+        report.holders_checked, report.findings.len()
+    );
+    output.push_str(&summary);
+    for finding in report.findings.iter() {
+        output.push_str(&format!("{:?}\n", finding));
+    }
+    return output;
+}
+
+// The auditor is not a member of the organization, so its certificate is unsigned
+// and only records the organization whose perspective it audits from. It keeps
+// reputations apart from the simulation's, so its model has an interner of its own.
+fn new_observer(
+    node_url: &str,
+    org_pubkey: &str,
+    reputation_model: &ReputationModelConfig,
+    tsg: &TsgConfig,
+    default_reputation: f32
+) -> Result<UserIdentity> {
+    let client = Client::new_from_url(node_url);
+    let (kp, (_, sec)) = gen_iota_keypair();
+    let reputation_map: ReputationMap = HashMap::new();
+
+    let observer = UserIdentity {
+        channel_client: Subscriber::new("auditor", client),
+        id_info: IdInfo {
+            seed: Some(String::from("auditor")),
+            did_key: sec,
+            reliability: None,
+            org_cert: OrganizationCertificate {
+                client_pubkey: generate_sigs::get_multibase(&kp),
+                timeout: 0,
+                org_pubkey: String::from(org_pubkey),
+                signature: Vec::new()
+            },
+            state: UserState {
+                reputation_model: new_model(reputation_model, default_reputation, &Interner::new()),
+                tsg: tsg.clone(),
+                ..Default::default()
+            }
+        },
+        reputation_map: reputation_map,
+        user_reputation_threshold: 0.0,
+        user_default_reputation: 0.0
+    };
+    return Ok(observer);
+}
+
+#[test]
+pub fn test_compare_reputations() {
+    let held: HashMap<String, f32> = vec![
+        (String::from("a"), 0.5),
+        (String::from("b"), 0.9),
+        (String::from("c"), 0.1),
+    ].into_iter().collect();
+    let expected: HashMap<String, f32> = vec![
+        (String::from("a"), 0.5),
+        (String::from("b"), 0.7),
+        (String::from("d"), 1.0),
+    ].into_iter().collect();
+
+    let findings = compare_reputations("holder", &held, &expected);
+    assert_eq!(findings.len(), 3);
+    for finding in findings {
+        match finding {
+            AuditFinding::Missing { subject, .. }       => assert_eq!(subject, "d"),
+            AuditFinding::Unexpected { subject, .. }    => assert_eq!(subject, "c"),
+            AuditFinding::Drift { subject, .. }         => assert_eq!(subject, "b"),
+        }
+    }
+}
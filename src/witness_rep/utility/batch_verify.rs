@@ -0,0 +1,192 @@
+use ed25519_zebra::{batch, Signature, VerificationKey, VerificationKeyBytes};
+use sha2::{Digest, Sha256};
+
+use std::collections::{HashMap, VecDeque};
+use std::convert::TryFrom;
+
+/// A signature waiting to be verified, with the key and the message it is verified against
+#[derive(Clone, Debug)]
+pub struct PendingSignature {
+    pub key: Vec<u8>,
+    pub message: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+/// How many signatures were checked against their keys, and how many the cache spared
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VerificationStats {
+    pub verified: usize,
+    pub cached: usize,
+    pub batches: usize,
+}
+
+/// How many signatures a verifier remembers unless told otherwise, enough for the channels
+/// of a simulation to be re-read and audited without verifying them again
+pub const DEFAULT_CACHE_CAPACITY: usize = 100_000;
+
+/// Verifies signatures in batches, remembering those already found valid. The same
+/// statements are verified again whenever a channel is re-read or audited, so whoever
+/// reads channels keeps a verifier for as long as they read them. Ed25519 signatures are
+/// deterministic, so a signer has a single valid signature for a message, which is kept
+/// to compare against. Once the cache is full, the signatures verified first are forgotten.
+pub struct SignatureVerifier {
+    /// The valid signatures, by signer key and hash of the signed message
+    verified: HashMap<(Vec<u8>, [u8; 32]), Vec<u8>>,
+    /// The keys of the cache, in the order they were added
+    order: VecDeque<(Vec<u8>, [u8; 32])>,
+    capacity: usize,
+    pub stats: VerificationStats,
+}
+
+fn message_hash(message: &[u8]) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&Sha256::digest(message));
+    return hash;
+}
+
+impl SignatureVerifier {
+    pub fn new() -> SignatureVerifier {
+        return SignatureVerifier::with_capacity(DEFAULT_CACHE_CAPACITY);
+    }
+
+    pub fn with_capacity(capacity: usize) -> SignatureVerifier {
+        return SignatureVerifier {
+            verified: HashMap::new(),
+            order: VecDeque::new(),
+            capacity: capacity,
+            stats: VerificationStats::default()
+        };
+    }
+
+    fn is_cached(&self, sig: &PendingSignature) -> bool {
+        return self.verified
+            .get(&(sig.key.clone(), message_hash(&sig.message)))
+            .map_or(false, |signature| signature == &sig.signature);
+    }
+
+    fn cache(&mut self, sigs: &Vec<&PendingSignature>) {
+        for sig in sigs.iter() {
+            let key = (sig.key.clone(), message_hash(&sig.message));
+            if self.verified.insert(key.clone(), sig.signature.clone()).is_none() {
+                self.order.push_back(key);
+            }
+        }
+        while self.verified.len() > self.capacity {
+            match self.order.pop_front() {
+                Some(oldest) => self.verified.remove(&oldest),
+                None => break
+            };
+        }
+    }
+
+    // The number of signatures remembered
+    pub fn cached(&self) -> usize {
+        return self.verified.len();
+    }
+
+    // Forgets the signatures verified so far, e.g. to time verification from a cold start
+    pub fn clear_cache(&mut self) {
+        self.verified.clear();
+        self.order.clear();
+    }
+
+    // Verifies the signatures together, returning whether each is valid. The signatures the
+    // cache holds are skipped, and the others are checked in a single batch, which is much
+    // cheaper than checking them one by one. Only if the batch fails are they checked one by
+    // one (see verify_one), to find out which are invalid.
+    pub fn verify_batch(&mut self, sigs: &Vec<PendingSignature>) -> Vec<bool> {
+        let mut valid: Vec<bool> = sigs.iter().map(|sig| self.is_cached(sig)).collect();
+        let pending: Vec<usize> = (0..sigs.len()).filter(|i| !valid[*i]).collect();
+        self.stats.verified += pending.len();
+        self.stats.cached += sigs.len() - pending.len();
+        self.stats.batches += 1;
+        if pending.len() == 0 {
+            return valid;
+        }
+
+        let mut verifier = batch::Verifier::new();
+        let mut malformed = false;
+        for i in pending.iter() {
+            let sig = &sigs[*i];
+            match parse_signature(sig) {
+                Some((key, signature)) => verifier.queue((key, signature, &sig.message)),
+                None => malformed = true
+            }
+        }
+
+        let batch_valid = !malformed && verifier.verify(rand::thread_rng()).is_ok();
+        for i in pending.iter() {
+            valid[*i] = batch_valid || verify_one(&sigs[*i]);
+        }
+        let newly_valid: Vec<&PendingSignature> = pending.iter().filter(|i| valid[**i]).map(|i| &sigs[*i]).collect();
+        self.cache(&newly_valid);
+        return valid;
+    }
+}
+
+// The key and signature as ed25519-zebra reads them, if they have the right lengths
+fn parse_signature(sig: &PendingSignature) -> Option<(VerificationKeyBytes, Signature)> {
+    let key = <[u8; 32]>::try_from(sig.key.as_slice()).ok()?;
+    let signature = <[u8; 64]>::try_from(sig.signature.as_slice()).ok()?;
+    return Some((VerificationKeyBytes::from(key), Signature::from(signature)));
+}
+
+// Verifies a single signature. It follows the same (ZIP-215) rules as the batch, so a
+// signature is valid or not regardless of the signatures it is verified with.
+pub fn verify_one(sig: &PendingSignature) -> bool {
+    return match parse_signature(sig) {
+        Some((key, signature)) => VerificationKey::try_from(key)
+            .and_then(|key| key.verify(&signature, &sig.message))
+            .is_ok(),
+        None => false
+    };
+}
+
+#[cfg(test)]
+fn signed(message: &str) -> PendingSignature {
+    use identity::crypto::{Ed25519, KeyPair, Sign};
+    let keypair = KeyPair::new_ed25519().unwrap();
+    let signature: [u8; 64] = Ed25519::sign(message.as_bytes(), keypair.private()).unwrap();
+    return PendingSignature {
+        key: keypair.public().as_ref().to_vec(),
+        message: message.as_bytes().to_vec(),
+        signature: signature.to_vec()
+    };
+}
+
+#[test]
+pub fn test_verify_batch() {
+    let mut verifier = SignatureVerifier::new();
+    let sigs: Vec<PendingSignature> = (0..5).map(|i| signed(&format!("test_verify_batch {}", i))).collect();
+    assert_eq!(verifier.verify_batch(&sigs), vec![true; 5]);
+    assert!(sigs.iter().all(verify_one));
+    assert_eq!(verifier.stats.verified, 5);
+
+    // forged signatures fail the batch, and only they are found invalid
+    let mut forged = sigs.clone();
+    forged[2].message = b"test_verify_batch forged".to_vec();
+    forged[3].signature = vec![0; 64];
+    assert_eq!(verifier.verify_batch(&forged), vec![true, true, false, false, true]);
+
+    // verified one by one, they are found invalid too
+    assert!(!verify_one(&forged[2]) && !verify_one(&forged[3]));
+
+    // a cached signature only counts for the message and signature it was verified with,
+    // and is not verified again
+    let stats = verifier.stats.clone();
+    assert_eq!(verifier.verify_batch(&sigs[..1].to_vec()), vec![true]);
+    assert_eq!(verifier.stats.cached, stats.cached + 1);
+    assert_eq!(verifier.stats.verified, stats.verified);
+    let mut truncated = sigs[0].clone();
+    truncated.signature.truncate(10);
+    assert_eq!(verifier.verify_batch(&vec![truncated]), vec![false]);
+
+    // a full cache forgets the signatures it verified first
+    let mut bounded = SignatureVerifier::with_capacity(3);
+    bounded.verify_batch(&sigs);
+    assert_eq!(bounded.cached(), 3);
+    bounded.verify_batch(&sigs[..1].to_vec());
+    assert_eq!(bounded.stats.cached, 0);
+    assert_eq!(bounded.verify_batch(&sigs[4..].to_vec()), vec![true]);
+    assert_eq!(bounded.stats.cached, 1);
+}
@@ -1,4 +1,7 @@
-use crate::witness_rep::utility::verify_interaction::WhichBranch;
+use crate::witness_rep::{
+    utility::verify_interaction::WhichBranch,
+    implementation::organization_policy::{PolicyDecision, PolicyMessage},
+};
 
 use iota_streams::app_channels::api::tangle::{
     MessageContent, UnwrappedMessage
@@ -7,47 +10,51 @@ use identity::{
     did::MethodData
 };
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 
-// Ectracts all message payloads and pubkeys from each branch. start_branch
-// starts at 0.
+// Extracts all message payloads and pubkeys from the selected branches. Each branch
+// holds the messages of a single interaction and is identified by the run it was sent
+// in (see workaround_channel_bug). The branches are returned ordered by run.
 pub fn extract_msg(
     retrieved_msgs: Vec<UnwrappedMessage>,
     branches: WhichBranch
-) -> Vec<(String, String)> {
-    /* retrieved_msgs
-        .iter()
-        .for_each(|msg| {
-            let content = &msg.body;
-            match content {
-                MessageContent::SignedPacket {
-                    pk,
-                    public_payload,
-                    masked_payload: _,
-                } => {
-                    let pay = String::from_utf8(public_payload.0.to_vec()).unwrap();
-                    let pubk = MethodData::new_multibase(pk);
-                    if let MethodData::PublicKeyMultibase(mbpub) = pubk {
-                        messages.last_mut().unwrap().push((pay, mbpub));
-                    } else {
-                        panic!("Failed to decode public key")
-                    }
-                },
-                MessageContent::Keyload  => {
-                    // create a new vector for each keyload
-                    let new_vec: Vec<(String, String)> = Vec::from([]);
-                    messages.push(new_vec);
-                }
-                _ => {
-                    panic!("Simulation only built to handle signed and keyload messages");
-                }
-            }
-        }); */
-    let mut last_branch = 0;
-    let mut messages: HashMap<usize, Vec<(String, String)>> = HashMap::new();
-    let new_vec: Vec<(String, String)> = Vec::from([]);
-    
+) -> Vec<Vec<(String, String)>> {
+    let (messages, _) = extract_branches(retrieved_msgs);
+    return select_branches(messages, &branches);
+}
+
+// The selected entries of a map by run, like the branches extract_branches returns or
+// their arrivals, ordered by run
+pub fn select_branches<T>(mut by_run: BTreeMap<usize, T>, branches: &WhichBranch) -> Vec<T> {
+    return match branches {
+        WhichBranch::OneBranch(b)  => by_run.remove(b).into_iter().collect(),
+        WhichBranch::FromBranch(b) => by_run.split_off(b).into_iter().map(|(_, branch)| branch).collect(),
+        WhichBranch::LastBranch    => by_run.into_iter().next_back().map(|(_, branch)| branch).into_iter().collect(),
+    };
+}
+
+// Extracts every branch of the channel, keyed by the run of the interaction, and when
+// each of their messages reached the channel. Runs are unique across channels, so the
+// branches of several channels can be merged into a single history.
+pub fn extract_branches(
+    retrieved_msgs: Vec<UnwrappedMessage>
+) -> (BTreeMap<usize, Vec<(String, String)>>, BTreeMap<usize, Vec<u32>>) {
+    // BTreeMap because it is ordered by run
+    let mut messages: BTreeMap<usize, Vec<(String, String)>> = BTreeMap::new();
+    let mut arrivals: BTreeMap<usize, Vec<u32>> = BTreeMap::new();
+    extend_branches(&mut messages, &mut arrivals, retrieved_msgs);
+    return (messages, arrivals);
+}
+
+// Adds messages fetched after those already in the branches, as if all of them had been
+// extracted at once. Returns the runs whose branches were started or added to.
+pub fn extend_branches(
+    messages: &mut BTreeMap<usize, Vec<(String, String)>>,
+    arrivals: &mut BTreeMap<usize, Vec<u32>>,
+    retrieved_msgs: Vec<UnwrappedMessage>
+) -> BTreeSet<usize> {
+    let mut changed: BTreeSet<usize> = BTreeSet::new();
     retrieved_msgs
         .iter()
         .for_each(|msg| {
@@ -58,27 +65,34 @@ pub fn extract_msg(
                     public_payload,
                     masked_payload: _,
                 } => {
-                    // decode the message and check if it's a tx_msg
-                    let mut pay = String::from_utf8(public_payload.0.to_vec()).unwrap();
-                    let run = &pay[0..1].to_string();
-                    let run_i = run.parse::<usize>().unwrap();
+                    // decode the message and check if it's a tx_msg. Payloads which were not
+                    // published by the ledger are not part of any interaction, so they are ignored
+                    let (run_i, arrival, pay) = match decode_payload(&public_payload.0) {
+                        Some(decoded) => decoded,
+                        None          => return
+                    };
 
-                    if run_i > last_branch {
-                        last_branch = run_i;
+                    // the organization's policy decisions are not part of the interaction
+                    if pay.starts_with("{\"PolicyDecision") {
+                        return;
                     }
 
-                    pay = (&pay[1..]).to_string();
-
-                    let substr = &pay[0..16];
-                    if substr == "{\"InteractionMsg" {
-                        messages.insert(run_i, new_vec.clone());
+                    // an InteractionMsg starts a new branch
+                    if pay.starts_with("{\"InteractionMsg") {
+                        messages.insert(run_i, Vec::new());
+                        arrivals.insert(run_i, Vec::new());
                     }
 
                     let pubk = MethodData::new_multibase(pk);
                     if let MethodData::PublicKeyMultibase(mbpub) = pubk {
-                        let mut branch = messages.remove(&run_i).unwrap();
-                        branch.push((pay, mbpub));
-                        messages.insert(run_i, branch);
+                        // messages which precede their InteractionMsg are not part of
+                        // any interaction, so they are ignored
+                        if let Some(branch) = messages.get_mut(&run_i) {
+                            branch.push((pay, mbpub));
+                            // packets published without their arrival count as in time
+                            arrivals.entry(run_i).or_default().push(arrival.unwrap_or(0));
+                            changed.insert(run_i);
+                        }
                     } else {
                         panic!("Failed to decode public key")
                     }
@@ -87,10 +101,77 @@ pub fn extract_msg(
             }
         });
     
-    // TODO OneBranch and FromBranch
-    return match branches {
-        WhichBranch::OneBranch(b)  => messages.remove(&last_branch).unwrap(),
-        WhichBranch::FromBranch(b) => messages.remove(&last_branch).unwrap(),
-        WhichBranch::LastBranch  => messages.remove(&last_branch).unwrap(),
+    return changed;
+}
+
+// Extracts the policy decisions the organization published on its channel
+pub fn extract_policy_decisions(
+    retrieved_msgs: Vec<UnwrappedMessage>
+) -> Vec<PolicyDecision> {
+    let mut decisions: Vec<PolicyDecision> = Vec::new();
+    for msg in retrieved_msgs.iter() {
+        if let MessageContent::SignedPacket { pk: _, public_payload, masked_payload: _ } = &msg.body {
+            let pay = match decode_payload(&public_payload.0) {
+                Some((_, _, pay)) => pay,
+                None              => continue
+            };
+            if let Ok(PolicyMessage::PolicyDecision(decision)) = serde_json::from_str(&pay) {
+                decisions.push(decision);
+            }
+        }
+    }
+    return decisions;
+}
+
+// Decodes a packet's payload and separates its prefix (see split_prefix).
+// Gives None for payloads which are not utf8 or lack the prefix.
+pub fn decode_payload(payload: &[u8]) -> Option<(usize, Option<u32>, String)> {
+    let pay = String::from_utf8(payload.to_vec()).ok()?;
+    return split_prefix(&pay);
+}
+
+// Separates the run number prepended by workaround_channel_bug from the message.
+// The run can be any number of digits, so we read up until the start of the json.
+pub fn split_run_prefix(payload: &str) -> Option<(usize, String)> {
+    let (run, _, msg) = split_prefix(payload)?;
+    return Some((run, msg));
+}
+
+// Like split_run_prefix, but also separates the time the packet reached the channel,
+// which the ledger stamps the packets of an interaction with (see stamp_arrival).
+// Packets without one, like the organization's policy decisions, give None.
+// Gives None altogether for payloads without a valid prefix, like those
+// published on the channel by anyone other than the ledger.
+pub fn split_prefix(payload: &str) -> Option<(usize, Option<u32>, String)> {
+    let json_start = payload.find('{')?;
+    let (run, arrival) = match payload[0..json_start].split_once('@') {
+        Some((run, arrival)) => (run, Some(arrival.parse::<u32>().ok()?)),
+        None                 => (&payload[0..json_start], None)
     };
-}
\ No newline at end of file
+    let run = run.parse::<usize>().ok()?;
+    return Some((run, arrival, payload[json_start..].to_string()));
+}
+
+#[test]
+pub fn test_split_run_prefix() {
+    let (run, msg) = split_run_prefix("7{\"WitnessStatement\":{}}").unwrap();
+    assert_eq!(run, 7);
+    assert_eq!(msg, "{\"WitnessStatement\":{}}");
+
+    // runs past 9 used to be cut down to their first digit
+    let (run, msg) = split_run_prefix("123{\"InteractionMsg\":{}}").unwrap();
+    assert_eq!(run, 123);
+    assert_eq!(msg, "{\"InteractionMsg\":{}}");
+
+    // the ledger stamps the packets of an interaction with their arrival
+    let (run, arrival, msg) = split_prefix("12@1643573000{\"WitnessStatement\":{}}").unwrap();
+    assert_eq!((run, arrival), (12, Some(1643573000)));
+    assert_eq!(msg, "{\"WitnessStatement\":{}}");
+    assert_eq!(split_run_prefix("12@1643573000{}"), Some((12, String::from("{}"))));
+
+    // foreign or malformed payloads are skipped rather than crashing the reader
+    assert_eq!(split_prefix("{\"WitnessStatement\":{}}"), None);
+    assert_eq!(split_prefix("12@late{}"), None);
+    assert_eq!(split_prefix("no json"), None);
+    assert_eq!(decode_payload(&[0xff, 0xfe]), None);
+}
@@ -1,3 +1,6 @@
 pub mod extract_msgs;
 pub mod verify_interaction;
-pub mod read_msgs;
\ No newline at end of file
+pub mod read_msgs;
+pub mod audit;
+pub mod batch_verify;
+pub mod score_lines;
@@ -1,3 +1,13 @@
+use crate::witness_rep::utility::{
+    extract_msgs,
+    verify_interaction::WhichBranch
+};
+
+use wb_reputation_system::{
+    utility::parse_messages,
+    data_types::tsg_data_types::message::MessageAndPubkey
+};
+
 use iota_streams::{
     app_channels::api::tangle::UnwrappedMessage,
     app::transport::tangle::client::Client,
@@ -8,6 +18,7 @@ use iota_streams::{
 };
 
 use core::str::FromStr;
+use std::collections::{BTreeMap, HashMap};
 
 pub async fn read_msgs(
     node_url: &str, 
@@ -24,4 +35,113 @@ pub async fn read_msgs(
 
     // fetch messages from address, and extract their payloads
     return reader.fetch_next_msgs().await;
-}
\ No newline at end of file
+}
+
+/// A reader that stays attached to an organization's channel. The reader subscriber keeps
+/// its place in the channel, so each fetch only gets the messages published since the last,
+/// which are added to the branches read so far. Interactions are parsed once, when first asked for.
+pub struct ChannelReader {
+    reader: Subscriber<Client>,
+    /// Every branch of the channel read so far, by run (see extract_msgs::extract_branches)
+    branches: BTreeMap<usize, Vec<(String, String)>>,
+    /// When each message of the branches reached the channel, by run
+    arrivals: BTreeMap<usize, Vec<u32>>,
+    /// The interactions parsed so far, by run. Dropped when their branch grows.
+    parsed: HashMap<usize, Vec<MessageAndPubkey>>,
+    /// The number of messages fetched from the channel so far
+    pub fetched: usize,
+}
+
+impl ChannelReader {
+    pub async fn new(node_url: &str, ann_msg: &str) -> Result<ChannelReader> {
+        let client = Client::new_from_url(node_url);
+        let mut reader = Subscriber::new("reader", client);
+        let ann_address = Address::from_str(ann_msg)?;
+        reader.receive_announcement(&ann_address).await?;
+        return Ok(ChannelReader {
+            reader: reader,
+            branches: BTreeMap::new(),
+            arrivals: BTreeMap::new(),
+            parsed: HashMap::new(),
+            fetched: 0
+        });
+    }
+
+    // The reader of the channel among those kept by announcement, which is attached to
+    // the channel the first time it is read
+    pub async fn open<'a>(
+        node_url: &str,
+        ann_msg: &str,
+        readers: &'a mut HashMap<String, ChannelReader>
+    ) -> Result<&'a mut ChannelReader> {
+        if !readers.contains_key(ann_msg) {
+            let reader = ChannelReader::new(node_url, ann_msg).await?;
+            readers.insert(String::from(ann_msg), reader);
+        }
+        return Ok(readers.get_mut(ann_msg).unwrap());
+    }
+
+    // Fetches the messages published since the last fetch. Returns how many there were.
+    pub async fn fetch(&mut self) -> Result<usize> {
+        let new_msgs = self.reader.fetch_next_msgs().await?;
+        let count = new_msgs.len();
+        self.fetched += count;
+        for run in extract_msgs::extend_branches(&mut self.branches, &mut self.arrivals, new_msgs) {
+            self.parsed.remove(&run);
+        }
+        return Ok(count);
+    }
+
+    // The runs of the selected branches, oldest first
+    pub fn runs(&self, branches: &WhichBranch) -> Vec<usize> {
+        return match branches {
+            WhichBranch::OneBranch(b)  => self.branches.get_key_value(b).map(|(run, _)| *run).into_iter().collect(),
+            WhichBranch::FromBranch(b) => self.branches.range(*b..).map(|(run, _)| *run).collect(),
+            WhichBranch::LastBranch    => self.branches.keys().next_back().cloned().into_iter().collect(),
+        };
+    }
+
+    // The messages and pubkeys of the selected branches, like extract_msgs::extract_msg
+    pub fn branches(&self, branches: &WhichBranch) -> Vec<Vec<(String, String)>> {
+        return self.runs(branches)
+            .iter()
+            .map(|run| self.branches[run].clone())
+            .collect();
+    }
+
+    pub fn branch(&self, run: usize) -> Option<&Vec<(String, String)>> {
+        return self.branches.get(&run);
+    }
+
+    // When each message of the selected branches reached the channel, in the order of the branches
+    pub fn arrivals(&self, branches: &WhichBranch) -> Vec<Vec<u32>> {
+        return self.runs(branches)
+            .iter()
+            .map(|run| self.arrivals[run].clone())
+            .collect();
+    }
+
+    // The parsed messages of the interaction of the run, if its branch has been read
+    pub fn interaction(&mut self, run: usize) -> Result<Option<&Vec<MessageAndPubkey>>> {
+        if !self.parsed.contains_key(&run) {
+            let branch = match self.branches.get(&run) {
+                Some(branch) => branch,
+                None => return Ok(None)
+            };
+            let parsed_msgs = parse_messages::parse_messages(branch)?;
+            self.parsed.insert(run, parsed_msgs);
+        }
+        return Ok(self.parsed.get(&run));
+    }
+
+    // The parsed messages of the selected branches, oldest first
+    pub fn interactions(&mut self, branches: &WhichBranch) -> Result<Vec<Vec<MessageAndPubkey>>> {
+        let mut interactions: Vec<Vec<MessageAndPubkey>> = Vec::new();
+        for run in self.runs(branches) {
+            if let Some(parsed_msgs) = self.interaction(run)? {
+                interactions.push(parsed_msgs.clone());
+            }
+        }
+        return Ok(interactions);
+    }
+}
@@ -0,0 +1,11 @@
+// Parses a line of the score files the simulation writes, of the form "<pubkey>: <score>".
+// Empty lines give None.
+pub fn get_line_info(line: &str) -> Option<(String, f32)> {
+    if line == "" {
+        return None;
+    }
+    let mut split = line.split(": ");
+    let str = split.next().unwrap();
+    let rel: f32 = split.next().unwrap().parse().unwrap();
+    return Some((String::from(str), rel));
+}
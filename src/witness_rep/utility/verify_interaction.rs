@@ -1,5 +1,10 @@
 use crate::witness_rep::{
-    utility::{extract_msgs}
+    utility::{
+        extract_msgs,
+        batch_verify::{PendingSignature, SignatureVerifier, verify_one}
+    },
+    iota_did::did_registry::{DidResolver, resolve_signer_key},
+    trust_score_generators::statement_rules::{StatementReport, check_statements}
 };
 
 use wb_reputation_system::{
@@ -9,7 +14,8 @@ use wb_reputation_system::{
             event_protocol_messages::{ApplicationMsg, Contract},
             event_protocol_messages as message,
             signatures::{
-                witness_sig, interaction_sig
+                witness_sig, interaction_sig,
+                organization_cert::OrganizationCertificate
             },
         }
     },
@@ -19,10 +25,7 @@ use iota_streams::{
     app_channels::api::tangle::UnwrappedMessage,
     core::{println, Result},
 };
-use identity::{
-    crypto::{Ed25519, Verify},
-    did::MethodData,
-};
+use identity::did::MethodData;
 
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -32,6 +35,9 @@ pub enum PublickeyOwner {
     Witness(String)
 }
 
+/// A branch holds the messages of one interaction, and is indexed by
+/// the run in which the interaction took place
+#[derive(Clone, Debug)]
 pub enum WhichBranch {
     /// Verifies a specific branch
     OneBranch(usize),
@@ -43,27 +49,65 @@ pub enum WhichBranch {
 
 /// Returns whether the interaction's msgs were valid, the messages
 /// and the channel pks which signed the msgs. Depending on the application
-/// the contract format differs as well as the possible messages
+/// the contract format differs as well as the possible messages. When
+/// verifying past branches, check_timeouts should be false as their
+/// signatures are likely to have timed out since. If a resolver is given,
+/// signatures are only valid if the signer's key belongs to a registered
+/// DID document; otherwise the key the signer presents is trusted. The
+/// verifier remembers the signatures it has found valid.
 pub async fn verify_interaction(
     msgs: Vec<UnwrappedMessage>,
     branches: WhichBranch,
-    application: String
+    application: String,
+    check_timeouts: bool,
+    resolver: Option<&dyn DidResolver>,
+    verifier: &mut SignatureVerifier
 ) -> Result<(bool, Vec<String>, Vec<String>)> {
     
-    let msgs = extract_msgs::extract_msg(msgs, branches);
-/*     let msgs: Vec<(String, String)> = branches_msgs.into_iter()
+    let (messages, arrivals) = extract_msgs::extract_branches(msgs);
+    let branches_msgs = extract_msgs::select_branches(messages, &branches);
+    let branches_arrivals = extract_msgs::select_branches(arrivals, &branches);
+    let msgs: Vec<(String, String)> = branches_msgs.clone().into_iter()
         .flatten()
-        .collect(); */
+        .collect();
 
     let only_msgs = msgs.iter().map(|(msg, _)| msg.clone()).collect();
     let only_pks = msgs.iter().map(|(_, pk)| pk.clone()).collect();
 
+    // each branch is a separate interaction, so is verified independently
+    for (branch, arrivals) in branches_msgs.iter().zip(branches_arrivals.iter()) {
+        if !verify_branch(branch, arrivals, application.clone(), check_timeouts, resolver, verifier)? {
+            return Ok((false, only_msgs, only_pks));
+        }
+    }
+
+    return Ok((true, only_msgs, only_pks));
+}
+
+/// Verifies the messages of a single interaction, in the order they were sent, given
+/// when each of them reached the channel (see extract_msgs::extract_branches)
+pub fn verify_branch(
+    msgs: &Vec<(String, String)>,
+    arrivals: &Vec<u32>,
+    application: String,
+    check_timeouts: bool,
+    resolver: Option<&dyn DidResolver>,
+    verifier: &mut SignatureVerifier
+) -> Result<bool> {
     // parse the string into the InteractionMsg/WitnessStatement/CompensationMsg
     // format and check if valid
     let mut valid_pks: Vec<PublickeyOwner> = Vec::new();
     for (cur_msg, pk) in msgs.iter() {
-        let deserialised_msg: message::Message = serde_json::from_str(cur_msg.as_str())?;
-        let verified = verify_msg((deserialised_msg,pk), valid_pks.clone(), application.clone())?;
+        // a message which cannot be parsed has been tampered with
+        let deserialised_msg: message::Message = match serde_json::from_str(cur_msg.as_str()) {
+            Ok(m)   => m,
+            Err(_)  => return Ok(false)
+        };
+        // similarly, keys or signatures which cannot be decoded are invalid
+        let verified = match verify_msg((deserialised_msg,pk), valid_pks.clone(), application.clone(), check_timeouts, resolver, verifier) {
+            Ok(v)   => v,
+            Err(_)  => return Ok(false)
+        };
 
         let final_verify = match verified {
             (true, Some(ret_pk))=> {
@@ -78,20 +122,52 @@ pub async fn verify_interaction(
 
         println!("Verified status of msg: {}", final_verify);
         if !final_verify {
-            return Ok((false, only_msgs, only_pks));
+            return Ok(false);
         }
     }
 
-    return Ok((true, only_msgs, only_pks));
+    // late, repeated and contradicting statements are valid messages, but are flagged to the reader
+    let report = check_branch_statements(msgs, arrivals);
+    if report.breached() {
+        println!(
+            "Statements breaking the rules: late from {:?}, repeated by {:?}, contradicted by {:?}",
+            report.late, report.duplicates, report.equivocators
+        );
+    }
+
+    return Ok(true);
+}
+
+/// Applies the rules on witness statements to the messages of a single interaction.
+/// Late, repeated and contradicting statements are still signed by a witness of the
+/// interaction, so they do not fail verify_branch; they are reported here instead,
+/// and the TSGs leave them out. Each message may come with the time it reached the
+/// channel, without which no statement is late. Messages which cannot be parsed are
+/// skipped, as verify_branch already rejects them.
+pub fn check_branch_statements(msgs: &Vec<(String, String)>, arrivals: &Vec<u32>) -> StatementReport {
+    let parsed: Vec<(&str, message::Message, Option<u32>)> = msgs
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (msg, pk))| {
+            serde_json::from_str(msg.as_str()).ok().map(|m| (pk.as_str(), m, arrivals.get(i).cloned()))
+        })
+        .collect();
+    return check_statements(parsed.iter().map(|(pk, msg, arrival)| (*pk, msg, *arrival)));
 }
 
 /// Accepts a tuple of a message content and the sender's channel public key.
 /// If it is a valid InteractionMsg, it will return true and a valid channel
-/// public keys and it's ownership
+/// public keys and it's ownership. A WitnessStatement is valid whenever it was
+/// sent by a witness of the interaction, even if it repeats an earlier one:
+/// rejecting it would let a single witness invalidate the whole interaction by
+/// stating twice. verify_branch flags such statements instead.
 pub fn verify_msg( 
     (tx_msg,channel_pk) : (message::Message, &String), 
     mut valid_pks: Vec<PublickeyOwner>,
-    application: String
+    application: String,
+    check_timeouts: bool,
+    resolver: Option<&dyn DidResolver>,
+    verifier: &mut SignatureVerifier
 ) -> Result<(bool, Option<Vec<PublickeyOwner>>)> {
     match tx_msg {
         message::Message::InteractionMsg {
@@ -110,18 +186,23 @@ pub fn verify_msg(
                 _ => panic!("Interaction signature verification failed because could not recognize the application")
             }
 
-            // store the witness sigs to check the interaction sigs
+            // store the witness sigs to check the interaction sigs, and every signature
+            // of the message, to be verified together once everything else is checked
             let mut witness_sigs: Vec<Vec<u8>> = Vec::new();
+            let mut pending: Vec<PendingSignature> = Vec::new();
+            let mut signer_pks: Vec<PublickeyOwner> = Vec::new();
 
             // Check that each witness sig is valid, meaning it was sent by the owner of the DID,
             // not just any person who holds the public key of the DID
             for ws in wit_sigs.iter() {
-                let (verified, pk, sig) = verify_witness_sig(ws.clone(), Some(contract.clone()))?;
-                if !verified {
-                    panic!("Interaction signature verification failed")
-                } else {
-                    valid_pks.push(PublickeyOwner::Witness(pk));
-                    witness_sigs.push(sig);
+                let (signature, pk, sig) = prepare_witness_sig(ws.clone(), Some(&contract), check_timeouts, resolver)?;
+                match signature {
+                    None => return Ok((false, None)),
+                    Some(signature) => {
+                        pending.push(signature);
+                        signer_pks.push(PublickeyOwner::Witness(pk));
+                        witness_sigs.push(sig);
+                    }
                 }
             }
 
@@ -133,13 +214,21 @@ pub fn verify_msg(
             // agreeing to have a witness witness the event.
             witness_sigs.sort();
             for ts in tn_sigs.iter() {
-                let (verified, pk) = verify_interaction_sig(ts.clone(), Some(contract.clone()), witness_sigs.clone())?;
-                if !verified {
-                    panic!("Witness signature verification failed")
-                } else {
-                    valid_pks.push(PublickeyOwner::Participant(pk));
+                let (signature, pk) = prepare_interaction_sig(ts.clone(), Some(&contract), &witness_sigs, check_timeouts, resolver)?;
+                match signature {
+                    None => return Ok((false, None)),
+                    Some(signature) => {
+                        pending.push(signature);
+                        signer_pks.push(PublickeyOwner::Participant(pk));
+                    }
                 }
             }
+
+            // the message is only valid if every one of its signatures is
+            if verifier.verify_batch(&pending).contains(&false) {
+                return Ok((false, None));
+            }
+            valid_pks.append(&mut signer_pks);
             return Ok((true, Some(valid_pks)))
         },
         message::Message::WitnessStatement {
@@ -193,7 +282,26 @@ pub fn get_sigs(
 pub fn verify_witness_sig(
     sig: witness_sig::WitnessSig,
     interaction_contract: Option<Contract>,
+    check_timeouts: bool,
+    resolver: Option<&dyn DidResolver>
 ) -> Result<(bool, String, Vec<u8>)>{
+    let (signature, pk, sig) = prepare_witness_sig(sig, interaction_contract.as_ref(), check_timeouts, resolver)?;
+    let verified = match signature {
+        Some(signature) => verify_one(&signature),
+        None            => false
+    };
+    return Ok((verified, pk, sig));
+}
+
+/// Checks everything about a witness sig but the signature itself, which is returned
+/// to be verified (see batch_verify.rs), or None if the other checks fail. Also returns
+/// the channel pubkey of the signer, and the sig bytes.
+pub fn prepare_witness_sig(
+    sig: witness_sig::WitnessSig,
+    interaction_contract: Option<&Contract>,
+    check_timeouts: bool,
+    resolver: Option<&dyn DidResolver>
+) -> Result<(Option<PendingSignature>, String, Vec<u8>)>{
     match sig {
         witness_sig::WitnessSig {
             contract,
@@ -203,14 +311,12 @@ pub fn verify_witness_sig(
             signer_did_pubkey,
             signature,
         } => {
-            let pre_sig = witness_sig::WitnessPreSig {
-                contract: contract.clone(),
-                signer_channel_pubkey: signer_channel_pubkey.clone(),
-                org_cert: org_cert.clone(),
-                timeout,
-            };
-
-            let pre_sig = serde_json::to_string(&pre_sig).unwrap();
+            // check the contract
+            if let Some(c) = interaction_contract {
+                if c != &contract {
+                    return Ok((None,signer_channel_pubkey,signature));
+                }
+            }
 
             // verify the timeout
             let start = SystemTime::now();
@@ -218,28 +324,30 @@ pub fn verify_witness_sig(
                 .duration_since(UNIX_EPOCH)
                 .expect("Time went backwards");
 
-            if timeout < current_time.as_secs() as u32 {
-                panic!("Signature verification failed")
+            if check_timeouts && timeout < current_time.as_secs() as u32 {
+                return Ok((None,signer_channel_pubkey,signature));
             }
 
-            // verify the digital signature
-            let signer_did_pubkey = MethodData::PublicKeyMultibase(signer_did_pubkey);
-            let decoded_pubkey = MethodData::try_decode(&signer_did_pubkey)?;
-            let sig_unsigned = Ed25519::verify(pre_sig.as_bytes(), &signature, &decoded_pubkey);
-            
-            if let Ok(()) = sig_unsigned {
-                // check the contract
-                match interaction_contract {
-                    Some(c) => {
-                        if c == contract {
-                            return Ok((true,signer_channel_pubkey,signature));
-                        }
-                    },
-                    None => return Ok((true,signer_channel_pubkey,signature))
-                }
-                
-            }
-            panic!("Signature verification failed")
+            let pre_sig = witness_sig::WitnessPreSig {
+                contract: contract,
+                signer_channel_pubkey: signer_channel_pubkey.clone(),
+                org_cert: org_cert.clone(),
+                timeout,
+            };
+
+            let pre_sig = serde_json::to_string(&pre_sig).unwrap();
+
+            // the digital signature is verified against the signer's DID key
+            let decoded_pubkey = match get_signer_key(signer_did_pubkey, &org_cert, resolver)? {
+                Some(key)   => key,
+                None        => return Ok((None,signer_channel_pubkey,signature))
+            };
+            let pending = PendingSignature {
+                key: decoded_pubkey,
+                message: pre_sig.into_bytes(),
+                signature: signature.clone()
+            };
+            return Ok((Some(pending),signer_channel_pubkey,signature));
         }
     }
 }
@@ -248,8 +356,29 @@ pub fn verify_witness_sig(
 pub fn verify_interaction_sig(
     sig: interaction_sig::InteractionSig,
     interaction_contract: Option<Contract>,
-    sorted_witness_sigs: Vec<Vec<u8>>
+    sorted_witness_sigs: Vec<Vec<u8>>,
+    check_timeouts: bool,
+    resolver: Option<&dyn DidResolver>
 )-> Result<(bool, String)>{
+    let (signature, pk) = prepare_interaction_sig(
+        sig, interaction_contract.as_ref(), &sorted_witness_sigs, check_timeouts, resolver
+    )?;
+    let verified = match signature {
+        Some(signature) => verify_one(&signature),
+        None            => false
+    };
+    return Ok((verified, pk));
+}
+
+// Like prepare_witness_sig, checks everything about a participant sig but the signature
+// itself, which is returned to be verified, and returns the channel pubkey of the signer
+pub fn prepare_interaction_sig(
+    sig: interaction_sig::InteractionSig,
+    interaction_contract: Option<&Contract>,
+    sorted_witness_sigs: &Vec<Vec<u8>>,
+    check_timeouts: bool,
+    resolver: Option<&dyn DidResolver>
+)-> Result<(Option<PendingSignature>, String)>{
     match sig {
         interaction_sig::InteractionSig {
             contract,
@@ -261,6 +390,12 @@ pub fn verify_interaction_sig(
             signer_did_pubkey,
             signature,
         } => {
+            // check the contract
+            if let Some(c) = interaction_contract {
+                if c != &contract {
+                    return Ok((None,signer_channel_pubkey));
+                }
+            }
 
             // Ensure that the participant sigs reference the exact same list of witnesses
             // as the tx_msg. We can do this quickly by sorting both lists, and checking for
@@ -274,18 +409,19 @@ pub fn verify_interaction_sig(
                 .duration_since(UNIX_EPOCH)
                 .expect("Time went backwards");
 
-            if timeout < current_time.as_secs() as u32 {
-                panic!("Signature verification failed")
+            if check_timeouts && timeout < current_time.as_secs() as u32 {
+                return Ok((None,signer_channel_pubkey));
             }
 
-            // verify the uniformity of the wwitness sigs
-            if sorted_wn_sigs_to_check != sorted_witness_sigs {
-                panic!("The witnesses linked in the participant sig differ from those in the transaction message")
+            // verify the uniformity of the wwitness sigs (a participant sig that links
+            // different witnesses to those in the transaction message is invalid)
+            if &sorted_wn_sigs_to_check != sorted_witness_sigs {
+                return Ok((None,signer_channel_pubkey));
             }
 
 
             let pre_sig = interaction_sig::InteractionPreSig {
-                contract: contract.clone(),
+                contract: contract,
                 signer_channel_pubkey: signer_channel_pubkey.clone(),
                 witnesses,
                 wit_node_sigs,
@@ -295,26 +431,39 @@ pub fn verify_interaction_sig(
 
             let pre_sig = serde_json::to_string(&pre_sig).unwrap();
 
-            let signer_did_pubkey = MethodData::PublicKeyMultibase(signer_did_pubkey);
-            let decoded_pubkey = MethodData::try_decode(&signer_did_pubkey)?;
-            let sig_unsigned = Ed25519::verify(pre_sig.as_bytes(), &signature, &decoded_pubkey);
-            if let Ok(()) = sig_unsigned {
-                match interaction_contract {
-                    Some(c) => {
-                        if c == contract {
-                            return Ok((true,signer_channel_pubkey));
-                        }
-                    },
-                    None => return Ok((true,signer_channel_pubkey))
-                }
-                
-            }
-            panic!("Signature verification failed")
+            let decoded_pubkey = match get_signer_key(signer_did_pubkey, &org_cert, resolver)? {
+                Some(key)   => key,
+                None        => return Ok((None,signer_channel_pubkey))
+            };
+            let pending = PendingSignature {
+                key: decoded_pubkey,
+                message: pre_sig.into_bytes(),
+                signature: signature
+            };
+            return Ok((Some(pending),signer_channel_pubkey));
 
         }
     }
 }
 
+/// Returns the key bytes to verify a signature against. With a resolver, these are the
+/// key of the DID document the signer's organization certificate is bound to, or None
+/// if it is not registered (see resolve_signer_key). Without one, the key the signer
+/// presented is trusted.
+pub fn get_signer_key(
+    signer_did_pubkey: String,
+    org_cert: &OrganizationCertificate,
+    resolver: Option<&dyn DidResolver>
+) -> Result<Option<Vec<u8>>> {
+    match resolver {
+        Some(r) => return resolve_signer_key(r, org_cert),
+        None    => {
+            let signer_did_pubkey = MethodData::PublicKeyMultibase(signer_did_pubkey);
+            return Ok(Some(MethodData::try_decode(&signer_did_pubkey)?));
+        }
+    }
+}
+
 /* pub fn testing() -> Result<()> {
     let tx = message::Message::WitnessStatement {
         outcome: true